@@ -0,0 +1,273 @@
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::rc::Rc;
+
+use crate::grammar::Grammar;
+
+// --- BOUNDED AMBIGUITY CHECKER ---
+// Builds, for every length up to `max_length`, the set of terminal strings each
+// nonterminal derives together with (at most two) distinct parse trees. The
+// first sentence of the start symbol with two trees is a concrete witness.
+
+pub const DEFAULT_MAX_LENGTH: usize = 6;
+
+/// Longest `max_length` a request may ask for; tables grow exponentially with it.
+pub const MAX_LENGTH_LIMIT: usize = 16;
+
+/// Upper bound on (nonterminal, sentence) entries before the search gives up.
+const ENTRY_BUDGET: usize = 200_000;
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct ParseTree {
+    pub symbol: String,
+    /// Index into `Grammar::productions`; `None` for terminal leaves.
+    pub production: Option<usize>,
+    pub children: Vec<ParseTree>,
+}
+
+impl ParseTree {
+    /// Sentential forms of the leftmost derivation described by this tree.
+    pub fn leftmost_derivation(&self) -> Vec<String> {
+        let mut form: Vec<&ParseTree> = vec![self];
+        let mut steps = vec![render_form(&form)];
+        while let Some(i) = form.iter().position(|t| t.production.is_some()) {
+            let node = form[i];
+            form.splice(i..i + 1, node.children.iter());
+            steps.push(render_form(&form));
+        }
+        steps
+    }
+}
+
+fn render_form(form: &[&ParseTree]) -> String {
+    if form.is_empty() {
+        "ε".to_string()
+    } else {
+        form.iter().map(|t| t.symbol.as_str()).collect::<Vec<_>>().join(" ")
+    }
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct Derivation {
+    pub tree: ParseTree,
+    pub steps: Vec<String>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct AmbiguityReport {
+    pub ambiguous: bool,
+    pub max_length: usize,
+    /// Longest sentence length that was fully explored.
+    pub searched_length: usize,
+    pub truncated: bool,
+    pub sentence: Option<Vec<String>>,
+    pub derivations: Vec<Derivation>,
+    pub message: String,
+}
+
+#[derive(PartialEq, Debug)]
+enum Node {
+    Leaf(String),
+    Inner { lhs: String, production: usize, children: Vec<Rc<Node>> },
+}
+
+impl Node {
+    fn to_tree(&self) -> ParseTree {
+        match self {
+            Node::Leaf(s) => ParseTree { symbol: s.clone(), production: None, children: Vec::new() },
+            Node::Inner { lhs, production, children } => ParseTree {
+                symbol: lhs.clone(),
+                production: Some(*production),
+                children: children.iter().map(|c| c.to_tree()).collect(),
+            },
+        }
+    }
+}
+
+type Table = BTreeMap<String, BTreeMap<Vec<String>, Vec<Rc<Node>>>>;
+
+pub fn check_ambiguity(grammar: &Grammar, max_length: usize) -> AmbiguityReport {
+    let min_len = min_lengths(grammar);
+    let mut tables: Vec<Table> = Vec::new();
+    let mut entries = 0usize;
+
+    for len in 0..=max_length {
+        tables.push(Table::new());
+        // Set when the budget runs out before this length's fixpoint is reached.
+        let mut cut_short = false;
+        'fixpoint: loop {
+            let mut changed = false;
+            for (pi, p) in grammar.productions.iter().enumerate() {
+                let mut found = Vec::new();
+                let mut search = Search { grammar, tables: &tables, min_len: &min_len, found: &mut found };
+                search.combine(&p.rhs, 0, len, &mut Vec::new(), &mut Vec::new());
+                let overflow = found.len() > ENTRY_BUDGET;
+
+                for (sentence, children) in found {
+                    let trees = tables[len].entry(p.lhs.clone()).or_default().entry(sentence).or_default();
+                    if trees.len() >= 2 {
+                        continue;
+                    }
+                    let node = Rc::new(Node::Inner { lhs: p.lhs.clone(), production: pi, children });
+                    if !trees.contains(&node) {
+                        if trees.is_empty() {
+                            entries += 1;
+                        }
+                        trees.push(node);
+                        changed = true;
+                    }
+                }
+                if overflow || entries > ENTRY_BUDGET {
+                    cut_short = true;
+                    break 'fixpoint;
+                }
+            }
+            if !changed {
+                break;
+            }
+        }
+
+        if let Some(sentences) = tables[len].get(&grammar.start) {
+            if let Some((sentence, trees)) = sentences.iter().find(|(_, t)| t.len() >= 2) {
+                let shown = if sentence.is_empty() { "ε".to_string() } else { sentence.join(" ") };
+                return AmbiguityReport {
+                    ambiguous: true,
+                    max_length,
+                    searched_length: len,
+                    truncated: false,
+                    sentence: Some(sentence.clone()),
+                    derivations: trees
+                        .iter()
+                        .map(|t| {
+                            let tree = t.to_tree();
+                            let steps = tree.leftmost_derivation();
+                            Derivation { tree, steps }
+                        })
+                        .collect(),
+                    message: format!("Ambiguous: \"{}\" has two distinct derivations from {}", shown, grammar.start),
+                };
+            }
+        }
+
+        if cut_short || entries > ENTRY_BUDGET {
+            let searched = if cut_short { len.saturating_sub(1) } else { len };
+            return AmbiguityReport {
+                ambiguous: false,
+                max_length,
+                searched_length: searched,
+                truncated: true,
+                sentence: None,
+                derivations: Vec::new(),
+                message: format!(
+                    "No ambiguity found up to length {} (search stopped early: too many sentences)",
+                    searched
+                ),
+            };
+        }
+    }
+
+    AmbiguityReport {
+        ambiguous: false,
+        max_length,
+        searched_length: max_length,
+        truncated: false,
+        sentence: None,
+        derivations: Vec::new(),
+        message: format!("No ambiguity found up to length {}", max_length),
+    }
+}
+
+/// Shortest terminal string length derivable from each nonterminal
+/// (unproductive nonterminals are absent).
+fn min_lengths(grammar: &Grammar) -> HashMap<String, usize> {
+    let mut min: HashMap<String, usize> = HashMap::new();
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for p in &grammar.productions {
+            let len = p.rhs.iter().try_fold(0usize, |acc, s| {
+                if grammar.is_terminal(s) {
+                    Some(acc + 1)
+                } else {
+                    min.get(s).map(|l| acc + l)
+                }
+            });
+            if let Some(len) = len {
+                if min.get(&p.lhs).is_none_or(|&cur| len < cur) {
+                    min.insert(p.lhs.clone(), len);
+                    changed = true;
+                }
+            }
+        }
+    }
+    min
+}
+
+struct Search<'a> {
+    grammar: &'a Grammar,
+    tables: &'a [Table],
+    min_len: &'a HashMap<String, usize>,
+    found: &'a mut Vec<(Vec<String>, Vec<Rc<Node>>)>,
+}
+
+impl Search<'_> {
+    fn symbol_min(&self, symbol: &str) -> Option<usize> {
+        if self.grammar.is_terminal(symbol) {
+            Some(1)
+        } else {
+            self.min_len.get(symbol).copied()
+        }
+    }
+
+    /// Enumerates every way `rhs[idx..]` derives a string of exactly `remaining` terminals.
+    fn combine(&mut self, rhs: &[String], idx: usize, remaining: usize, sentence: &mut Vec<String>, children: &mut Vec<Rc<Node>>) {
+        // Past the budget the caller gives up anyway; stop enumerating.
+        if self.found.len() > ENTRY_BUDGET {
+            return;
+        }
+        if idx == rhs.len() {
+            if remaining == 0 {
+                self.found.push((sentence.clone(), children.clone()));
+            }
+            return;
+        }
+        let mut rest_min = 0;
+        for s in &rhs[idx + 1..] {
+            match self.symbol_min(s) {
+                Some(l) => rest_min += l,
+                None => return,
+            }
+        }
+        if rest_min > remaining {
+            return;
+        }
+
+        let symbol = &rhs[idx];
+        if self.grammar.is_terminal(symbol) {
+            if remaining - rest_min >= 1 {
+                sentence.push(symbol.clone());
+                children.push(Rc::new(Node::Leaf(symbol.clone())));
+                self.combine(rhs, idx + 1, remaining - 1, sentence, children);
+                sentence.pop();
+                children.pop();
+            }
+            return;
+        }
+
+        let Some(lo) = self.min_len.get(symbol).copied() else { return };
+        let tables = self.tables;
+        for len in lo..=(remaining - rest_min) {
+            let Some(sentences) = tables.get(len).and_then(|t| t.get(symbol)) else { continue };
+            for (s, trees) in sentences {
+                for tree in trees {
+                    let mark = sentence.len();
+                    sentence.extend(s.iter().cloned());
+                    children.push(Rc::clone(tree));
+                    self.combine(rhs, idx + 1, remaining - len, sentence, children);
+                    sentence.truncate(mark);
+                    children.pop();
+                }
+            }
+        }
+    }
+}
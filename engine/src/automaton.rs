@@ -0,0 +1,660 @@
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+
+use crate::regex::{char_after, flex_definitions, parse_flex_pattern, Pattern, Regex};
+use crate::ASTNode;
+
+// --- FLEX AUTOMATA ---
+// Thompson NFA for the whole rule set, subset construction into a DFA over
+// character classes, then Hopcroft minimization. Each DFA state accepts the
+// lowest-numbered rule among its NFA states, which together with a scanner
+// that remembers the last accepting position gives flex's longest-match,
+// first-rule-wins semantics.
+
+/// Guards against patterns like `a{1000}{1000}` exhausting memory.
+pub(crate) const MAX_NFA_STATES: usize = 200_000;
+const MAX_DFA_STATES: usize = 20_000;
+
+#[derive(Serialize, Clone, Debug)]
+pub struct StartCondition {
+    pub name: String,
+    pub exclusive: bool,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct AutomatonRule {
+    /// Position of the rule in the FlexFile.
+    pub index: usize,
+    pub pattern: String,
+    /// Where the pattern starts in the source.
+    pub line: usize,
+    pub column: usize,
+    pub action: String,
+    /// Where the action's code starts in the source.
+    pub action_line: usize,
+    pub parsed: Pattern,
+    /// Head and trailing-context matchers, compiled once for `head_length`.
+    #[serde(skip)]
+    context: Option<ContextSplit>,
+}
+
+#[derive(Clone, Debug)]
+struct ContextSplit {
+    head: Matcher,
+    tail: Matcher,
+}
+
+impl AutomatonRule {
+    pub fn active_in(&self, condition: &StartCondition) -> bool {
+        let scs = &self.parsed.start_conditions;
+        if scs.is_empty() {
+            !condition.exclusive
+        } else {
+            scs.iter().any(|s| s == "*" || *s == condition.name)
+        }
+    }
+
+    /// The regex the scanner actually matches: pattern plus trailing context.
+    pub fn full_regex(&self) -> Regex {
+        match &self.parsed.trailing {
+            Some(t) => Regex::Concat { items: vec![self.parsed.regex.clone(), t.clone()] },
+            None => self.parsed.regex.clone(),
+        }
+    }
+
+    /// Length of the lexeme proper within `matched` (which includes any
+    /// trailing context): the longest head matching `r` with the rest matching `s`.
+    pub fn head_length(&self, matched: &[char]) -> usize {
+        let Some(split) = &self.context else { return matched.len() };
+        split.head.prefix_lengths(matched).into_iter().rev().find(|&i| split.tail.matches(&matched[i..])).unwrap_or(matched.len())
+    }
+}
+
+fn context_split(parsed: &Pattern) -> Result<Option<ContextSplit>, String> {
+    let Some(trailing) = &parsed.trailing else { return Ok(None) };
+    Ok(Some(ContextSplit { head: Matcher::new(&parsed.regex)?, tail: Matcher::new(trailing)? }))
+}
+
+/// Thompson NFA of one regex, for matching whole strings by simulation.
+#[derive(Clone, Debug)]
+pub struct Matcher {
+    nfa: Nfa,
+    start: usize,
+    end: usize,
+}
+
+impl Matcher {
+    pub fn new(re: &Regex) -> Result<Self, String> {
+        let mut builder = NfaBuilder { states: Vec::new() };
+        let (start, end) = builder.build(re)?;
+        Ok(Matcher { nfa: Nfa { states: builder.states, starts: Vec::new() }, start, end })
+    }
+
+    /// Every `i` for which `input[..i]` matches, in increasing order.
+    pub fn prefix_lengths(&self, input: &[char]) -> Vec<usize> {
+        let mut current = epsilon_closure(&self.nfa, [self.start]);
+        let mut out = Vec::new();
+        for i in 0..=input.len() {
+            if current.contains(&self.end) {
+                out.push(i);
+            }
+            let Some(&c) = input.get(i) else { break };
+            let next: Vec<usize> = current
+                .iter()
+                .flat_map(|&s| self.nfa.states[s].edges.iter())
+                .filter(|(ranges, _)| ranges.iter().any(|&(lo, hi)| lo <= c && c <= hi))
+                .map(|(_, t)| *t)
+                .collect();
+            if next.is_empty() {
+                break;
+            }
+            current = epsilon_closure(&self.nfa, next);
+        }
+        out
+    }
+
+    pub fn matches(&self, input: &[char]) -> bool {
+        self.prefix_lengths(input).last() == Some(&input.len())
+    }
+}
+
+/// Whether `re` matches all of `input` (for one-off checks; reuse a
+/// `Matcher` to match the same regex repeatedly).
+pub fn regex_matches(re: &Regex, input: &[char]) -> bool {
+    Matcher::new(re).is_ok_and(|m| m.matches(input))
+}
+
+/// A readable character from `ranges`: printable ASCII when there is one.
+pub fn representative(ranges: &[(char, char)]) -> char {
+    ranges
+        .iter()
+        .find_map(|&(lo, hi)| {
+            let lo = lo.max('!');
+            (lo <= hi.min('~')).then_some(lo)
+        })
+        .or_else(|| ranges.iter().find(|&&(lo, hi)| lo <= ' ' && ' ' <= hi).map(|_| ' '))
+        .unwrap_or(ranges[0].0)
+}
+
+/// Shortest string `re` matches in full, if it matches any.
+pub fn shortest_match(re: &Regex) -> Option<String> {
+    let mut builder = NfaBuilder { states: Vec::new() };
+    let (start, end) = builder.build(re).ok()?;
+    let states = builder.states;
+    // 0-1 BFS: epsilon moves are free, character moves cost one.
+    let mut dist = vec![usize::MAX; states.len()];
+    let mut parent: Vec<Option<(usize, Option<char>)>> = vec![None; states.len()];
+    let mut queue = VecDeque::from([start]);
+    dist[start] = 0;
+    while let Some(s) = queue.pop_front() {
+        for &t in &states[s].epsilon {
+            if dist[s] < dist[t] {
+                dist[t] = dist[s];
+                parent[t] = Some((s, None));
+                queue.push_front(t);
+            }
+        }
+        for (ranges, t) in &states[s].edges {
+            if dist[s] + 1 < dist[*t] {
+                dist[*t] = dist[s] + 1;
+                parent[*t] = Some((s, Some(representative(ranges))));
+                queue.push_back(*t);
+            }
+        }
+    }
+    if dist[end] == usize::MAX {
+        return None;
+    }
+    let mut out = Vec::new();
+    let mut cur = end;
+    while let Some((prev, c)) = parent[cur] {
+        out.extend(c);
+        cur = prev;
+    }
+    Some(out.into_iter().rev().collect())
+}
+
+/// Entry states of one start condition: `bol` is used at the beginning of a
+/// line and additionally reaches the `^`-anchored rules.
+#[derive(Serialize, Clone, Debug)]
+pub struct StartStates {
+    pub condition: String,
+    pub normal: usize,
+    pub bol: usize,
+}
+
+#[derive(Serialize, Clone, Debug, Default)]
+pub struct NfaState {
+    pub epsilon: Vec<usize>,
+    pub edges: Vec<(Vec<(char, char)>, usize)>,
+    /// Rule index (into `LexerAutomaton::rules`) accepted here.
+    pub accept: Option<usize>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct Nfa {
+    pub states: Vec<NfaState>,
+    pub starts: Vec<StartStates>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct DfaState {
+    /// Character class -> target state; missing classes go to the dead state.
+    pub transitions: BTreeMap<usize, usize>,
+    pub accept: Option<usize>,
+    pub nfa_states: Vec<usize>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct Dfa {
+    /// Disjoint char intervals covering all of Unicode; the DFA alphabet.
+    pub classes: Vec<(char, char)>,
+    pub states: Vec<DfaState>,
+    pub starts: Vec<StartStates>,
+}
+
+impl Dfa {
+    pub fn class_of(&self, c: char) -> usize {
+        match self.classes.binary_search_by(|&(lo, _)| lo.cmp(&c)) {
+            Ok(i) => i,
+            Err(i) => i - 1,
+        }
+    }
+
+    pub fn next(&self, state: usize, c: char) -> Option<usize> {
+        self.states[state].transitions.get(&self.class_of(c)).copied()
+    }
+
+    pub fn start(&self, condition: &str, bol: bool) -> Option<usize> {
+        self.starts.iter().find(|s| s.condition == condition).map(|s| if bol { s.bol } else { s.normal })
+    }
+
+    /// Longest prefix of `input` accepted from `start`: `(length, rule)`.
+    pub fn longest_match(&self, start: usize, input: &[char]) -> Option<(usize, usize)> {
+        let mut state = start;
+        let mut best = self.states[state].accept.map(|r| (0, r));
+        for (i, &c) in input.iter().enumerate() {
+            match self.next(state, c) {
+                Some(s) => state = s,
+                None => break,
+            }
+            if let Some(r) = self.states[state].accept {
+                best = Some((i + 1, r));
+            }
+        }
+        best
+    }
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct LexerAutomaton {
+    pub start_conditions: Vec<StartCondition>,
+    pub rules: Vec<AutomatonRule>,
+    pub nfa: Nfa,
+    /// Minimized DFA.
+    pub dfa: Dfa,
+}
+
+struct NfaBuilder {
+    states: Vec<NfaState>,
+}
+
+impl NfaBuilder {
+    fn add(&mut self) -> Result<usize, String> {
+        if self.states.len() >= MAX_NFA_STATES {
+            return Err("Pattern set is too large to compile".to_string());
+        }
+        self.states.push(NfaState::default());
+        Ok(self.states.len() - 1)
+    }
+
+    /// Thompson fragment for `re`: `(entry, exit)`.
+    fn build(&mut self, re: &Regex) -> Result<(usize, usize), String> {
+        let s = self.add()?;
+        match re {
+            Regex::Empty => {
+                let e = self.add()?;
+                self.states[s].epsilon.push(e);
+                Ok((s, e))
+            }
+            Regex::Literal { c } => {
+                let e = self.add()?;
+                self.states[s].edges.push((vec![(*c, *c)], e));
+                Ok((s, e))
+            }
+            Regex::Class { ranges } => {
+                let e = self.add()?;
+                if !ranges.is_empty() {
+                    self.states[s].edges.push((ranges.clone(), e));
+                }
+                Ok((s, e))
+            }
+            Regex::Concat { items } => {
+                let mut cur = s;
+                for item in items {
+                    let (is, ie) = self.build(item)?;
+                    self.states[cur].epsilon.push(is);
+                    cur = ie;
+                }
+                Ok((s, cur))
+            }
+            Regex::Alt { items } => {
+                let e = self.add()?;
+                for item in items {
+                    let (is, ie) = self.build(item)?;
+                    self.states[s].epsilon.push(is);
+                    self.states[ie].epsilon.push(e);
+                }
+                Ok((s, e))
+            }
+            Regex::Repeat { inner, min, max } => {
+                let mut cur = s;
+                for _ in 0..*min {
+                    let (is, ie) = self.build(inner)?;
+                    self.states[cur].epsilon.push(is);
+                    cur = ie;
+                }
+                let e = self.add()?;
+                match max {
+                    None => {
+                        let (is, ie) = self.build(inner)?;
+                        self.states[cur].epsilon.push(is);
+                        self.states[cur].epsilon.push(e);
+                        self.states[ie].epsilon.push(is);
+                        self.states[ie].epsilon.push(e);
+                    }
+                    Some(max) => {
+                        for _ in *min..*max {
+                            let (is, ie) = self.build(inner)?;
+                            self.states[cur].epsilon.push(is);
+                            self.states[cur].epsilon.push(e);
+                            cur = ie;
+                        }
+                        self.states[cur].epsilon.push(e);
+                    }
+                }
+                Ok((s, e))
+            }
+        }
+    }
+}
+
+/// Start conditions declared by a FlexFile, INITIAL first.
+pub fn start_conditions(flex: &ASTNode) -> Vec<StartCondition> {
+    let mut out = vec![StartCondition { name: "INITIAL".to_string(), exclusive: false }];
+    if let ASTNode::FlexFile { definitions, .. } = flex {
+        for d in definitions {
+            if let ASTNode::FlexStartConditionDecl { names, exclusive } = d {
+                for n in names {
+                    if !out.iter().any(|sc| sc.name == *n) {
+                        out.push(StartCondition { name: n.clone(), exclusive: *exclusive });
+                    }
+                }
+            }
+        }
+    }
+    out
+}
+
+/// Parses every rule of a FlexFile (with definitions expanded).
+pub fn compile_rules(flex: &ASTNode) -> Result<Vec<AutomatonRule>, String> {
+    let ASTNode::FlexFile { rules, .. } = flex else {
+        return Err("Expected a Flex specification".to_string());
+    };
+    let definitions = flex_definitions(flex);
+    let mut out = Vec::new();
+    for (index, rule) in rules.iter().enumerate() {
+        match rule {
+            ASTNode::FlexRule { pattern, action, line, column, action_line } => {
+                let parsed = parse_flex_pattern(pattern, &definitions).map_err(|e| format!("Rule {}: {}", index + 1, e))?;
+                let context = context_split(&parsed).map_err(|e| format!("Rule {}: {}", index + 1, e))?;
+                out.push(AutomatonRule { index, pattern: pattern.clone(), line: *line, column: *column, action: action.clone(), action_line: *action_line, parsed, context });
+            }
+            ASTNode::Error { message, line, column } => {
+                return Err(format!("{} at line {}, column {}", message, line, column));
+            }
+            _ => {}
+        }
+    }
+    Ok(out)
+}
+
+pub fn build_nfa(rules: &[AutomatonRule], conditions: &[StartCondition]) -> Result<Nfa, String> {
+    let mut builder = NfaBuilder { states: Vec::new() };
+    // (entry state, rule position, bol-only)
+    let mut entries = Vec::new();
+    for (ri, rule) in rules.iter().enumerate() {
+        if rule.parsed.eof {
+            continue;
+        }
+        let (s, e) = builder.build(&rule.full_regex())?;
+        builder.states[e].accept = Some(ri);
+        entries.push((s, ri, rule.parsed.bol));
+    }
+
+    let mut starts = Vec::new();
+    for sc in conditions {
+        let normal = builder.add()?;
+        let bol = builder.add()?;
+        for &(s, ri, bol_only) in &entries {
+            if !rules[ri].active_in(sc) {
+                continue;
+            }
+            if !bol_only {
+                builder.states[normal].epsilon.push(s);
+            }
+            builder.states[bol].epsilon.push(s);
+        }
+        starts.push(StartStates { condition: sc.name.clone(), normal, bol });
+    }
+    Ok(Nfa { states: builder.states, starts })
+}
+
+fn epsilon_closure(nfa: &Nfa, seeds: impl IntoIterator<Item = usize>) -> Vec<usize> {
+    let mut seen = BTreeSet::new();
+    let mut stack: Vec<usize> = seeds.into_iter().collect();
+    while let Some(s) = stack.pop() {
+        if seen.insert(s) {
+            stack.extend(nfa.states[s].epsilon.iter().copied());
+        }
+    }
+    seen.into_iter().collect()
+}
+
+/// Splits Unicode into the coarsest intervals that no NFA edge distinguishes.
+fn character_classes(nfa: &Nfa) -> Vec<(char, char)> {
+    let mut bounds: BTreeSet<char> = BTreeSet::from(['\0']);
+    for st in &nfa.states {
+        for (ranges, _) in &st.edges {
+            for &(lo, hi) in ranges {
+                bounds.insert(lo);
+                if let Some(next) = char_after(hi) {
+                    bounds.insert(next);
+                }
+            }
+        }
+    }
+    let bounds: Vec<char> = bounds.into_iter().collect();
+    let mut classes = Vec::new();
+    for (i, &lo) in bounds.iter().enumerate() {
+        let hi = match bounds.get(i + 1) {
+            Some(&next) => crate::regex::char_before(next).unwrap(),
+            None => '\u{10FFFF}',
+        };
+        classes.push((lo, hi));
+    }
+    classes
+}
+
+pub fn subset_construction(nfa: &Nfa) -> Result<Dfa, String> {
+    let classes = character_classes(nfa);
+    let class_index = |c: char| match classes.binary_search_by(|&(lo, _)| lo.cmp(&c)) {
+        Ok(i) => i,
+        Err(i) => i - 1,
+    };
+    // Class ids covered by each edge, computed once.
+    let edge_classes: Vec<Vec<Vec<usize>>> = nfa
+        .states
+        .iter()
+        .map(|st| {
+            st.edges
+                .iter()
+                .map(|(ranges, _)| ranges.iter().flat_map(|&(lo, hi)| class_index(lo)..=class_index(hi)).collect())
+                .collect()
+        })
+        .collect();
+
+    let mut ids: BTreeMap<Vec<usize>, usize> = BTreeMap::new();
+    let mut states: Vec<DfaState> = Vec::new();
+    let mut queue = VecDeque::new();
+    let mut intern = |set: Vec<usize>, states: &mut Vec<DfaState>, queue: &mut VecDeque<usize>| -> Result<usize, String> {
+        if let Some(&id) = ids.get(&set) {
+            return Ok(id);
+        }
+        if states.len() >= MAX_DFA_STATES {
+            return Err("DFA is too large (more than 20000 states)".to_string());
+        }
+        let accept = set.iter().filter_map(|&s| nfa.states[s].accept).min();
+        let id = states.len();
+        states.push(DfaState { transitions: BTreeMap::new(), accept, nfa_states: set.clone() });
+        ids.insert(set, id);
+        queue.push_back(id);
+        Ok(id)
+    };
+
+    let mut starts = Vec::new();
+    for st in &nfa.starts {
+        let normal = intern(epsilon_closure(nfa, [st.normal]), &mut states, &mut queue)?;
+        let bol = intern(epsilon_closure(nfa, [st.bol]), &mut states, &mut queue)?;
+        starts.push(StartStates { condition: st.condition.clone(), normal, bol });
+    }
+
+    while let Some(id) = queue.pop_front() {
+        let mut moves: BTreeMap<usize, BTreeSet<usize>> = BTreeMap::new();
+        for &s in &states[id].nfa_states {
+            for (ei, (_, target)) in nfa.states[s].edges.iter().enumerate() {
+                for &c in &edge_classes[s][ei] {
+                    moves.entry(c).or_default().insert(*target);
+                }
+            }
+        }
+        for (c, targets) in moves {
+            let next = intern(epsilon_closure(nfa, targets), &mut states, &mut queue)?;
+            states[id].transitions.insert(c, next);
+        }
+    }
+    Ok(Dfa { classes, states, starts })
+}
+
+/// Hopcroft's partition refinement. States are first split by the rule they
+/// accept, so rule priorities survive minimization.
+pub fn minimize(dfa: &Dfa) -> Dfa {
+    let n = dfa.states.len() + 1;
+    let dead = n - 1;
+
+    // Incoming `(class, from)` edges of each state. Missing transitions go
+    // to the dead state and are never listed: a splitter holding it is
+    // replaced by its complement, which splits every block the same way.
+    let mut incoming: Vec<Vec<(usize, usize)>> = vec![Vec::new(); n];
+    for (s, st) in dfa.states.iter().enumerate() {
+        for (&c, &t) in &st.transitions {
+            incoming[t].push((c, s));
+        }
+    }
+
+    let mut initial: BTreeMap<Option<usize>, Vec<usize>> = BTreeMap::new();
+    for s in 0..n {
+        let accept = if s == dead { None } else { dfa.states[s].accept };
+        initial.entry(accept).or_default().push(s);
+    }
+    let mut blocks: Vec<Vec<usize>> = initial.into_values().collect();
+    let mut block_of = vec![0; n];
+    for (b, members) in blocks.iter().enumerate() {
+        for &s in members {
+            block_of[s] = b;
+        }
+    }
+    let mut work: Vec<usize> = (0..blocks.len()).collect();
+    let mut in_work = vec![true; blocks.len()];
+
+    while let Some(a) = work.pop() {
+        in_work[a] = false;
+        let splitter: Vec<usize> = if block_of[dead] == a {
+            (0..n).filter(|&s| block_of[s] != a).collect()
+        } else {
+            blocks[a].clone()
+        };
+        let mut edges: Vec<(usize, usize)> = splitter.iter().flat_map(|&t| incoming[t].iter().copied()).collect();
+        edges.sort_unstable();
+        for class_edges in edges.chunk_by(|x, y| x.0 == y.0) {
+            let mut touched: BTreeMap<usize, Vec<usize>> = BTreeMap::new();
+            for &(_, s) in class_edges {
+                touched.entry(block_of[s]).or_default().push(s);
+            }
+            for (y, mut xs) in touched {
+                xs.sort_unstable();
+                xs.dedup();
+                if xs.len() == blocks[y].len() {
+                    continue;
+                }
+                let z = blocks.len();
+                blocks[y].retain(|s| xs.binary_search(s).is_err());
+                for &s in &xs {
+                    block_of[s] = z;
+                }
+                blocks.push(xs);
+                in_work.push(false);
+                if in_work[y] || blocks[z].len() <= blocks[y].len() {
+                    work.push(z);
+                    in_work[z] = true;
+                } else {
+                    work.push(y);
+                    in_work[y] = true;
+                }
+            }
+        }
+    }
+
+    // Renumber blocks breadth-first from the start states, dropping the dead
+    // block unless a start condition can only reach it.
+    let dead_block = block_of[dead];
+    // Blocks reachable from `b` on one class, other than the dead block.
+    let successors = |b: usize| -> Vec<(usize, usize)> {
+        if b == dead_block {
+            return Vec::new();
+        }
+        dfa.states[blocks[b][0]].transitions.iter().map(|(&c, &t)| (c, block_of[t])).filter(|&(_, tb)| tb != dead_block).collect()
+    };
+    let mut new_id: BTreeMap<usize, usize> = BTreeMap::new();
+    let mut order = Vec::new();
+    let mut queue = VecDeque::new();
+    for st in &dfa.starts {
+        for s in [st.normal, st.bol] {
+            let b = block_of[s];
+            if let std::collections::btree_map::Entry::Vacant(e) = new_id.entry(b) {
+                e.insert(order.len());
+                order.push(b);
+                queue.push_back(b);
+            }
+        }
+    }
+    while let Some(b) = queue.pop_front() {
+        for (_, tb) in successors(b) {
+            if let std::collections::btree_map::Entry::Vacant(e) = new_id.entry(tb) {
+                e.insert(order.len());
+                order.push(tb);
+                queue.push_back(tb);
+            }
+        }
+    }
+
+    let states = order
+        .iter()
+        .map(|&b| {
+            let rep = blocks[b][0];
+            let transitions = successors(b).into_iter().map(|(c, tb)| (c, new_id[&tb])).collect();
+            let mut nfa_states: Vec<usize> = blocks[b]
+                .iter()
+                .filter(|&&s| s != dead)
+                .flat_map(|&s| dfa.states[s].nfa_states.iter().copied())
+                .collect();
+            nfa_states.sort_unstable();
+            nfa_states.dedup();
+            let accept = if rep == dead { None } else { dfa.states[rep].accept };
+            DfaState { transitions, accept, nfa_states }
+        })
+        .collect();
+    let starts = dfa
+        .starts
+        .iter()
+        .map(|st| StartStates {
+            condition: st.condition.clone(),
+            normal: new_id[&block_of[st.normal]],
+            bol: new_id[&block_of[st.bol]],
+        })
+        .collect();
+    Dfa { classes: dfa.classes.clone(), states, starts }
+}
+
+/// Automaton for one standalone pattern, active in INITIAL.
+pub fn compile_pattern(source: &str, parsed: &Pattern) -> Result<LexerAutomaton, String> {
+    if parsed.eof {
+        return Err("<<EOF>> does not match any characters".to_string());
+    }
+    let mut parsed = parsed.clone();
+    parsed.start_conditions.clear();
+    let start_conditions = vec![StartCondition { name: "INITIAL".to_string(), exclusive: false }];
+    let context = context_split(&parsed)?;
+    let rules = vec![AutomatonRule { index: 0, pattern: source.to_string(), line: 1, column: 1, action: String::new(), action_line: 1, parsed, context }];
+    let nfa = build_nfa(&rules, &start_conditions)?;
+    let dfa = minimize(&subset_construction(&nfa)?);
+    Ok(LexerAutomaton { start_conditions, rules, nfa, dfa })
+}
+
+pub fn compile_flex(flex: &ASTNode) -> Result<LexerAutomaton, String> {
+    let start_conditions = start_conditions(flex);
+    let rules = compile_rules(flex)?;
+    let nfa = build_nfa(&rules, &start_conditions)?;
+    let dfa = minimize(&subset_construction(&nfa)?);
+    Ok(LexerAutomaton { start_conditions, rules, nfa, dfa })
+}
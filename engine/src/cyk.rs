@@ -0,0 +1,160 @@
+use serde::Serialize;
+
+use crate::grammar::Grammar;
+
+// --- CYK RECOGNIZER ---
+// Runs over a grammar already in Chomsky normal form (see
+// `Transformation::ChomskyNormalForm`). `table[l - 1][i]` holds the
+// nonterminals deriving the `l` tokens starting at position `i`.
+
+#[derive(Serialize, Clone, Debug)]
+pub struct CykEntry {
+    pub nonterminal: String,
+    /// Index into `Grammar::productions` of the rule that first filled this cell.
+    pub production: usize,
+    /// Length of the left part for binary rules; `None` for `A -> a`.
+    pub split: Option<usize>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct CykResult {
+    pub tokens: Vec<String>,
+    pub accepted: bool,
+    pub table: Vec<Vec<Vec<CykEntry>>>,
+    /// Input tokens that are not terminals of the grammar.
+    pub unknown_tokens: Vec<String>,
+}
+
+/// Maps a token as typed by the user onto a grammar terminal, so `+`
+/// matches the literal `'+'`.
+fn resolve_token(grammar: &Grammar, token: &str) -> String {
+    if grammar.terminals.iter().any(|t| t == token) {
+        return token.to_string();
+    }
+    let quoted = format!("'{}'", token);
+    if grammar.terminals.contains(&quoted) {
+        return quoted;
+    }
+    token.to_string()
+}
+
+pub fn cyk(grammar: &Grammar, tokens: &[String]) -> Result<CykResult, String> {
+    for p in &grammar.productions {
+        let ok = match p.rhs.len() {
+            0 => p.lhs == grammar.start,
+            1 => grammar.is_terminal(&p.rhs[0]),
+            2 => !grammar.is_terminal(&p.rhs[0]) && !grammar.is_terminal(&p.rhs[1]),
+            _ => false,
+        };
+        if !ok {
+            return Err(format!("Grammar is not in Chomsky normal form: {}", p));
+        }
+    }
+
+    let tokens: Vec<String> = tokens.iter().map(|t| resolve_token(grammar, t)).collect();
+    let unknown_tokens: Vec<String> = tokens
+        .iter()
+        .filter(|t| !grammar.terminals.contains(t))
+        .cloned()
+        .collect();
+    let n = tokens.len();
+
+    if n == 0 {
+        let accepted = grammar.productions.iter().any(|p| p.lhs == grammar.start && p.rhs.is_empty());
+        return Ok(CykResult { tokens, accepted, table: Vec::new(), unknown_tokens });
+    }
+
+    let mut table: Vec<Vec<Vec<CykEntry>>> = (0..n).map(|l| vec![Vec::new(); n - l]).collect();
+    for (i, tok) in tokens.iter().enumerate() {
+        for (pi, p) in grammar.productions.iter().enumerate() {
+            if p.rhs.len() == 1 && p.rhs[0] == *tok && !table[0][i].iter().any(|e| e.nonterminal == p.lhs) {
+                table[0][i].push(CykEntry { nonterminal: p.lhs.clone(), production: pi, split: None });
+            }
+        }
+    }
+
+    for len in 2..=n {
+        for i in 0..=(n - len) {
+            let mut cell: Vec<CykEntry> = Vec::new();
+            for split in 1..len {
+                let left = &table[split - 1][i];
+                let right = &table[len - split - 1][i + split];
+                for (pi, p) in grammar.productions.iter().enumerate() {
+                    if p.rhs.len() != 2 || cell.iter().any(|e| e.nonterminal == p.lhs) {
+                        continue;
+                    }
+                    if left.iter().any(|e| e.nonterminal == p.rhs[0]) && right.iter().any(|e| e.nonterminal == p.rhs[1]) {
+                        cell.push(CykEntry { nonterminal: p.lhs.clone(), production: pi, split: Some(split) });
+                    }
+                }
+            }
+            table[len - 1][i] = cell;
+        }
+    }
+
+    let accepted = table[n - 1][0].iter().any(|e| e.nonterminal == grammar.start);
+    Ok(CykResult { tokens, accepted, table, unknown_tokens })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transform::{transform, Transformation};
+
+    fn grammar(rules: &str) -> Grammar {
+        Grammar::from_ast(&crate::parse_bison(&format!("%token NUM ID\n%%\n{}", rules))).unwrap()
+    }
+
+    fn tokens(input: &str) -> Vec<String> {
+        input.split_whitespace().map(str::to_string).collect()
+    }
+
+    /// { a^n b^n : n >= 1 }, already in CNF.
+    const BALANCED: &str = "s: a x | a b ;\nx: s b ;\na: 'a' ;\nb: 'b' ;\n";
+
+    #[test]
+    fn recognizes_balanced_strings_and_records_splits() {
+        let g = grammar(BALANCED);
+        let result = cyk(&g, &tokens("a a b b")).unwrap();
+        assert!(result.accepted);
+        assert_eq!(result.tokens, ["'a'", "'a'", "'b'", "'b'"]);
+        assert!(result.unknown_tokens.is_empty());
+        let names = |cell: &[CykEntry]| cell.iter().map(|e| e.nonterminal.clone()).collect::<Vec<_>>();
+        assert_eq!(names(&result.table[0][0]), ["a"]);
+        assert_eq!(names(&result.table[1][1]), ["s"]);
+        assert_eq!(names(&result.table[2][1]), ["x"]);
+        let top = &result.table[3][0][0];
+        assert_eq!((top.nonterminal.as_str(), top.split), ("s", Some(1)));
+        assert_eq!(g.productions[top.production].to_string(), "s -> a x");
+
+        assert!(!cyk(&g, &tokens("a b b")).unwrap().accepted);
+        assert!(!cyk(&g, &tokens("b a")).unwrap().accepted);
+    }
+
+    #[test]
+    fn parses_the_expression_grammar_after_cnf_conversion() {
+        let g = grammar("e: e '+' t | t ;\nt: t '*' f | f ;\nf: '(' e ')' | NUM ;\n");
+        let cnf = transform(&g, &[Transformation::ChomskyNormalForm]).unwrap().grammar;
+        assert!(cyk(&cnf, &tokens("NUM + NUM * NUM")).unwrap().accepted);
+        assert!(cyk(&cnf, &tokens("( NUM + NUM ) * NUM")).unwrap().accepted);
+        assert!(!cyk(&cnf, &tokens("NUM + * NUM")).unwrap().accepted);
+        assert!(!cyk(&cnf, &tokens("( NUM")).unwrap().accepted);
+
+        let result = cyk(&cnf, &tokens("NUM - NUM")).unwrap();
+        assert!(!result.accepted);
+        assert_eq!(result.unknown_tokens, ["-"]);
+    }
+
+    #[test]
+    fn empty_input_needs_an_empty_start_rule() {
+        let g = grammar("s: a b | ;\na: 'a' ;\nb: 'b' ;\n");
+        assert!(cyk(&g, &[]).unwrap().accepted);
+        assert!(!cyk(&grammar(BALANCED), &[]).unwrap().accepted);
+    }
+
+    #[test]
+    fn rejects_grammars_outside_cnf() {
+        let err = cyk(&grammar("s: 'a' s 'b' | 'a' 'b' ;\n"), &tokens("a b")).unwrap_err();
+        assert_eq!(err, "Grammar is not in Chomsky normal form: s -> 'a' s 'b'");
+    }
+}
@@ -0,0 +1,369 @@
+use std::collections::BTreeMap;
+
+use crate::grammar::{Grammar, Production, END_MARKER};
+use crate::lalr::{terminal_symbols, UNDEFINED_SYMBOL};
+use crate::lexgen::{comment_safe, defines_function, mentions, number_output_lines, user_code};
+use crate::ll1::analyze_ll1;
+use crate::rustgen::{check_default_action, emit_token_types, rust_action_body, RustSymbols, MODULE_ATTRIBUTES};
+use crate::yaccgen::{
+    action_lines, char_literal_code, declares_locations, default_lexer_code, default_main, header_block, is_c_identifier, support_declarations,
+    symbol_types, token_codes, translate_action, union_body, Interface, RuleContext, ValueAccess, PARSER_OUTPUT, PARSER_SOURCE,
+};
+use crate::{ASTNode, CodegenOptions};
+
+// --- RECURSIVE-DESCENT GENERATION ---
+// For LL(1) grammars, a parser written the way one would by hand: a function
+// per nonterminal that picks its production from the lookahead, using the
+// predictive table of `analyze_ll1`, then calls or matches each symbol in
+// turn. Values are returned rather than stacked, so `$n` is a local and
+// nothing outside the rule (`$0`, `@n`) is reachable. There is no error
+// recovery: the first syntax error, or `YYERROR`, ends the parse.
+
+/// nonterminal -> lookahead -> the production it selects.
+type PredictiveTable = BTreeMap<String, BTreeMap<String, usize>>;
+
+/// Grammar and its predictive table, or why no recursive-descent parser
+/// exists for it.
+fn predictive_table(bison: &ASTNode) -> Result<(Grammar, PredictiveTable), String> {
+    let grammar = Grammar::from_ast(bison)?;
+    if let Some(p) = grammar.productions.iter().find(|p| p.rhs.iter().any(|s| s == "error")) {
+        return Err(format!("'{}' uses the error token, which needs an LALR parser's error recovery", p));
+    }
+    let report = analyze_ll1(&grammar);
+    if !report.is_ll1 {
+        let mut reasons: Vec<String> = report
+            .left_recursion
+            .iter()
+            .map(|r| format!("'{}' is left-recursive ({})", r.nonterminal, r.cycle.join(" => ")))
+            .collect();
+        reasons.extend(report.conflicts.iter().map(|c| format!("'{}' on '{}' could be {}", c.nonterminal, c.lookahead, c.rules.join(" or "))));
+        return Err(format!("Grammar is not LL(1): {}", reasons.join("; ")));
+    }
+    let table = report.table.into_iter().map(|(nt, row)| (nt, row.into_iter().map(|(t, cell)| (t, cell[0])).collect())).collect();
+    Ok((grammar, table))
+}
+
+/// A nonterminal's productions in grammar order, each with the lookaheads
+/// that select it, in symbol-number order.
+fn alternatives<'a>(
+    grammar: &'a Grammar,
+    row: &BTreeMap<String, usize>,
+    terminals: &[String],
+    nt: &'a str,
+) -> Vec<(usize, &'a Production, Vec<usize>)> {
+    grammar
+        .productions_for(nt)
+        .map(|(pi, p)| {
+            let lookaheads = (0..terminals.len()).filter(|&t| row.get(&terminals[t]) == Some(&pi)).collect();
+            (pi, p, lookaheads)
+        })
+        .filter(|(_, _, lookaheads): &(usize, &Production, Vec<usize>)| !lookaheads.is_empty())
+        .collect()
+}
+
+/// Nonterminal names made into identifiers: `expr.list` -> `expr_list`.
+fn function_name(prefix: &str, nt: &str) -> String {
+    let name: String = nt.chars().map(|c| if c.is_ascii_alphanumeric() || c == '_' { c } else { '_' }).collect();
+    format!("{}{}", prefix, name)
+}
+
+// --- C ---
+
+/// `case` label for a token: its name or character literal where C has
+/// one, else its number.
+fn case_label(sym: &str, code: i64) -> String {
+    if sym == END_MARKER {
+        "YYEOF".to_string()
+    } else if char_literal_code(sym).is_some() || is_c_identifier(sym) {
+        sym.to_string()
+    } else {
+        format!("{} /* {} */", code, comment_safe(sym))
+    }
+}
+
+/// A recursive-descent y.tab.c: same interface as `generate_parser`'s
+/// (`yyparse`, `yylex`, `yylval`, y.tab.h), built from the LL(1) table.
+pub fn generate_c_descent(bison: &ASTNode, codegen: &CodegenOptions) -> Result<String, String> {
+    let ASTNode::BisonFile { prologue, prologue_line, epilogue, epilogue_line, .. } = bison else {
+        return Err("Expected a Bison specification".to_string());
+    };
+    let (grammar, table) = predictive_table(bison)?;
+    if declares_locations(bison) {
+        return Err("%locations is not supported by the recursive-descent parser".to_string());
+    }
+    let iface = Interface::from_ast(bison);
+    if !iface.is_default() {
+        return Err("%define api.pure, api.push-pull and %parse-param/%lex-param need the LALR parser".to_string());
+    }
+    let prologue = prologue.as_deref().unwrap_or("");
+    let epilogue = epilogue.as_deref().unwrap_or("");
+    let terminals = terminal_symbols(&grammar);
+    let codes = token_codes(&terminals);
+    let types = symbol_types(bison);
+    let typed = union_body(bison).is_some();
+    let lines = action_lines(bison);
+
+    // --- Functions ---
+    let mut functions = String::new();
+    for nt in &grammar.nonterminals {
+        let alts = alternatives(&grammar, &table[nt], &terminals, nt);
+        let width = grammar.productions_for(nt).map(|(_, p)| p.rhs.len()).max().unwrap_or(0);
+        let rules: Vec<String> = grammar.productions_for(nt).map(|(_, p)| if p.rhs.is_empty() { "ε".to_string() } else { p.rhs.join(" ") }).collect();
+        functions.push_str(&format!("\n/* {}: {} */\n", comment_safe(nt), comment_safe(&rules.join(" | "))));
+        functions.push_str(&format!("static YYSTYPE {}(void)\n{{\n    YYSTYPE yyval;\n", function_name("yyparse_", nt)));
+        if width > 0 {
+            functions.push_str(&format!("    YYSTYPE yyrhs[{}];\n", width + 1));
+        }
+        functions.push_str("\n    memset(&yyval, 0, sizeof yyval);\n    switch (yychar)\n    {\n");
+        for (pi, p, lookaheads) in alts {
+            for &t in &lookaheads {
+                functions.push_str(&format!("    case {}:\n", case_label(&terminals[t], codes[t])));
+            }
+            functions.push_str(&format!("        /* {} */\n", comment_safe(&p.to_string())));
+            for (i, sym) in p.rhs.iter().enumerate() {
+                let call = match terminals.iter().position(|t| t == sym) {
+                    Some(t) => format!("yymatch({})", case_label(sym, codes[t])),
+                    None => format!("{}()", function_name("yyparse_", sym)),
+                };
+                functions.push_str(&format!("        yyrhs[{}] = {};\n", i + 1, call));
+            }
+            if !p.rhs.is_empty() {
+                functions.push_str("        yyval = yyrhs[1];\n");
+            }
+            if let Some(action) = &p.action {
+                let ctx = RuleContext { lhs: &p.lhs, rhs: &p.rhs, types: &types, typed, access: ValueAccess::Frame };
+                let (body, _) = translate_action(action, &ctx)?;
+                let line = lines.get(pi).copied().unwrap_or(0);
+                functions.push_str(&user_code(codegen, PARSER_SOURCE, line, &format!("        {{ {} }}", body)));
+            }
+            functions.push_str("        break;\n\n");
+        }
+        functions.push_str("    default:\n        yysyntax_error();\n    }\n    return yyval;\n}\n");
+    }
+
+    let mut code = String::from("/* Generated by Structura.ai Syntax Engine: recursive-descent parser */\n");
+    code.push_str("#include <stdio.h>\n#include <stdlib.h>\n#include <string.h>\n#include <setjmp.h>\n\n");
+    if !prologue.is_empty() {
+        code.push_str("/* --- PROLOGUE --- */\n");
+        code.push_str(&user_code(codegen, PARSER_SOURCE, *prologue_line, prologue));
+        code.push('\n');
+    }
+    code.push_str(&header_block(bison, &grammar, false));
+    code.push_str("\nint yychar;\nYYSTYPE yylval;\nint yynerrs;\n");
+    code.push_str(&support_declarations(prologue, epilogue, codegen, &iface, false));
+    code.push_str(&format!("#define YYEOF 0\n#define YYUNDEFTOK {}\n#define YYNTOKENS {}\n", UNDEFINED_SYMBOL, terminals.len()));
+    if !codegen.combined && !defines_function(prologue, "yylex") && !defines_function(epilogue, "yylex") {
+        code.push('\n');
+        code.push_str(&default_lexer_code(&terminals, &iface, false));
+    }
+    code.push_str(DESCENT_RUNTIME);
+
+    code.push_str("/* One function per nonterminal. */\n");
+    for nt in &grammar.nonterminals {
+        code.push_str(&format!("static YYSTYPE {}(void);\n", function_name("yyparse_", nt)));
+    }
+    code.push_str(&functions);
+    code.push_str(&DESCENT_DRIVER.replace("START", &function_name("yyparse_", &grammar.start)));
+
+    code.push_str(&default_main(prologue, epilogue, codegen, &iface, false));
+    if !epilogue.is_empty() {
+        code.push_str("\n/* --- EPILOGUE --- */\n");
+        code.push_str(&user_code(codegen, PARSER_SOURCE, *epilogue_line, epilogue));
+    }
+    Ok(number_output_lines(code, PARSER_OUTPUT))
+}
+
+const DESCENT_RUNTIME: &str = r#"
+/* Actions leave the parse through yyparse's setjmp. */
+static jmp_buf yyjmp;
+#define YYACCEPT longjmp(yyjmp, 2)
+#define YYABORT longjmp(yyjmp, 1)
+#define YYERROR longjmp(yyjmp, 1)
+#define yyerrok ((void) 0)
+#define yyclearin yyadvance()
+#define YYRECOVERING() 0
+
+static void yyadvance(void)
+{
+    yychar = yylex();
+    if (yychar < 0)
+        yychar = YYEOF;
+}
+
+static void yysyntax_error(void)
+{
+    yynerrs++;
+    yyerror("syntax error");
+    longjmp(yyjmp, 1);
+}
+
+/* Consumes the lookahead, which must be token `yytoken`, and returns its value. */
+static YYSTYPE yymatch(int yytoken)
+{
+    YYSTYPE yyv = yylval;
+    if (yychar != yytoken)
+        yysyntax_error();
+    yyadvance();
+    return yyv;
+}
+
+"#;
+
+const DESCENT_DRIVER: &str = r#"
+int yyparse(void)
+{
+    yynerrs = 0;
+    switch (setjmp(yyjmp))
+    {
+    case 0:
+        yyadvance();
+        START();
+        if (yychar != YYEOF)
+            yysyntax_error();
+        return 0;
+    case 2:
+        return 0;
+    default:
+        return 1;
+    }
+}
+"#;
+
+// --- RUST ---
+
+/// The Rust counterpart of `generate_c_descent`: a `Parser` over the same
+/// `Token` and `Lexer` as `generate_rust_parser`'s, with a method per
+/// nonterminal returning that nonterminal's value.
+pub fn generate_rust_descent(bison: &ASTNode) -> Result<String, String> {
+    let ASTNode::BisonFile { prologue, epilogue, .. } = bison else {
+        return Err("Expected a Bison specification".to_string());
+    };
+    let (grammar, table) = predictive_table(bison)?;
+    let terminals = terminal_symbols(&grammar);
+    let mut symbols = terminals.clone();
+    symbols.extend(grammar.nonterminals.iter().cloned());
+    let syms = RustSymbols::new(bison, &symbols, terminals.len())?;
+
+    // --- Methods ---
+    let mut methods = String::new();
+    for nt in &grammar.nonterminals {
+        let lhs_type = syms.type_of(nt);
+        let rules: Vec<String> = grammar.productions_for(nt).map(|(_, p)| if p.rhs.is_empty() { "ε".to_string() } else { p.rhs.join(" ") }).collect();
+        methods.push_str(&format!("\n    /// {}: {}\n", nt, rules.join(" | ").replace('`', "'")));
+        methods.push_str(&format!("    fn {}(&mut self) -> Result<{}, Control> {{\n        match self.yylook {{\n", function_name("parse_", nt), lhs_type));
+        for (_, p, lookaheads) in alternatives(&grammar, &table[nt], &terminals, nt) {
+            let patterns: Vec<String> = lookaheads.iter().filter_map(|&t| syms.token_pattern(t)).collect();
+            if patterns.is_empty() {
+                continue;
+            }
+            methods.push_str(&format!("            {} => {{\n                // {}\n", patterns.join(" | "), p));
+            for (i, sym) in p.rhs.iter().enumerate() {
+                match terminals.iter().position(|t| t == sym) {
+                    Some(t) => methods.push_str(&format!(
+                        "                let _{} = self.yymatch({})?.{}(); // {}\n",
+                        i + 1,
+                        t,
+                        syms.values.accessor(&syms.types[t]),
+                        sym
+                    )),
+                    None => methods.push_str(&format!("                let _{} = self.{}()?;\n", i + 1, function_name("parse_", sym))),
+                }
+            }
+            let first = p.rhs.first().map(|s| syms.type_of(s));
+            match &p.action {
+                Some(action) => {
+                    let body = rust_action_body(p, action, &syms, lhs_type, first, "                ")?;
+                    if mentions(&body, "yyloc") || mentions(&body, "yylocs") {
+                        return Err(format!("locations are not supported by the recursive-descent parser (rule for '{}')", p.lhs));
+                    }
+                    methods.push_str(&body);
+                }
+                None => {
+                    check_default_action(p, lhs_type, first)?;
+                    let value = if first.is_some() && lhs_type != "()" { "_1" } else { "Default::default()" };
+                    methods.push_str(&format!("                let yyval: {} = {};\n", lhs_type, value));
+                }
+            }
+            methods.push_str("                Ok(yyval)\n            }\n");
+        }
+        methods.push_str("            _ => Err(self.yysyntax_error()),\n        }\n    }\n");
+    }
+
+    let mut code = String::from("// Generated by Structura.ai Syntax Engine: recursive-descent parser\n");
+    code.push_str(MODULE_ATTRIBUTES);
+    code.push_str(DESCENT_DOC);
+    if let Some(p) = prologue {
+        code.push_str("\n// --- PROLOGUE ---\n");
+        code.push_str(p);
+        code.push('\n');
+    }
+    emit_token_types(&mut code, &syms);
+    let start = function_name("parse_", &grammar.start);
+    code.push_str(&RUST_DESCENT_DRIVER.replace("START_TYPE", syms.type_of(&grammar.start)).replace("START", &start));
+    code.push_str(&methods);
+    code.push_str("}\n");
+
+    if let Some(e) = epilogue {
+        code.push_str("\n// --- EPILOGUE ---\n");
+        code.push_str(e);
+        code.push('\n');
+    }
+    Ok(code)
+}
+
+const DESCENT_DOC: &str = "
+// Actions are Rust and run as `Parser` methods: `$$` is `yyval` and `$n` the
+// n-th symbol's value. `return Err(YYACCEPT)` ends the parse with `Ok(None)`;
+// `return Err(YYABORT)` and `return Err(YYERROR)` with `Err(Aborted)`.
+";
+
+const RUST_DESCENT_DRIVER: &str = r#"pub struct Parser<L: Lexer> {
+    pub lexer: L,
+    pub yynerrs: usize,
+    /// The lookahead token.
+    yylook: Token,
+}
+
+impl<L: Lexer> Parser<L> {
+    pub fn new(lexer: L) -> Self {
+        Parser { lexer, yynerrs: 0, yylook: Token::Eof }
+    }
+
+    pub fn yyerror(&mut self, message: &str) {
+        self.lexer.yyerror(message);
+    }
+
+    fn yysyntax_error(&mut self) -> Control {
+        self.yynerrs += 1;
+        self.yyerror("syntax error");
+        Control::Abort
+    }
+
+    /// Consumes the lookahead, which must be symbol `symbol`, and returns its value.
+    fn yymatch(&mut self, symbol: usize) -> Result<YYValue, Control> {
+        let (found, value) = yytoken(std::mem::replace(&mut self.yylook, Token::Eof));
+        if found != symbol {
+            return Err(self.yysyntax_error());
+        }
+        self.yylook = self.lexer.yylex();
+        Ok(value)
+    }
+
+    /// Parses the whole input: the start symbol's value, or `None` when an
+    /// action accepted early with `YYACCEPT`.
+    pub fn parse(&mut self) -> Result<Option<START_TYPE>, ParseError> {
+        self.yynerrs = 0;
+        self.yylook = self.lexer.yylex();
+        match self.START() {
+            Ok(value) => match self.yylook {
+                Token::Eof => Ok(Some(value)),
+                _ => {
+                    self.yysyntax_error();
+                    Err(ParseError::Aborted)
+                }
+            },
+            Err(Control::Accept) => Ok(None),
+            Err(_) => Err(ParseError::Aborted),
+        }
+    }
+"#;
@@ -0,0 +1,105 @@
+use serde::Serialize;
+
+// --- GCC DIAGNOSTICS ---
+// gcc's and ld's stderr split into one diagnostic per message. The generated
+// code carries `#line` directives, so errors inside actions, the prologue or
+// the epilogue already name the .l/.y spec; every other file is looked up
+// among the generated outputs to recover the spec it came from.
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+    Error,
+    Warning,
+    Note,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct Diagnostic {
+    /// File the diagnostic is reported in: a spec, a generated or extra file,
+    /// "link" for the linker, "gcc" for the driver and "host" for the server.
+    pub file: String,
+    /// Source `file` was generated from.
+    pub source: Option<String>,
+    pub line: Option<usize>,
+    pub column: Option<usize>,
+    pub severity: Severity,
+    pub message: String,
+}
+
+impl Diagnostic {
+    pub fn error(file: &str, source: Option<&str>, line: Option<usize>, column: Option<usize>, message: String) -> Self {
+        Diagnostic { file: file.to_string(), source: source.map(str::to_string), line, column, severity: Severity::Error, message }
+    }
+}
+
+/// `12:5: message` → (Some(12), Some(5), "message").
+fn split_location(rest: &str) -> (Option<usize>, Option<usize>, &str) {
+    let mut parts = rest.splitn(3, ':');
+    let line = parts.next().and_then(|p| p.parse().ok());
+    let Some(line) = line else { return (None, None, rest) };
+    let column_or_text = parts.next().unwrap_or("");
+    match column_or_text.parse() {
+        Ok(column) => (Some(line), Some(column), parts.next().unwrap_or("").trim()),
+        Err(_) => (Some(line), None, rest.split_once(':').map_or("", |(_, m)| m).trim()),
+    }
+}
+
+/// `warning: unused variable` → (Warning, "unused variable"); untagged
+/// messages, like the linker's, are errors.
+fn split_severity(message: &str) -> (Severity, &str) {
+    let tags = [("fatal error:", Severity::Error), ("error:", Severity::Error), ("warning:", Severity::Warning), ("note:", Severity::Note)];
+    tags.iter()
+        .find_map(|(tag, severity)| message.strip_prefix(tag).map(|m| (*severity, m.trim())))
+        .unwrap_or((Severity::Error, message))
+}
+
+/// One diagnostic per gcc or linker message. `files` pairs each file name
+/// gcc may report with the spec it was generated from; specs named by
+/// `#line` are matched as themselves.
+pub fn parse_gcc_output(stderr: &str, files: &[(&str, &str)]) -> Vec<Diagnostic> {
+    let mut names: Vec<(&str, &str)> = files.to_vec();
+    for &(_, source) in files {
+        if !names.iter().any(|(name, _)| *name == source) {
+            names.push((source, source));
+        }
+    }
+    let mut out: Vec<Diagnostic> = Vec::new();
+    for line in stderr.lines() {
+        let linker = line.strip_prefix("/usr/bin/ld: ").or_else(|| line.strip_prefix("ld: "));
+        let line = linker.unwrap_or(line);
+        let known = names.iter().find(|(name, _)| line.strip_prefix(name).is_some_and(|rest| rest.starts_with(':')));
+        if let Some(&(name, source)) = known {
+            let rest = &line[name.len() + 1..];
+            // "In function 'yyparse':" and friends only give context.
+            if rest.starts_with(' ') {
+                continue;
+            }
+            // Linker lines read `y.tab.c:(.text+0x2b): undefined reference ...`.
+            let (line, column, message) = match rest.strip_prefix('(') {
+                Some(section) => (None, None, section.split_once("):").map_or(section, |(_, m)| m).trim()),
+                None => split_location(rest),
+            };
+            let (severity, message) = split_severity(message);
+            out.push(Diagnostic { file: name.to_string(), source: Some(source.to_string()), line, column, severity, message: message.to_string() });
+        } else if linker.is_some() {
+            // "/tmp/cc1.o: in function `main':" only gives context.
+            if !line.ends_with("':") {
+                let (severity, message) = split_severity(line);
+                out.push(Diagnostic { file: "link".to_string(), source: None, line: None, column: None, severity, message: message.to_string() });
+            }
+        } else if line.starts_with("gcc:") || line.starts_with("cc1:") {
+            let (severity, message) = split_severity(line.split_once(':').map_or("", |(_, m)| m).trim());
+            out.push(Diagnostic { file: "gcc".to_string(), source: None, line: None, column: None, severity, message: message.to_string() });
+        } else if !line.starts_with(' ') && !line.starts_with("collect2:") && !line.starts_with("In file included from") && !line.trim().is_empty() {
+            match out.last_mut() {
+                Some(last) => {
+                    last.message.push('\n');
+                    last.message.push_str(line);
+                }
+                None => out.push(Diagnostic::error("gcc", None, None, None, line.to_string())),
+            }
+        }
+    }
+    out
+}
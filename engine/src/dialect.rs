@@ -0,0 +1,442 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::regex::{char_after, char_before, complement_ranges, normalize_ranges, parse_flex_pattern, property_name, Regex};
+
+// --- REGEX DIALECT TRANSLATION ---
+// Re-emits a parsed flex pattern in another engine's syntax. Because the
+// parser has already resolved escapes, definitions and class negation, the
+// emitters only deal with literals, ranges and operators; anything flex
+// expresses that the target cannot is reported instead of silently dropped.
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Dialect {
+    PosixEre,
+    Pcre,
+    #[serde(rename = "javascript")]
+    JavaScript,
+    Rust,
+}
+
+pub const ALL_DIALECTS: [Dialect; 4] = [Dialect::PosixEre, Dialect::Pcre, Dialect::JavaScript, Dialect::Rust];
+
+#[derive(Serialize, Clone, Debug)]
+pub struct TranslationIssue {
+    /// The flex construct concerned, e.g. `trailing context`.
+    pub construct: String,
+    pub message: String,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct Translation {
+    pub dialect: Dialect,
+    pub pattern: String,
+    /// Flags the pattern must be compiled with (e.g. `u` for JavaScript).
+    pub flags: String,
+    /// True when nothing had to be dropped or approximated.
+    pub faithful: bool,
+    /// Constructs that were dropped or only approximated.
+    pub issues: Vec<TranslationIssue>,
+    /// Behavioural differences worth knowing that do not change the pattern.
+    pub notes: Vec<String>,
+}
+
+/// Removes `c` from `ranges`, reporting whether it was there.
+fn split_out(ranges: &[(char, char)], c: char) -> (Vec<(char, char)>, bool) {
+    let mut out = Vec::new();
+    let mut found = false;
+    for &(lo, hi) in ranges {
+        if lo <= c && c <= hi {
+            found = true;
+            if lo < c {
+                out.push((lo, char_before(c).unwrap()));
+            }
+            if c < hi {
+                out.push((char_after(c).unwrap(), hi));
+            }
+        } else {
+            out.push((lo, hi));
+        }
+    }
+    (out, found)
+}
+
+struct Emitter {
+    dialect: Dialect,
+    issues: Vec<TranslationIssue>,
+    uses_alternation: bool,
+    uses_non_ascii: bool,
+    uses_property: bool,
+}
+
+impl Emitter {
+    fn issue(&mut self, construct: &str, message: &str) {
+        if !self.issues.iter().any(|i| i.construct == construct && i.message == message) {
+            self.issues.push(TranslationIssue { construct: construct.to_string(), message: message.to_string() });
+        }
+    }
+
+    fn group(&self, inner: &str) -> String {
+        match self.dialect {
+            Dialect::PosixEre => format!("({})", inner),
+            _ => format!("(?:{})", inner),
+        }
+    }
+
+    /// A character outside brackets.
+    fn literal(&mut self, c: char) -> String {
+        if (c as u32) > 0x7f {
+            self.uses_non_ascii = true;
+        }
+        if self.dialect == Dialect::PosixEre {
+            // ERE has no escapes for control characters; the raw byte is literal.
+            return match c {
+                '.' | '[' | ']' | '(' | ')' | '*' | '+' | '?' | '{' | '}' | '|' | '^' | '$' | '\\' => format!("\\{}", c),
+                c => c.to_string(),
+            };
+        }
+        match c {
+            '.' | '[' | ']' | '(' | ')' | '*' | '+' | '?' | '{' | '}' | '|' | '^' | '$' | '\\' => format!("\\{}", c),
+            // Keeps the pattern usable inside a `/.../` literal.
+            '/' if self.dialect == Dialect::JavaScript => "\\/".to_string(),
+            c => self.escape_special(c).unwrap_or_else(|| c.to_string()),
+        }
+    }
+
+    /// Escape sequence for control and non-printing characters (non-ERE dialects).
+    fn escape_special(&self, c: char) -> Option<String> {
+        let code = c as u32;
+        match c {
+            '\n' => Some("\\n".to_string()),
+            '\t' => Some("\\t".to_string()),
+            '\r' => Some("\\r".to_string()),
+            _ if code < 0x20 || code == 0x7f => Some(format!("\\x{:02x}", code)),
+            _ if c.is_whitespace() || c.is_control() => Some(match self.dialect {
+                Dialect::JavaScript => format!("\\u{{{:x}}}", code),
+                _ => format!("\\x{{{:x}}}", code),
+            }),
+            _ => None,
+        }
+    }
+
+    /// A character inside brackets.
+    fn class_char(&mut self, c: char) -> String {
+        if (c as u32) > 0x7f {
+            self.uses_non_ascii = true;
+        }
+        if self.dialect == Dialect::PosixEre {
+            // Backslash is literal inside POSIX brackets; `]`, `^` and `-`
+            // are placed by `class` instead.
+            return c.to_string();
+        }
+        match c {
+            '\\' | ']' | '[' | '^' | '-' => format!("\\{}", c),
+            // The Rust crate also treats `&&`, `--` and `~~` as set operators.
+            '&' | '~' if self.dialect == Dialect::Rust => format!("\\{}", c),
+            c => self.escape_special(c).unwrap_or_else(|| c.to_string()),
+        }
+    }
+
+    fn class_body(&mut self, ranges: &[(char, char)]) -> String {
+        if self.dialect != Dialect::PosixEre {
+            let mut out = String::new();
+            for &(lo, hi) in ranges {
+                out.push_str(&self.class_char(lo));
+                if hi > lo {
+                    if hi as u32 > lo as u32 + 1 {
+                        out.push('-');
+                    }
+                    out.push_str(&self.class_char(hi));
+                }
+            }
+            return out;
+        }
+        // POSIX: `]` is literal only first, `-` only last, and `^` anywhere
+        // but first, so those three are pulled out and placed explicitly.
+        let (ranges, close) = split_out(ranges, ']');
+        let (ranges, caret) = split_out(&ranges, '^');
+        let (ranges, dash) = split_out(&ranges, '-');
+        let mut out = String::new();
+        if close {
+            out.push(']');
+        }
+        for (lo, hi) in ranges {
+            out.push_str(&self.class_char(lo));
+            if hi > lo {
+                if hi as u32 > lo as u32 + 1 {
+                    out.push('-');
+                }
+                out.push_str(&self.class_char(hi));
+            }
+        }
+        match (out.is_empty(), caret, dash) {
+            (true, true, true) => out.push_str("-^"),
+            (_, true, _) => {
+                out.push('^');
+                if dash {
+                    out.push('-');
+                }
+            }
+            (_, false, true) => out.push('-'),
+            _ => {}
+        }
+        out
+    }
+
+    /// `\p{Name}` syntax; `Alnum` has no property of its own in these engines.
+    fn property(&mut self, name: &str, negated: bool) -> String {
+        self.uses_property = true;
+        match (name, negated) {
+            ("Alnum", false) => "[\\p{Alphabetic}\\p{N}]".to_string(),
+            ("Alnum", true) => "[^\\p{Alphabetic}\\p{N}]".to_string(),
+            (name, false) => format!("\\p{{{}}}", name),
+            (name, true) => format!("\\P{{{}}}", name),
+        }
+    }
+
+    fn class(&mut self, ranges: &[(char, char)]) -> String {
+        let ranges = normalize_ranges(ranges.to_vec());
+        if ranges.is_empty() {
+            self.issue("empty class", "A class matching nothing has no direct equivalent; emitted as an impossible class");
+            return match self.dialect {
+                Dialect::PosixEre => "[^\u{1}-\u{10FFFF}]".to_string(),
+                _ => "[^\\s\\S]".to_string(),
+            };
+        }
+        if ranges.len() == 1 && ranges[0].0 == ranges[0].1 {
+            return self.literal(ranges[0].0);
+        }
+        let complement = complement_ranges(&ranges);
+        if complement.is_empty() {
+            return match self.dialect {
+                Dialect::PosixEre => {
+                    self.issue("any character", "POSIX '.' never matches NUL and may skip newline under REG_NEWLINE");
+                    ".".to_string()
+                }
+                Dialect::Rust => "(?s:.)".to_string(),
+                _ => "[\\s\\S]".to_string(),
+            };
+        }
+        // Flex's `.` is everything but newline.
+        if complement == [('\n', '\n')] && matches!(self.dialect, Dialect::Pcre | Dialect::Rust) {
+            return ".".to_string();
+        }
+        if self.dialect != Dialect::PosixEre {
+            if let Some(name) = property_name(&ranges) {
+                return self.property(name, false);
+            }
+            if let Some(name) = property_name(&complement) {
+                return self.property(name, true);
+            }
+        }
+        let covered: u64 = ranges.iter().map(|&(lo, hi)| hi as u64 - lo as u64 + 1).sum();
+        if covered > 0x10000 {
+            return format!("[^{}]", self.class_body(&complement));
+        }
+        if self.dialect == Dialect::PosixEre && ranges.iter().any(|&(_, hi)| hi as u32 > 0x7f) {
+            self.issue("non-ASCII range", "POSIX bracket ranges beyond ASCII depend on the locale");
+        }
+        format!("[{}]", self.class_body(&ranges))
+    }
+
+    fn regex(&mut self, re: &Regex) -> String {
+        match re {
+            Regex::Empty => match self.dialect {
+                Dialect::PosixEre => {
+                    self.issue("empty string", "POSIX leaves an empty group '()' undefined");
+                    "()".to_string()
+                }
+                _ => "(?:)".to_string(),
+            },
+            Regex::Literal { c } => self.literal(*c),
+            Regex::Class { ranges } => self.class(ranges),
+            Regex::Concat { items } => items
+                .iter()
+                .map(|item| match item {
+                    Regex::Alt { .. } => {
+                        let inner = self.regex(item);
+                        self.group(&inner)
+                    }
+                    _ => self.regex(item),
+                })
+                .collect(),
+            Regex::Alt { items } => {
+                self.uses_alternation = true;
+                items.iter().map(|item| self.regex(item)).collect::<Vec<String>>().join("|")
+            }
+            Regex::Repeat { inner, min, max } => {
+                let body = self.regex(inner);
+                let atomic = matches!(inner.as_ref(), Regex::Literal { .. } | Regex::Class { .. });
+                let body = if atomic { body } else { self.group(&body) };
+                let quantifier = match (min, max) {
+                    (0, None) => "*".to_string(),
+                    (1, None) => "+".to_string(),
+                    (0, Some(1)) => "?".to_string(),
+                    (n, None) => format!("{{{},}}", n),
+                    (n, Some(m)) if n == m => format!("{{{}}}", n),
+                    (n, Some(m)) => format!("{{{},{}}}", n, m),
+                };
+                if self.dialect == Dialect::PosixEre && max.is_some_and(|m| m > 255) {
+                    self.issue("repetition", "POSIX only guarantees repetition counts up to RE_DUP_MAX (255)");
+                }
+                format!("{}{}", body, quantifier)
+            }
+        }
+    }
+}
+
+/// Translates a flex rule pattern into `dialect`, expanding `{NAME}` references.
+pub fn translate_pattern(src: &str, definitions: &BTreeMap<String, String>, dialect: Dialect) -> Result<Translation, String> {
+    let pattern = parse_flex_pattern(src, definitions)?;
+    let mut emitter = Emitter { dialect, issues: Vec::new(), uses_alternation: false, uses_non_ascii: false, uses_property: false };
+    let mut notes = Vec::new();
+    let mut flags = String::new();
+
+    if pattern.eof {
+        emitter.issue("<<EOF>>", "End-of-file rules have no regex equivalent");
+        return Ok(Translation { dialect, pattern: String::new(), flags, faithful: false, issues: emitter.issues, notes });
+    }
+    if !pattern.start_conditions.is_empty() {
+        emitter.issue(
+            "start conditions",
+            &format!("<{}> is scanner state, not pattern syntax; it was dropped", pattern.start_conditions.join(",")),
+        );
+    }
+
+    let mut out = String::new();
+    if pattern.bol {
+        match dialect {
+            Dialect::PosixEre => notes.push("'^' matches at line starts only when compiled with REG_NEWLINE".to_string()),
+            Dialect::JavaScript => flags.push('m'),
+            _ => out.push_str("(?m)"),
+        }
+        out.push('^');
+    }
+    let body = emitter.regex(&pattern.regex);
+    // Keep a top-level alternation from swallowing the anchor or lookahead.
+    let wrap = matches!(pattern.regex, Regex::Alt { .. }) && (pattern.bol || pattern.trailing.is_some());
+    out.push_str(&if wrap { emitter.group(&body) } else { body });
+
+    match &pattern.trailing {
+        // `$`: only before a newline.
+        Some(Regex::Literal { c: '\n' }) => match dialect {
+            Dialect::Pcre | Dialect::JavaScript => out.push_str("(?=\\n)"),
+            Dialect::Rust => {
+                out.push_str("(?m:$)");
+                emitter.issue("$", "'(?m:$)' also matches at the end of the input, where flex's '$' does not");
+            }
+            Dialect::PosixEre => {
+                out.push('$');
+                notes.push("'$' matches before a newline only when compiled with REG_NEWLINE".to_string());
+            }
+        },
+        Some(trailing) => match dialect {
+            Dialect::Pcre | Dialect::JavaScript => {
+                let look = emitter.regex(trailing);
+                out.push_str(&format!("(?={})", look));
+                notes.push("Trailing context became a lookahead: the engine picks the head greedily, not by flex's longest-match rule".to_string());
+            }
+            Dialect::Rust | Dialect::PosixEre => {
+                emitter.issue("trailing context", "This dialect has no lookahead; the '/...' part was dropped");
+            }
+        },
+        None => {}
+    }
+
+    if dialect == Dialect::JavaScript {
+        flags.push('u');
+    }
+    if emitter.uses_alternation && dialect != Dialect::PosixEre {
+        notes.push("Alternation is leftmost-first here; flex always takes the longest alternative".to_string());
+    }
+    if emitter.uses_property && dialect == Dialect::Pcre {
+        notes.push("Binary Unicode properties such as \\p{Alphabetic} need PCRE2 10.40 or later".to_string());
+    }
+    if (emitter.uses_non_ascii || emitter.uses_property) && dialect == Dialect::Pcre {
+        notes.push("Compile with PCRE2_UTF so non-ASCII characters are matched as code points".to_string());
+    }
+    let faithful = emitter.issues.is_empty();
+    Ok(Translation { dialect, pattern: out, flags, faithful, issues: emitter.issues, notes })
+}
+
+/// The same pattern in every supported dialect.
+pub fn translate_all(src: &str, definitions: &BTreeMap<String, String>) -> Result<Vec<Translation>, String> {
+    ALL_DIALECTS.iter().map(|&d| translate_pattern(src, definitions, d)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn translate(src: &str, dialect: Dialect) -> Translation {
+        let definitions: BTreeMap<String, String> = [("DIGIT".to_string(), "[0-9]".to_string())].into_iter().collect();
+        translate_pattern(src, &definitions, dialect).unwrap()
+    }
+
+    #[test]
+    fn identifiers_and_numbers_translate_everywhere() {
+        for t in translate_all("[a-z_][a-z0-9_]*", &BTreeMap::new()).unwrap() {
+            assert_eq!(t.pattern, "[_a-z][0-9_a-z]*", "{:?}", t.dialect);
+            assert!(t.faithful && t.issues.is_empty() && t.notes.is_empty());
+        }
+        let t = translate("{DIGIT}+\\.{DIGIT}*", Dialect::Pcre);
+        assert_eq!(t.pattern, "[0-9]+\\.[0-9]*");
+        assert_eq!(translate("[[:alpha:]]+", Dialect::PosixEre).pattern, "[A-Za-z]+");
+        assert_eq!(translate("\"a.b\"", Dialect::Rust).pattern, "a\\.b");
+        assert_eq!(translate("x", Dialect::JavaScript).flags, "u");
+    }
+
+    #[test]
+    fn trailing_context_becomes_a_lookahead_or_is_dropped() {
+        let pcre = translate("^if/[ (]", Dialect::Pcre);
+        assert_eq!(pcre.pattern, "(?m)^if(?=[\\x{20}(])");
+        assert!(pcre.faithful);
+        assert!(pcre.notes[0].starts_with("Trailing context became a lookahead"));
+
+        let js = translate("^if/[ (]", Dialect::JavaScript);
+        assert_eq!((js.pattern.as_str(), js.flags.as_str()), ("^if(?=[\\u{20}(])", "mu"));
+
+        let rust = translate("^if/[ (]", Dialect::Rust);
+        assert_eq!(rust.pattern, "(?m)^if");
+        assert!(!rust.faithful);
+        assert_eq!(rust.issues[0].message, "This dialect has no lookahead; the '/...' part was dropped");
+    }
+
+    #[test]
+    fn end_of_line_anchor_differs_by_engine() {
+        assert_eq!(translate("x$", Dialect::Pcre).pattern, "x(?=\\n)");
+        let posix = translate("x$", Dialect::PosixEre);
+        assert_eq!(posix.pattern, "x$");
+        assert_eq!(posix.notes, ["'$' matches before a newline only when compiled with REG_NEWLINE"]);
+        let rust = translate("x$", Dialect::Rust);
+        assert_eq!(rust.pattern, "x(?m:$)");
+        assert!(!rust.faithful);
+    }
+
+    #[test]
+    fn alternation_notes_leftmost_first_semantics() {
+        assert!(translate("a{2,3}|b", Dialect::PosixEre).notes.is_empty());
+        let pcre = translate("a{2,3}|b", Dialect::Pcre);
+        assert_eq!(pcre.pattern, "a{2,3}|b");
+        assert_eq!(pcre.notes, ["Alternation is leftmost-first here; flex always takes the longest alternative"]);
+    }
+
+    #[test]
+    fn classes_and_properties() {
+        assert_eq!(translate("[^\\n]+", Dialect::Pcre).pattern, ".+");
+        assert_eq!(translate("[^\\n]+", Dialect::JavaScript).pattern, "[^\\n]+");
+        assert_eq!(translate("\\p{L}+", Dialect::Rust).pattern, "\\p{Alphabetic}+");
+        assert_eq!(translate("\\p{L}+", Dialect::Pcre).notes.len(), 2);
+    }
+
+    #[test]
+    fn eof_rules_and_bad_patterns() {
+        let eof = translate("<<EOF>>", Dialect::JavaScript);
+        assert!(!eof.faithful && eof.pattern.is_empty());
+        assert_eq!(eof.issues[0].message, "End-of-file rules have no regex equivalent");
+
+        let err = translate_pattern("{NOPE}", &BTreeMap::new(), Dialect::Pcre).unwrap_err();
+        assert!(err.starts_with("Undefined definition '{NOPE}'"), "{}", err);
+    }
+}
@@ -0,0 +1,199 @@
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+
+use crate::action_return_token;
+use crate::automaton::{representative, AutomatonRule, Dfa, LexerAutomaton};
+use crate::interpreter::{action_begin_condition, run_flex_from, ScanResult};
+
+// --- LEXER EQUIVALENCE ---
+// Walks the product of two minimized DFAs breadth-first. Two scanners agree
+// on every input exactly when, for every string, both accept it with the
+// same outcome or both reject it; the first pair of states where that fails
+// gives a shortest distinguishing input.
+
+/// Product states the search may visit before giving up as inconclusive.
+const MAX_PRODUCT_STATES: usize = 200_000;
+
+/// What a rule does from the parser's point of view.
+fn outcome(rule: &AutomatonRule) -> String {
+    let mut out = match action_return_token(&rule.action) {
+        Some(token) => format!("return {}", token),
+        None => {
+            let action = rule.action.trim().trim_start_matches('{').trim_end_matches('}');
+            let action: String = action.split_whitespace().collect::<Vec<_>>().join(" ");
+            if action.is_empty() || action == ";" { "skip".to_string() } else { action }
+        }
+    };
+    if let Some(next) = action_begin_condition(&rule.action) {
+        out.push_str(&format!(", BEGIN {}", next));
+    }
+    out
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct Difference {
+    pub start_condition: String,
+    /// Whether the input starts at the beginning of a line.
+    pub at_line_start: bool,
+    pub input: String,
+    /// Rule outcome each spec gives `input` as a single lexeme (`None` = no match);
+    /// with trailing context on either side, also the length of yytext.
+    pub left_outcome: Option<String>,
+    pub right_outcome: Option<String>,
+    pub left_rule: Option<usize>,
+    pub right_rule: Option<usize>,
+    pub left: ScanResult,
+    pub right: ScanResult,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct EquivalenceReport {
+    pub equivalent: bool,
+    /// The search hit MAX_PRODUCT_STATES before finding a difference.
+    pub inconclusive: bool,
+    pub conditions_only_left: Vec<String>,
+    pub conditions_only_right: Vec<String>,
+    pub difference: Option<Difference>,
+    /// Product states visited across all start conditions.
+    pub explored_states: usize,
+}
+
+/// Class boundaries of both DFAs merged: each interval is uniform for both.
+fn merged_alphabet(a: &Dfa, b: &Dfa) -> Vec<(char, char)> {
+    let starts: BTreeSet<char> = a.classes.iter().chain(b.classes.iter()).map(|&(lo, _)| lo).collect();
+    let starts: Vec<char> = starts.into_iter().collect();
+    starts
+        .iter()
+        .enumerate()
+        .map(|(i, &lo)| match starts.get(i + 1) {
+            Some(&next) => (lo, crate::regex::char_before(next).unwrap()),
+            None => (lo, '\u{10FFFF}'),
+        })
+        .collect()
+}
+
+type Pair = (Option<usize>, Option<usize>);
+
+struct Search<'a> {
+    left: &'a LexerAutomaton,
+    right: &'a LexerAutomaton,
+    /// One readable character per merged class, printable ones first.
+    letters: Vec<char>,
+    explored: usize,
+    /// Set once MAX_PRODUCT_STATES cut a search short.
+    exhausted: bool,
+}
+
+fn accept(automaton: &LexerAutomaton, state: Option<usize>) -> Option<usize> {
+    state.and_then(|s| automaton.dfa.states[s].accept)
+}
+
+/// Outcomes of both sides after reading `input` into `pair`. A rule with
+/// trailing context leaves yytext shorter than the match, so then both get
+/// their yytext length, computed from `input` only when it is needed.
+fn labels(left: &LexerAutomaton, right: &LexerAutomaton, pair: Pair, input: impl FnOnce() -> Vec<char>) -> (Option<String>, Option<String>) {
+    let rules = (accept(left, pair.0).map(|r| &left.rules[r]), accept(right, pair.1).map(|r| &right.rules[r]));
+    let label = |rule: Option<&AutomatonRule>| rule.map(outcome);
+    if ![rules.0, rules.1].iter().flatten().any(|rule| rule.parsed.trailing.is_some()) {
+        return (label(rules.0), label(rules.1));
+    }
+    let input = input();
+    let with_length = |rule: Option<&AutomatonRule>| rule.map(|rule| format!("{}, yyleng {}", outcome(rule), rule.head_length(&input)));
+    (with_length(rules.0), with_length(rules.1))
+}
+
+impl Search<'_> {
+    /// Shortest non-empty input on which the two scanners disagree.
+    fn shortest_difference(&mut self, start: Pair) -> Option<(String, Pair)> {
+        let mut parent: BTreeMap<Pair, (Pair, char)> = BTreeMap::new();
+        let mut seen = BTreeSet::from([start]);
+        let mut queue = VecDeque::from([start]);
+        let path = |parent: &BTreeMap<Pair, (Pair, char)>, mut cur: Pair| {
+            let mut input = Vec::new();
+            while let Some(&(prev, c)) = parent.get(&cur) {
+                input.push(c);
+                cur = prev;
+            }
+            input.reverse();
+            input
+        };
+        while let Some(pair) = queue.pop_front() {
+            if self.explored >= MAX_PRODUCT_STATES {
+                self.exhausted = true;
+                return None;
+            }
+            self.explored += 1;
+            for &c in &self.letters {
+                let next = (
+                    pair.0.and_then(|s| self.left.dfa.next(s, c)),
+                    pair.1.and_then(|s| self.right.dfa.next(s, c)),
+                );
+                if next == (None, None) || !seen.insert(next) {
+                    continue;
+                }
+                parent.insert(next, (pair, c));
+                let (l, r) = labels(self.left, self.right, next, || path(&parent, next));
+                if l != r {
+                    return Some((path(&parent, next).into_iter().collect(), next));
+                }
+                queue.push_back(next);
+            }
+        }
+        None
+    }
+}
+
+pub fn compare_lexers(left: &LexerAutomaton, right: &LexerAutomaton) -> EquivalenceReport {
+    let names = |a: &LexerAutomaton| a.start_conditions.iter().map(|sc| sc.name.clone()).collect::<BTreeSet<String>>();
+    let (left_names, right_names) = (names(left), names(right));
+    let conditions_only_left: Vec<String> = left_names.difference(&right_names).cloned().collect();
+    let conditions_only_right: Vec<String> = right_names.difference(&left_names).cloned().collect();
+
+    let mut letters: Vec<char> = merged_alphabet(&left.dfa, &right.dfa).iter().map(|&r| representative(&[r])).collect();
+    letters.sort_by_key(|c| !(' '..='~').contains(c));
+    let mut search = Search { left, right, letters, explored: 0, exhausted: false };
+
+    // Shortest difference over every condition, line starts first.
+    let mut best: Option<(String, String, bool, Pair)> = None;
+    let mut checked = BTreeSet::new();
+    for sc in left.start_conditions.iter().chain(right.start_conditions.iter()) {
+        if !checked.insert(sc.name.clone()) {
+            continue;
+        }
+        for at_line_start in [true, false] {
+            let start = (left.dfa.start(&sc.name, at_line_start), right.dfa.start(&sc.name, at_line_start));
+            if let Some((input, pair)) = search.shortest_difference(start) {
+                if best.as_ref().is_none_or(|b| input.chars().count() < b.0.chars().count()) {
+                    best = Some((input, sc.name.clone(), at_line_start, pair));
+                }
+            }
+        }
+    }
+
+    let difference = best.map(|(input, condition, at_line_start, pair)| {
+        let left_rule = accept(left, pair.0);
+        let right_rule = accept(right, pair.1);
+        let (left_outcome, right_outcome) = labels(left, right, pair, || input.chars().collect());
+        Difference {
+            left_outcome,
+            right_outcome,
+            left_rule: left_rule.map(|r| left.rules[r].index),
+            right_rule: right_rule.map(|r| right.rules[r].index),
+            left: run_flex_from(left, &input, &condition, at_line_start),
+            right: run_flex_from(right, &input, &condition, at_line_start),
+            start_condition: condition,
+            at_line_start,
+            input,
+        }
+    });
+    // A difference found before the budget ran out is still a real one.
+    let inconclusive = search.exhausted && difference.is_none();
+    EquivalenceReport {
+        equivalent: !inconclusive && difference.is_none() && conditions_only_left.is_empty() && conditions_only_right.is_empty(),
+        inconclusive,
+        conditions_only_left,
+        conditions_only_right,
+        difference,
+        explored_states: search.explored,
+    }
+}
@@ -0,0 +1,390 @@
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+
+use crate::automaton::{compile_pattern, representative, shortest_match, LexerAutomaton};
+use crate::generator::{sample_regex, Rng};
+use crate::regex::{complement_ranges, normalize_ranges, parse_flex_pattern, property_name, validate_regex, Pattern, Regex};
+
+// --- REGEX EXPLAINER ---
+// Describes a pattern in plain English by walking its regex tree, then backs
+// the description up with example strings that the pattern's own DFA
+// accepts and rejects. Everything is deterministic, so the same pattern
+// always gets the same explanation.
+
+/// Examples listed per side.
+const MAX_EXAMPLES: usize = 5;
+const EXAMPLE_SEED: u64 = 7;
+
+#[derive(Serialize, Clone, Debug)]
+pub struct ExplainNode {
+    pub kind: String,
+    pub description: String,
+    pub children: Vec<ExplainNode>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct Explanation {
+    pub pattern: Option<String>,
+    /// One-sentence plain-English summary.
+    pub summary: String,
+    pub tree: ExplainNode,
+    /// Start conditions, anchors and trailing context, one clause each.
+    pub context: Vec<String>,
+    /// Strings the pattern matches in full (trailing context included).
+    pub matches: Vec<String>,
+    pub non_matches: Vec<String>,
+}
+
+/// A description, plus a plural form when it names a single character kind
+/// (so repetitions can say "one or more digits").
+struct Phrase {
+    text: String,
+    plural: Option<String>,
+}
+
+fn char_name(c: char) -> String {
+    match c {
+        '\n' => "newline".to_string(),
+        '\t' => "tab".to_string(),
+        '\r' => "carriage return".to_string(),
+        ' ' => "space".to_string(),
+        c if c.is_control() || (c as u32) > 0x7e && !c.is_alphanumeric() => format!("U+{:04X}", c as u32),
+        c => format!("'{}'", c),
+    }
+}
+
+fn list(items: &[String], conjunction: &str) -> String {
+    match items {
+        [] => String::new(),
+        [one] => one.clone(),
+        [init @ .., last] => format!("{} {} {}", init.join(", "), conjunction, last),
+    }
+}
+
+fn range_items(ranges: &[(char, char)]) -> Vec<String> {
+    ranges
+        .iter()
+        .flat_map(|&(lo, hi)| match hi as u32 - lo as u32 {
+            0 => vec![char_name(lo)],
+            1 => vec![char_name(lo), char_name(hi)],
+            _ => vec![format!("{} to {}", char_name(lo), char_name(hi))],
+        })
+        .collect()
+}
+
+/// Character class with a common name: `(ranges, singular, plural)`.
+type NamedClass = (&'static [(char, char)], &'static str, &'static str);
+
+fn class_phrase(ranges: &[(char, char)]) -> Phrase {
+    let named = |s: &str, p: &str| Phrase { text: s.to_string(), plural: Some(p.to_string()) };
+    let ranges = normalize_ranges(ranges.to_vec());
+    let known: &[NamedClass] = &[
+        (&[('0', '9')], "a digit", "digits"),
+        (&[('a', 'z')], "a lowercase letter", "lowercase letters"),
+        (&[('A', 'Z')], "an uppercase letter", "uppercase letters"),
+        (&[('A', 'Z'), ('a', 'z')], "a letter", "letters"),
+        (&[('0', '9'), ('A', 'Z'), ('a', 'z')], "a letter or digit", "letters or digits"),
+        (&[('A', 'Z'), ('_', '_'), ('a', 'z')], "a letter or underscore", "letters or underscores"),
+        (&[('0', '9'), ('A', 'Z'), ('_', '_'), ('a', 'z')], "a letter, digit or underscore", "letters, digits or underscores"),
+        (&[('0', '9'), ('A', 'F'), ('a', 'f')], "a hexadecimal digit", "hexadecimal digits"),
+        (&[('\t', '\t'), (' ', ' ')], "a space or tab", "spaces or tabs"),
+        (&[('\t', '\r'), (' ', ' ')], "a whitespace character", "whitespace characters"),
+        (&[('\t', '\n'), (' ', ' ')], "a space, tab or newline", "spaces, tabs or newlines"),
+    ];
+    if let Some((_, s, p)) = known.iter().find(|(r, _, _)| *r == ranges.as_slice()) {
+        return named(s, p);
+    }
+    if ranges.is_empty() {
+        return Phrase { text: "no character at all".to_string(), plural: None };
+    }
+    let complement = complement_ranges(&ranges);
+    if complement.is_empty() {
+        return named("any character", "characters");
+    }
+    if let Some((s, p)) = property_name(&ranges).and_then(property_phrase) {
+        return named(s, p);
+    }
+    if let Some((s, p)) = property_name(&complement).and_then(property_phrase) {
+        return Phrase { text: format!("any character that is not {}", s), plural: Some(format!("characters that are not {}", p)) };
+    }
+    let covered: u64 = ranges.iter().map(|&(lo, hi)| hi as u64 - lo as u64 + 1).sum();
+    let complement_items = range_items(&complement);
+    if covered > 0x10000 && complement_items.len() <= 8 {
+        let except = list(&complement_items, "or");
+        return Phrase { text: format!("any character except {}", except), plural: Some(format!("characters other than {}", except)) };
+    }
+    if ranges.len() == 1 && ranges[0].0 == ranges[0].1 {
+        let name = char_name(ranges[0].0);
+        return Phrase { plural: Some(format!("{} characters", name)), text: name };
+    }
+    let items = range_items(&ranges);
+    if items.len() > 8 {
+        let some = items[..3].join(", ");
+        return Phrase {
+            text: format!("one of {} character ranges (such as {})", items.len(), some),
+            plural: Some(format!("characters from {} ranges (such as {})", items.len(), some)),
+        };
+    }
+    let items = list(&items, "or");
+    Phrase { text: format!("one of {}", items), plural: Some(format!("characters from {}", items)) }
+}
+
+/// Wording for a `\p{..}` class: `(singular, plural)`.
+fn property_phrase(name: &str) -> Option<(&'static str, &'static str)> {
+    Some(match name {
+        "Alphabetic" => ("a Unicode letter", "Unicode letters"),
+        "Lowercase" => ("a lowercase letter in any script", "lowercase letters in any script"),
+        "Uppercase" => ("an uppercase letter in any script", "uppercase letters in any script"),
+        "N" => ("a Unicode number character", "Unicode number characters"),
+        "White_Space" => ("a Unicode whitespace character", "Unicode whitespace characters"),
+        "Cc" => ("a control character", "control characters"),
+        "Alnum" => ("a Unicode letter or number", "Unicode letters or numbers"),
+        "ASCII" => ("an ASCII character", "ASCII characters"),
+        _ => return None,
+    })
+}
+
+fn literal_run(items: &[Regex]) -> Option<String> {
+    items.iter().map(|r| if let Regex::Literal { c } = r { Some(*c) } else { None }).collect()
+}
+
+fn repeat_phrase(inner: &Child, min: u32, max: Option<u32>) -> Phrase {
+    let inner = Phrase { text: inner.0.description.clone(), plural: inner.1.clone() };
+    let count = match (min, max) {
+        (0, Some(1)) => return Phrase { text: format!("optionally {}", inner.text), plural: None },
+        (1, Some(1)) => return inner,
+        (0, None) => "zero or more".to_string(),
+        (1, None) => "one or more".to_string(),
+        (n, None) => format!("at least {}", n),
+        (n, Some(m)) if n == m => format!("exactly {}", n),
+        (0, Some(m)) => format!("up to {}", m),
+        (n, Some(m)) => format!("between {} and {}", n, m),
+    };
+    let text = match inner.plural {
+        Some(p) => format!("{} {}", count, p),
+        None => format!("{} repetitions of ({})", count, inner.text),
+    };
+    Phrase { text, plural: None }
+}
+
+/// A described subtree, with the plural form of its phrase.
+type Child = (ExplainNode, Option<String>);
+
+/// Describes one node from its children's descriptions, so each subtree is
+/// described once.
+fn describe(re: &Regex, depth: usize, children: &[Child]) -> Phrase {
+    match re {
+        Regex::Empty => Phrase { text: "nothing (the empty string)".to_string(), plural: None },
+        Regex::Literal { c } => {
+            let name = char_name(*c);
+            let plural = if *c == '\n' { "newlines".to_string() } else { format!("{} characters", name) };
+            Phrase { text: if *c == '\n' { "a newline".to_string() } else { name }, plural: Some(plural) }
+        }
+        Regex::Class { ranges } => class_phrase(ranges),
+        Regex::Concat { items } => {
+            if let Some(word) = literal_run(items) {
+                return Phrase { text: format!("the text {:?}", word), plural: None };
+            }
+            // Runs of literals read better as one quoted string.
+            let mut parts: Vec<(bool, String)> = Vec::new();
+            let mut i = 0;
+            while i < items.len() {
+                let run = items[i..].iter().take_while(|r| matches!(r, Regex::Literal { .. })).count();
+                if run >= 2 {
+                    parts.push((false, format!("{:?}", literal_run(&items[i..i + run]).unwrap())));
+                    i += run;
+                    continue;
+                }
+                let part = match &items[i] {
+                    Regex::Repeat { min: 0, max: Some(1), .. } if i > 0 => (true, children[i].0.children[0].description.clone()),
+                    _ => (false, children[i].0.description.clone()),
+                };
+                parts.push(part);
+                i += 1;
+            }
+            let joiner = if depth == 0 { ", followed by " } else { " and " };
+            let mut text = parts[0].1.clone();
+            for (optional, part) in &parts[1..] {
+                text.push_str(if *optional { ", optionally followed by " } else { joiner });
+                text.push_str(part);
+            }
+            Phrase { text, plural: None }
+        }
+        Regex::Alt { items } => {
+            let words: Option<Vec<String>> = items
+                .iter()
+                .map(|r| match r {
+                    Regex::Literal { c } if c.is_alphanumeric() => Some(format!("{:?}", c.to_string())),
+                    Regex::Concat { items } => literal_run(items).map(|w| format!("{:?}", w)),
+                    _ => None,
+                })
+                .collect();
+            if let Some(words) = words {
+                return Phrase { text: format!("one of the words {}", list(&words, "or")), plural: None };
+            }
+            let parts: Vec<String> = children.iter().map(|c| c.0.description.clone()).collect();
+            let text = if parts.len() == 2 { format!("either {} or {}", parts[0], parts[1]) } else { format!("one of: {}", list(&parts, "or")) };
+            Phrase { text, plural: None }
+        }
+        Regex::Repeat { min, max, .. } => repeat_phrase(&children[0], *min, *max),
+    }
+}
+
+fn tree(re: &Regex, depth: usize) -> ExplainNode {
+    subtree(re, depth).0
+}
+
+fn subtree(re: &Regex, depth: usize) -> Child {
+    let (kind, children): (&str, Vec<Child>) = match re {
+        Regex::Empty => ("empty", Vec::new()),
+        Regex::Literal { .. } => ("literal", Vec::new()),
+        Regex::Class { .. } => ("class", Vec::new()),
+        Regex::Concat { items } => ("concat", items.iter().map(|r| subtree(r, depth + 1)).collect()),
+        Regex::Alt { items } => ("alternation", items.iter().map(|r| subtree(r, depth + 1)).collect()),
+        Regex::Repeat { inner, .. } => ("repeat", vec![subtree(inner, depth + 1)]),
+    };
+    let phrase = describe(re, depth, &children);
+    let children = children.into_iter().map(|c| c.0).collect();
+    (ExplainNode { kind: kind.to_string(), description: phrase.text, children }, phrase.plural)
+}
+
+fn context_clauses(pattern: &Pattern) -> Vec<String> {
+    let mut out = Vec::new();
+    match pattern.start_conditions.as_slice() {
+        [] => {}
+        [sc] if sc == "*" => out.push("in every start condition".to_string()),
+        [sc] => out.push(format!("only in start condition {}", sc)),
+        scs => out.push(format!("only in start conditions {}", list(scs, "and"))),
+    }
+    if pattern.bol {
+        out.push("only at the start of a line".to_string());
+    }
+    match &pattern.trailing {
+        Some(Regex::Literal { c: '\n' }) => out.push("only at the end of a line".to_string()),
+        Some(t) => out.push(format!("only when followed by {} (which is not part of the match)", tree(t, 1).description)),
+        None => {}
+    }
+    out
+}
+
+fn capitalize(s: &str) -> String {
+    let mut chars = s.chars();
+    match chars.next() {
+        Some(c) => c.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+fn accepts(automaton: &LexerAutomaton, text: &str) -> bool {
+    let dfa = &automaton.dfa;
+    let Some(mut state) = dfa.start("INITIAL", true) else { return false };
+    for c in text.chars() {
+        match dfa.next(state, c) {
+            Some(next) => state = next,
+            None => return false,
+        }
+    }
+    dfa.states[state].accept.is_some()
+}
+
+/// Matching strings: the shortest one, then seeded samples of the regex.
+fn matching_examples(automaton: &LexerAutomaton, full: &Regex) -> Vec<String> {
+    let mut out: Vec<String> = shortest_match(full).into_iter().collect();
+    let mut rng = Rng::new(EXAMPLE_SEED);
+    for _ in 0..MAX_EXAMPLES * 8 {
+        if out.len() >= MAX_EXAMPLES {
+            break;
+        }
+        let sample = sample_regex(full, &mut rng);
+        if !out.contains(&sample) && accepts(automaton, &sample) {
+            out.push(sample);
+        }
+    }
+    out
+}
+
+/// Rejected strings, shortest first: prefixes stuck in non-accepting DFA
+/// states, and prefixes extended by a character with no transition.
+fn non_matching_examples(automaton: &LexerAutomaton, matches: &[String]) -> Vec<String> {
+    let dfa = &automaton.dfa;
+    let Some(start) = dfa.start("INITIAL", true) else { return Vec::new() };
+    let mut class_order: Vec<usize> = (0..dfa.classes.len()).collect();
+    class_order.sort_by_key(|&c| !(' '..='~').contains(&representative(&[dfa.classes[c]])));
+
+    let mut out: Vec<String> = Vec::new();
+    let mut seen = BTreeSet::from([start]);
+    let mut queue = VecDeque::from([(start, String::new())]);
+    while let Some((s, text)) = queue.pop_front() {
+        if out.len() >= MAX_EXAMPLES {
+            break;
+        }
+        if dfa.states[s].accept.is_none() {
+            out.push(text.clone());
+        }
+        let mut dead_added = false;
+        for &c in &class_order {
+            let mut next_text = text.clone();
+            next_text.push(representative(&[dfa.classes[c]]));
+            match dfa.states[s].transitions.get(&c) {
+                None if !dead_added => {
+                    dead_added = true;
+                    out.push(next_text);
+                }
+                Some(&t) if seen.insert(t) => queue.push_back((t, next_text)),
+                _ => {}
+            }
+        }
+    }
+    // Near misses: a match with its last character dropped or doubled.
+    for m in matches {
+        let mut chars: Vec<char> = m.chars().collect();
+        if let Some(&last) = chars.last() {
+            chars.pop();
+            let shorter: String = chars.iter().collect();
+            let longer = format!("{}{}", m, last);
+            for candidate in [shorter, longer] {
+                if !accepts(automaton, &candidate) && !out.contains(&candidate) {
+                    out.push(candidate);
+                }
+            }
+        }
+    }
+    out.truncate(MAX_EXAMPLES);
+    out
+}
+
+fn explain(source: Option<&str>, pattern: &Pattern) -> Result<Explanation, String> {
+    let tree = tree(&pattern.regex, 0);
+    let context = context_clauses(pattern);
+    let mut summary = capitalize(&tree.description);
+    if !context.is_empty() {
+        summary.push_str(", ");
+        summary.push_str(&list(&context, "and"));
+    }
+    summary.push('.');
+
+    let automaton = compile_pattern(source.unwrap_or(""), pattern)?;
+    let full = automaton.rules[0].full_regex();
+    let matches = matching_examples(&automaton, &full);
+    let non_matches = non_matching_examples(&automaton, &matches);
+    Ok(Explanation { pattern: source.map(str::to_string), summary, tree, context, matches, non_matches })
+}
+
+/// Explains a parsed regex tree (no anchors or trailing context), checked
+/// first since it may come straight from a request.
+pub fn explain_regex(regex: &Regex) -> Result<Explanation, String> {
+    validate_regex(regex)?;
+    let pattern = Pattern { start_conditions: Vec::new(), eof: false, bol: false, regex: regex.clone(), trailing: None };
+    explain(None, &pattern)
+}
+
+/// Explains a flex rule pattern, expanding `{NAME}` references first.
+pub fn explain_pattern(src: &str, definitions: &BTreeMap<String, String>) -> Result<Explanation, String> {
+    let pattern = parse_flex_pattern(src, definitions)?;
+    if pattern.eof {
+        let tree = ExplainNode { kind: "eof".to_string(), description: "the end of the input".to_string(), children: Vec::new() };
+        let summary = "Matches at the end of the input; no characters are consumed.".to_string();
+        return Ok(Explanation { pattern: Some(src.to_string()), summary, tree, context: context_clauses(&pattern), matches: Vec::new(), non_matches: Vec::new() });
+    }
+    explain(Some(src), &pattern)
+}
@@ -0,0 +1,271 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::grammar::Grammar;
+use crate::regex::{flex_definitions, parse_flex_pattern, Regex};
+use crate::{action_return_token, ASTNode};
+
+// --- RANDOM SENTENCE GENERATOR ---
+// Expands the start symbol with a seeded PRNG. Past `max_depth` only the
+// production closest to terminals is chosen, so every walk terminates; with
+// coverage guidance, productions not yet used are preferred.
+
+/// Most sentences one request may ask for.
+pub const MAX_COUNT: usize = 100;
+
+/// Deepest `max_depth` a request may ask for.
+pub const MAX_DEPTH_LIMIT: usize = 16;
+
+/// Sentence length past which the walk heads for the leaves, as if out of
+/// depth: below `max_depth`, length can still grow exponentially.
+const MAX_SENTENCE_TOKENS: usize = 2_000;
+
+/// Longest regex sample in bytes. Bounds like `a{60000}` are cut short here,
+/// so such a sample no longer matches its pattern.
+const MAX_SAMPLE_BYTES: usize = 256;
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct GeneratorOptions {
+    pub seed: u64,
+    pub count: usize,
+    pub max_depth: usize,
+    pub coverage: bool,
+}
+
+impl Default for GeneratorOptions {
+    fn default() -> Self {
+        GeneratorOptions { seed: 1, count: 5, max_depth: 8, coverage: true }
+    }
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct GeneratedSentence {
+    pub tokens: Vec<String>,
+    /// Concrete lexemes, one per token, when a flex spec was supplied.
+    pub lexemes: Option<Vec<String>>,
+    /// Lexemes joined with spaces: ready to paste as `/run` test input.
+    pub text: Option<String>,
+    /// Production indices used, in expansion order.
+    pub productions: Vec<usize>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct GenerationReport {
+    pub sentences: Vec<GeneratedSentence>,
+    pub covered: Vec<String>,
+    pub uncovered: Vec<String>,
+}
+
+/// SplitMix64: tiny, seedable, and stable across builds.
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Rng(seed)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform in `0..n` (`n > 0`).
+    pub fn below(&mut self, n: usize) -> usize {
+        (self.next_u64() % n as u64) as usize
+    }
+}
+
+/// Height of the shallowest derivation tree for each production
+/// (`usize::MAX` if it derives no terminal string).
+fn production_heights(grammar: &Grammar) -> Vec<usize> {
+    let mut nt_height: BTreeMap<&str, usize> = BTreeMap::new();
+    let mut heights = vec![usize::MAX; grammar.productions.len()];
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for (i, p) in grammar.productions.iter().enumerate() {
+            let mut h = 1;
+            for s in &p.rhs {
+                if grammar.is_terminal(s) {
+                    continue;
+                }
+                match nt_height.get(s.as_str()) {
+                    Some(&sh) => h = h.max(sh + 1),
+                    None => {
+                        h = usize::MAX;
+                        break;
+                    }
+                }
+            }
+            if h < heights[i] {
+                heights[i] = h;
+                changed = true;
+                let cur = nt_height.entry(p.lhs.as_str()).or_insert(usize::MAX);
+                if h < *cur {
+                    *cur = h;
+                }
+            }
+        }
+    }
+    heights
+}
+
+struct Walk<'a> {
+    grammar: &'a Grammar,
+    heights: Vec<usize>,
+    uses: Vec<usize>,
+    options: &'a GeneratorOptions,
+    rng: Rng,
+}
+
+impl Walk<'_> {
+    fn choose(&mut self, nt: &str, depth: usize) -> usize {
+        let candidates: Vec<usize> = self
+            .grammar
+            .productions_for(nt)
+            .map(|(i, _)| i)
+            .filter(|&i| self.heights[i] != usize::MAX)
+            .collect();
+        let budget = self.options.max_depth.saturating_sub(depth);
+        let fitting: Vec<usize> = candidates.iter().copied().filter(|&i| self.heights[i] <= budget).collect();
+        if fitting.is_empty() {
+            // Out of depth: take the shallowest production to head for the leaves.
+            return *candidates.iter().min_by_key(|&&i| self.heights[i]).unwrap();
+        }
+        let pool: Vec<usize> = if self.options.coverage {
+            let least = fitting.iter().map(|&i| self.uses[i]).min().unwrap();
+            fitting.into_iter().filter(|&i| self.uses[i] == least).collect()
+        } else {
+            fitting
+        };
+        pool[self.rng.below(pool.len())]
+    }
+
+    fn expand(&mut self, symbol: &str, depth: usize, tokens: &mut Vec<String>, used: &mut Vec<usize>) {
+        if self.grammar.is_terminal(symbol) {
+            tokens.push(symbol.to_string());
+            return;
+        }
+        let pi = self.choose(symbol, if tokens.len() >= MAX_SENTENCE_TOKENS { usize::MAX } else { depth });
+        self.uses[pi] += 1;
+        used.push(pi);
+        let rhs = self.grammar.productions[pi].rhs.clone();
+        for s in &rhs {
+            self.expand(s, depth + 1, tokens, used);
+        }
+    }
+}
+
+/// Random concrete string matched by `regex`, favouring printable ASCII.
+/// Samples stop growing at `MAX_SAMPLE_BYTES`.
+pub fn sample_regex(regex: &Regex, rng: &mut Rng) -> String {
+    let mut out = String::new();
+    sample_into(regex, rng, &mut out);
+    out
+}
+
+fn sample_into(regex: &Regex, rng: &mut Rng, out: &mut String) {
+    match regex {
+        Regex::Empty => {}
+        Regex::Literal { c } => out.push(*c),
+        Regex::Class { ranges } => {
+            let printable: Vec<(char, char)> = ranges
+                .iter()
+                .filter_map(|&(lo, hi)| {
+                    let lo = lo.max(' ');
+                    let hi = hi.min('~');
+                    (lo <= hi).then_some((lo, hi))
+                })
+                .collect();
+            let pool = if printable.is_empty() { ranges.clone() } else { printable };
+            if pool.is_empty() {
+                return;
+            }
+            let total: u64 = pool.iter().map(|&(lo, hi)| hi as u64 - lo as u64 + 1).sum();
+            let mut pick = rng.next_u64() % total;
+            for (lo, hi) in pool {
+                let width = hi as u64 - lo as u64 + 1;
+                if pick < width {
+                    // Stay clear of the surrogate gap in wide ranges.
+                    out.push(char::from_u32(lo as u32 + pick as u32).unwrap_or(lo));
+                    return;
+                }
+                pick -= width;
+            }
+        }
+        Regex::Concat { items } => {
+            for item in items {
+                sample_into(item, rng, out);
+            }
+        }
+        Regex::Alt { items } => sample_into(&items[rng.below(items.len())], rng, out),
+        Regex::Repeat { inner, min, max } => {
+            let extra_cap = max.map_or(3, |m| (m - min).min(3));
+            let count = (min + rng.below(extra_cap as usize + 1) as u32).min(MAX_SAMPLE_BYTES as u32);
+            for _ in 0..count {
+                if out.len() >= MAX_SAMPLE_BYTES {
+                    break;
+                }
+                sample_into(inner, rng, out);
+            }
+        }
+    }
+}
+
+/// Terminal name -> flex pattern of the rule whose action returns it.
+fn lexeme_patterns(flex: &ASTNode) -> BTreeMap<String, Regex> {
+    let mut out = BTreeMap::new();
+    let definitions = flex_definitions(flex);
+    if let ASTNode::FlexFile { rules, .. } = flex {
+        for rule in rules {
+            if let ASTNode::FlexRule { pattern, action, .. } = rule {
+                let (Some(token), Ok(parsed)) = (action_return_token(action), parse_flex_pattern(pattern, &definitions)) else { continue };
+                if !parsed.eof {
+                    out.entry(token).or_insert(parsed.regex);
+                }
+            }
+        }
+    }
+    out
+}
+
+pub fn generate_sentences(grammar: &Grammar, flex: Option<&ASTNode>, options: &GeneratorOptions) -> Result<GenerationReport, String> {
+    let heights = production_heights(grammar);
+    if !grammar.productions_for(&grammar.start).any(|(i, _)| heights[i] != usize::MAX) {
+        return Err(format!("Start symbol '{}' does not derive any terminal string", grammar.start));
+    }
+    let patterns = flex.map(lexeme_patterns);
+
+    let mut walk = Walk { grammar, uses: vec![0; heights.len()], heights, options, rng: Rng::new(options.seed) };
+    let mut sentences = Vec::new();
+    for _ in 0..options.count {
+        let mut tokens = Vec::new();
+        let mut productions = Vec::new();
+        walk.expand(&grammar.start, 0, &mut tokens, &mut productions);
+
+        let lexemes = patterns.as_ref().map(|pats| {
+            tokens
+                .iter()
+                .map(|t| match pats.get(t) {
+                    Some(re) => sample_regex(re, &mut walk.rng),
+                    // Character literals like '+' are their own lexeme.
+                    None if t.len() >= 3 && t.starts_with('\'') && t.ends_with('\'') => t[1..t.len() - 1].to_string(),
+                    None => t.clone(),
+                })
+                .collect::<Vec<String>>()
+        });
+        let text = lexemes.as_ref().map(|l| l.join(" "));
+        sentences.push(GeneratedSentence { tokens, lexemes, text, productions });
+    }
+
+    let (covered, uncovered): (Vec<usize>, Vec<usize>) = (0..grammar.productions.len()).partition(|&i| walk.uses[i] > 0);
+    Ok(GenerationReport {
+        sentences,
+        covered: covered.iter().map(|&i| grammar.productions[i].to_string()).collect(),
+        uncovered: uncovered.iter().map(|&i| grammar.productions[i].to_string()).collect(),
+    })
+}
@@ -0,0 +1,287 @@
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+use crate::ASTNode;
+
+// --- GRAMMAR MODEL ---
+// A flattened view of a BisonFile: one Production per alternative, with the
+// symbol classification (terminal / nonterminal) resolved up front.
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct Production {
+    pub lhs: String,
+    pub rhs: Vec<String>,
+    pub action: Option<String>,
+}
+
+impl fmt::Display for Production {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.rhs.is_empty() {
+            write!(f, "{} -> ε", self.lhs)
+        } else {
+            write!(f, "{} -> {}", self.lhs, self.rhs.join(" "))
+        }
+    }
+}
+
+/// End-of-input marker used in FOLLOW sets and parse tables.
+pub const END_MARKER: &str = "$end";
+
+#[derive(Serialize, Clone, Debug)]
+pub struct Grammar {
+    pub start: String,
+    pub terminals: Vec<String>,
+    pub nonterminals: Vec<String>,
+    pub productions: Vec<Production>,
+}
+
+impl Grammar {
+    pub fn from_ast(ast: &ASTNode) -> Result<Grammar, String> {
+        let (declarations, rules) = match ast {
+            ASTNode::BisonFile { declarations, rules, .. } => (declarations, rules),
+            ASTNode::Error { message, line, column } => {
+                return Err(format!("{} at line {}, column {}", message, line, column))
+            }
+            _ => return Err("Expected a Bison grammar".to_string()),
+        };
+
+        let mut nonterminals = Vec::new();
+        let mut productions = Vec::new();
+        for rule in rules {
+            match rule {
+                ASTNode::BisonGrammarRule { name, alternatives } => {
+                    if !nonterminals.contains(name) {
+                        nonterminals.push(name.clone());
+                    }
+                    for alt in alternatives {
+                        if let ASTNode::BisonAlternative { symbols, action, .. } = alt {
+                            productions.push(Production { lhs: name.clone(), rhs: symbols.clone(), action: action.clone() });
+                        }
+                    }
+                }
+                ASTNode::Error { message, line, column } => {
+                    return Err(format!("{} at line {}, column {}", message, line, column));
+                }
+                _ => {}
+            }
+        }
+        let declared_start = declarations.iter().find_map(|d| match d {
+            ASTNode::BisonStartDecl { symbol } => Some(symbol.clone()),
+            _ => None,
+        });
+        let start = match (declared_start, nonterminals.first()) {
+            (_, None) => return Err("Grammar has no rules".to_string()),
+            (Some(s), _) if !nonterminals.contains(&s) => return Err(format!("Start symbol '{}' has no rules", s)),
+            (Some(s), _) => s,
+            (None, Some(s)) => s.clone(),
+        };
+
+        // Declared tokens come first (in declaration order), then any other
+        // symbol that is used but never defined by a rule (e.g. '+').
+        let mut terminals: Vec<String> = Vec::new();
+        for decl in declarations {
+            if let ASTNode::BisonTokenDecl { names } = decl {
+                for n in names {
+                    if !terminals.contains(n) && !nonterminals.contains(n) {
+                        terminals.push(n.clone());
+                    }
+                }
+            }
+        }
+        for p in &productions {
+            for s in &p.rhs {
+                if !terminals.contains(s) && !nonterminals.contains(s) {
+                    terminals.push(s.clone());
+                }
+            }
+        }
+
+        Ok(Grammar { start, terminals, nonterminals, productions })
+    }
+
+    pub fn is_terminal(&self, symbol: &str) -> bool {
+        !self.nonterminals.iter().any(|n| n == symbol)
+    }
+
+    /// Productions of `lhs`, paired with their index in `self.productions`.
+    pub fn productions_for<'a>(&'a self, lhs: &'a str) -> impl Iterator<Item = (usize, &'a Production)> + 'a {
+        self.productions.iter().enumerate().filter(move |(_, p)| p.lhs == lhs)
+    }
+
+    pub fn nullable(&self) -> BTreeSet<String> {
+        let mut nullable = BTreeSet::new();
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for p in &self.productions {
+                if !nullable.contains(&p.lhs) && p.rhs.iter().all(|s| nullable.contains(s)) {
+                    nullable.insert(p.lhs.clone());
+                    changed = true;
+                }
+            }
+        }
+        nullable
+    }
+
+    /// Nonterminals that derive at least one terminal string.
+    pub fn productive(&self) -> BTreeSet<String> {
+        let mut productive = BTreeSet::new();
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for p in &self.productions {
+                if !productive.contains(&p.lhs)
+                    && p.rhs.iter().all(|s| self.is_terminal(s) || productive.contains(s))
+                {
+                    productive.insert(p.lhs.clone());
+                    changed = true;
+                }
+            }
+        }
+        productive
+    }
+
+    /// Symbols reachable from the start symbol.
+    pub fn reachable(&self) -> BTreeSet<String> {
+        let mut reachable = BTreeSet::new();
+        let mut stack = vec![self.start.clone()];
+        while let Some(s) = stack.pop() {
+            if !reachable.insert(s.clone()) {
+                continue;
+            }
+            for (_, p) in self.productions_for(&s) {
+                for sym in &p.rhs {
+                    if !reachable.contains(sym) {
+                        stack.push(sym.clone());
+                    }
+                }
+            }
+        }
+        reachable
+    }
+
+    /// Rebuilds a BisonFile AST: identifier terminals become a `%token`
+    /// declaration and productions are regrouped under their left-hand side.
+    pub fn to_ast(&self) -> ASTNode {
+        let names: Vec<String> = self.terminals.iter().filter(|t| is_token_name(t)).cloned().collect();
+        let declarations = if names.is_empty() { Vec::new() } else { vec![ASTNode::BisonTokenDecl { names }] };
+        let rules = self
+            .nonterminals
+            .iter()
+            .map(|nt| ASTNode::BisonGrammarRule {
+                name: nt.clone(),
+                alternatives: self
+                    .productions_for(nt)
+                    .map(|(_, p)| ASTNode::BisonAlternative { symbols: p.rhs.clone(), action: p.action.clone(), prec: None, action_line: 0 })
+                    .collect(),
+            })
+            .collect();
+        ASTNode::BisonFile { prologue: None, prologue_line: 0, declarations, rules, epilogue: None, epilogue_line: 0 }
+    }
+
+    /// Pretty-prints the grammar as Bison source that `parse_bison` reads back.
+    pub fn to_bison_source(&self) -> String {
+        let mut out = String::new();
+        let names: Vec<&str> = self.terminals.iter().filter(|t| is_token_name(t)).map(|t| t.as_str()).collect();
+        if !names.is_empty() {
+            out.push_str(&format!("%token {}\n", names.join(" ")));
+        }
+        out.push_str("%%\n");
+        for nt in &self.nonterminals {
+            out.push_str(nt);
+            out.push('\n');
+            for (i, (_, p)) in self.productions_for(nt).enumerate() {
+                out.push_str(if i == 0 { "    : " } else { "    | " });
+                if p.rhs.is_empty() {
+                    out.push_str("%empty");
+                } else {
+                    out.push_str(&p.rhs.join(" "));
+                }
+                if let Some(act) = &p.action {
+                    out.push_str(&format!(" {{ {} }}", act));
+                }
+                out.push('\n');
+            }
+            out.push_str("    ;\n\n");
+        }
+        out.push_str("%%\n");
+        out
+    }
+
+    pub fn symbol_sets(&self) -> SymbolSets {
+        let nullable = self.nullable();
+        let mut sets = SymbolSets { nullable, first: BTreeMap::new(), follow: BTreeMap::new() };
+        for nt in &self.nonterminals {
+            sets.first.insert(nt.clone(), BTreeSet::new());
+            sets.follow.insert(nt.clone(), BTreeSet::new());
+        }
+
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for p in &self.productions {
+                let (first, _) = sets.first_of(self, &p.rhs);
+                let entry = sets.first.get_mut(&p.lhs).unwrap();
+                let before = entry.len();
+                entry.extend(first);
+                changed |= entry.len() != before;
+            }
+        }
+
+        sets.follow.get_mut(&self.start).unwrap().insert(END_MARKER.to_string());
+        changed = true;
+        while changed {
+            changed = false;
+            for p in &self.productions {
+                for (i, sym) in p.rhs.iter().enumerate() {
+                    if self.is_terminal(sym) {
+                        continue;
+                    }
+                    let (mut follow, rest_nullable) = sets.first_of(self, &p.rhs[i + 1..]);
+                    if rest_nullable {
+                        follow.extend(sets.follow[&p.lhs].iter().cloned());
+                    }
+                    let entry = sets.follow.get_mut(sym).unwrap();
+                    let before = entry.len();
+                    entry.extend(follow);
+                    changed |= entry.len() != before;
+                }
+            }
+        }
+        sets
+    }
+}
+
+/// Terminals that need a `%token` declaration (not quoted literals or `error`).
+fn is_token_name(symbol: &str) -> bool {
+    !symbol.starts_with('\'') && !symbol.starts_with('"') && symbol != "error"
+}
+
+/// NULLABLE, FIRST and FOLLOW for every nonterminal of a grammar.
+#[derive(Serialize, Clone, Debug)]
+pub struct SymbolSets {
+    pub nullable: BTreeSet<String>,
+    pub first: BTreeMap<String, BTreeSet<String>>,
+    pub follow: BTreeMap<String, BTreeSet<String>>,
+}
+
+impl SymbolSets {
+    /// FIRST of a symbol sequence, and whether the whole sequence is nullable.
+    pub fn first_of(&self, grammar: &Grammar, seq: &[String]) -> (BTreeSet<String>, bool) {
+        let mut out = BTreeSet::new();
+        for sym in seq {
+            if grammar.is_terminal(sym) {
+                out.insert(sym.clone());
+                return (out, false);
+            }
+            if let Some(f) = self.first.get(sym) {
+                out.extend(f.iter().cloned());
+            }
+            if !self.nullable.contains(sym) {
+                return (out, false);
+            }
+        }
+        (out, true)
+    }
+}
@@ -0,0 +1,154 @@
+use serde::Serialize;
+
+use crate::action_return_token;
+use crate::automaton::LexerAutomaton;
+
+// --- FLEX INTERPRETER ---
+// Scans sample input with the compiled DFA the way a generated yylex() would:
+// longest match, earliest rule on ties, `^` rules only at line starts,
+// `BEGIN` in actions switching start conditions, and the default rule
+// echoing anything no rule matches.
+
+/// Stops runaway scans of very large inputs.
+const MAX_LEXEMES: usize = 10_000;
+
+/// Longest sample input accepted for a scan: matching rescans from every
+/// position, so a scan can take time quadratic in the input length.
+pub const MAX_SCAN_INPUT: usize = 16 * 1024;
+
+#[derive(Serialize, Clone, Debug)]
+pub struct Lexeme {
+    pub text: String,
+    /// Rule index in the FlexFile; `None` when the default rule echoed the text.
+    pub rule: Option<usize>,
+    pub pattern: Option<String>,
+    pub start_condition: String,
+    /// 1-based; `column` counts Unicode scalar values.
+    pub line: usize,
+    pub column: usize,
+    /// Same column in UTF-16 code units, as JavaScript editors count.
+    pub utf16_column: usize,
+    /// UTF-8 byte span in the input, as the generated C scanner sees it.
+    pub byte_offset: usize,
+    pub byte_length: usize,
+    /// Token handed to the parser by `return TOKEN;`, if any.
+    pub token: Option<String>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct ScanResult {
+    pub lexemes: Vec<Lexeme>,
+    /// Text written by the default ECHO rule.
+    pub echoed: String,
+    pub final_start_condition: String,
+    pub truncated: bool,
+}
+
+/// Target of `BEGIN(NAME)` / `BEGIN NAME` in an action (`0` means INITIAL).
+/// With several, the last one wins, as when the action runs straight through.
+pub fn action_begin_condition(action: &str) -> Option<String> {
+    let is_ident = |c: char| c.is_alphanumeric() || c == '_';
+    action.rmatch_indices("BEGIN").find_map(|(idx, _)| {
+        let rest = &action[idx + "BEGIN".len()..];
+        if action[..idx].ends_with(is_ident) || rest.starts_with(is_ident) {
+            return None;
+        }
+        let rest = rest.trim_start().trim_start_matches('(').trim_start();
+        let name: String = rest.chars().take_while(|&c| is_ident(c)).collect();
+        match name.as_str() {
+            "" => None,
+            "0" => Some("INITIAL".to_string()),
+            _ => Some(name),
+        }
+    })
+}
+
+pub fn run_flex(automaton: &LexerAutomaton, input: &str) -> ScanResult {
+    run_flex_from(automaton, input, "INITIAL", true)
+}
+
+/// Like `run_flex`, starting in `condition` and, unless `at_line_start`,
+/// as if the input continued a line.
+pub fn run_flex_from(automaton: &LexerAutomaton, input: &str, condition: &str, at_line_start: bool) -> ScanResult {
+    let chars: Vec<char> = input.chars().collect();
+    let dfa = &automaton.dfa;
+    let mut condition = condition.to_string();
+    let mut lexemes = Vec::new();
+    let mut echoed = String::new();
+    let (mut line, mut column, mut utf16_column) = (1, 1, 1);
+    let mut byte_offset = 0;
+    let mut pos = 0;
+
+    while pos < chars.len() {
+        if lexemes.len() >= MAX_LEXEMES {
+            return ScanResult { lexemes, echoed, final_start_condition: condition, truncated: true };
+        }
+        let bol = if pos == 0 { at_line_start } else { chars[pos - 1] == '\n' };
+        let matched = dfa.start(&condition, bol).and_then(|s| dfa.longest_match(s, &chars[pos..]));
+
+        let (len, lexeme) = match matched {
+            // Empty matches would loop forever; flex falls back to the default rule too.
+            Some((len, ri)) if len > 0 => {
+                let rule = &automaton.rules[ri];
+                let len = rule.head_length(&chars[pos..pos + len]).max(1);
+                let text: String = chars[pos..pos + len].iter().collect();
+                // `return yytext[0];` / `return *yytext;` pass the character itself.
+                let returns_char = rule.action.contains("return *yytext");
+                let token = match action_return_token(&rule.action) {
+                    Some(t) if t == "yytext" => Some(format!("'{}'", chars[pos])),
+                    None if returns_char => Some(format!("'{}'", chars[pos])),
+                    other => other,
+                };
+                let lexeme = Lexeme {
+                    text,
+                    rule: Some(rule.index),
+                    pattern: Some(rule.pattern.clone()),
+                    start_condition: condition.clone(),
+                    line,
+                    column,
+                    utf16_column,
+                    byte_offset,
+                    byte_length: chars[pos..pos + len].iter().map(|c| c.len_utf8()).sum(),
+                    token,
+                };
+                if let Some(next) = action_begin_condition(&rule.action) {
+                    if automaton.start_conditions.iter().any(|sc| sc.name == next) {
+                        condition = next;
+                    }
+                }
+                (len, lexeme)
+            }
+            _ => {
+                echoed.push(chars[pos]);
+                let lexeme = Lexeme {
+                    text: chars[pos].to_string(),
+                    rule: None,
+                    pattern: None,
+                    start_condition: condition.clone(),
+                    line,
+                    column,
+                    utf16_column,
+                    byte_offset,
+                    byte_length: chars[pos].len_utf8(),
+                    token: None,
+                };
+                (1, lexeme)
+            }
+        };
+        lexemes.push(lexeme);
+
+        for &c in &chars[pos..pos + len] {
+            byte_offset += c.len_utf8();
+            if c == '\n' {
+                line += 1;
+                column = 1;
+                utf16_column = 1;
+            } else {
+                column += 1;
+                utf16_column += c.len_utf16();
+            }
+        }
+        pos += len;
+    }
+    ScanResult { lexemes, echoed, final_start_condition: condition, truncated: false }
+}
@@ -0,0 +1,422 @@
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::grammar::{Grammar, END_MARKER};
+use crate::ASTNode;
+
+// --- LALR(1) TABLES ---
+// LR(0) item sets over the grammar augmented with `$accept: start $end`,
+// with lookaheads spread by the spontaneous/propagated method (Dragon book
+// 4.7.5). Conflicts are settled as bison settles them: precedence and
+// associativity decide shift/reduce when both sides have a level, otherwise
+// shift wins and the earlier rule wins a reduce/reduce conflict. Each state's
+// most common reduction becomes its default, so tables stay small and states
+// with nothing else to do reduce without reading a lookahead.
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Associativity {
+    Left,
+    Right,
+    Nonassoc,
+    Precedence,
+}
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum LrAction {
+    Shift { state: usize },
+    Reduce { rule: usize },
+    /// Explicit error from a `%nonassoc` operator.
+    Error,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct LrRule {
+    pub lhs: usize,
+    pub rhs: Vec<usize>,
+    /// Index into `Grammar::productions`; `None` for `$accept: start $end`.
+    pub production: Option<usize>,
+    /// Level from `%prec`, else from the rule's last terminal.
+    pub precedence: Option<usize>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct LrConflict {
+    pub state: usize,
+    pub symbol: String,
+    /// "shift/reduce" or "reduce/reduce".
+    pub kind: String,
+    pub rules: Vec<usize>,
+    pub resolution: String,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct LalrTables {
+    pub grammar: Grammar,
+    /// Terminals (`$end`, `error`, `$undefined`, then the grammar's), then
+    /// nonterminals (`$accept`, then the grammar's).
+    pub symbols: Vec<String>,
+    pub terminal_count: usize,
+    /// Precedence level and associativity of declared terminals.
+    pub precedence: BTreeMap<usize, (usize, Associativity)>,
+    pub rules: Vec<LrRule>,
+    /// Per state: explicit actions by terminal.
+    pub actions: Vec<BTreeMap<usize, LrAction>>,
+    /// Per state: reduction taken on any lookahead without an explicit action.
+    pub default_reductions: Vec<Option<usize>>,
+    /// Per state: successor by nonterminal.
+    pub gotos: Vec<BTreeMap<usize, usize>>,
+    /// Symbol shifted to enter each state.
+    pub accessing_symbol: Vec<usize>,
+    /// Entered after shifting `$end`: reaching it accepts.
+    pub final_state: usize,
+    pub conflicts: Vec<LrConflict>,
+}
+
+/// Symbol number of the `error` token.
+pub const ERROR_SYMBOL: usize = 1;
+/// Symbol number of `$undefined`.
+pub const UNDEFINED_SYMBOL: usize = 2;
+
+/// Stands for "inherited" while lookaheads are being traced.
+const DUMMY: usize = usize::MAX;
+
+type Item = (usize, usize);
+
+struct Builder {
+    rules: Vec<LrRule>,
+    terminal_count: usize,
+    by_lhs: Vec<Vec<usize>>,
+    nullable: Vec<bool>,
+    first: Vec<BTreeSet<usize>>,
+}
+
+impl Builder {
+    fn is_terminal(&self, sym: usize) -> bool {
+        sym < self.terminal_count
+    }
+
+    /// FIRST of `seq`, and whether all of it can be empty.
+    fn first_of(&self, seq: &[usize]) -> (BTreeSet<usize>, bool) {
+        let mut out = BTreeSet::new();
+        for &sym in seq {
+            out.extend(self.first[sym].iter().copied());
+            if !self.nullable[sym] {
+                return (out, false);
+            }
+        }
+        (out, true)
+    }
+
+    fn closure0(&self, kernel: &[Item]) -> BTreeSet<Item> {
+        let mut items: BTreeSet<Item> = kernel.iter().copied().collect();
+        let mut work: Vec<Item> = kernel.to_vec();
+        while let Some((r, d)) = work.pop() {
+            if let Some(&sym) = self.rules[r].rhs.get(d) {
+                for &q in &self.by_lhs[sym] {
+                    if items.insert((q, 0)) {
+                        work.push((q, 0));
+                    }
+                }
+            }
+        }
+        items
+    }
+
+    /// LR(1) closure, lookaheads grouped per item.
+    fn closure1(&self, seed: Vec<(Item, BTreeSet<usize>)>) -> BTreeMap<Item, BTreeSet<usize>> {
+        let mut work: Vec<Item> = seed.iter().map(|(item, _)| *item).collect();
+        let mut items: BTreeMap<Item, BTreeSet<usize>> = seed.into_iter().collect();
+        while let Some((r, d)) = work.pop() {
+            let rhs = &self.rules[r].rhs;
+            let Some(&sym) = rhs.get(d) else { continue };
+            if self.is_terminal(sym) {
+                continue;
+            }
+            let (mut lookahead, nullable) = self.first_of(&rhs[d + 1..]);
+            if nullable {
+                lookahead.extend(items[&(r, d)].iter().copied());
+            }
+            for &q in &self.by_lhs[sym] {
+                let new = !items.contains_key(&(q, 0));
+                let entry = items.entry((q, 0)).or_default();
+                let before = entry.len();
+                entry.extend(lookahead.iter().copied());
+                if new || entry.len() != before {
+                    work.push((q, 0));
+                }
+            }
+        }
+        items
+    }
+}
+
+/// `%left`/`%right`/`%nonassoc`/`%precedence` levels, lowest first.
+fn declared_precedence(ast: &ASTNode) -> BTreeMap<String, (usize, Associativity)> {
+    let mut out = BTreeMap::new();
+    if let ASTNode::BisonFile { declarations, .. } = ast {
+        let levels = declarations.iter().filter_map(|d| match d {
+            ASTNode::BisonPrecedenceDecl { associativity, symbols } => Some((associativity, symbols)),
+            _ => None,
+        });
+        for (i, (associativity, symbols)) in levels.enumerate() {
+            let assoc = match associativity.as_str() {
+                "left" => Associativity::Left,
+                "right" => Associativity::Right,
+                "nonassoc" => Associativity::Nonassoc,
+                _ => Associativity::Precedence,
+            };
+            for s in symbols {
+                out.insert(s.clone(), (i + 1, assoc));
+            }
+        }
+    }
+    out
+}
+
+/// `%prec` of every production, in `Grammar::productions` order.
+fn rule_prec_overrides(ast: &ASTNode) -> Vec<Option<String>> {
+    let mut out = Vec::new();
+    if let ASTNode::BisonFile { rules, .. } = ast {
+        for rule in rules {
+            if let ASTNode::BisonGrammarRule { alternatives, .. } = rule {
+                for alt in alternatives {
+                    if let ASTNode::BisonAlternative { prec, .. } = alt {
+                        out.push(prec.clone());
+                    }
+                }
+            }
+        }
+    }
+    out
+}
+
+/// Terminals in symbol-number order: `$end`, `error`, `$undefined`, then
+/// the grammar's own.
+pub fn terminal_symbols(grammar: &Grammar) -> Vec<String> {
+    let mut symbols = vec![END_MARKER.to_string(), "error".to_string(), "$undefined".to_string()];
+    symbols.extend(grammar.terminals.iter().filter(|t| *t != "error").cloned());
+    symbols
+}
+
+pub fn build_lalr(ast: &ASTNode) -> Result<LalrTables, String> {
+    let grammar = Grammar::from_ast(ast)?;
+
+    let mut symbols = terminal_symbols(&grammar);
+    let terminal_count = symbols.len();
+    symbols.push("$accept".to_string());
+    symbols.extend(grammar.nonterminals.iter().cloned());
+    let id: BTreeMap<&str, usize> = symbols.iter().enumerate().map(|(i, s)| (s.as_str(), i)).collect();
+
+    let declared = declared_precedence(ast);
+    let precedence: BTreeMap<usize, (usize, Associativity)> =
+        declared.iter().filter_map(|(s, &p)| id.get(s.as_str()).filter(|&&i| i < terminal_count).map(|&i| (i, p))).collect();
+    let overrides = rule_prec_overrides(ast);
+
+    let mut rules = vec![LrRule {
+        lhs: terminal_count,
+        rhs: vec![id[grammar.start.as_str()], 0],
+        production: None,
+        precedence: None,
+    }];
+    for (pi, p) in grammar.productions.iter().enumerate() {
+        let rhs: Vec<usize> = p.rhs.iter().map(|s| id[s.as_str()]).collect();
+        let from_last_terminal = rhs.iter().rev().find(|&&s| s < terminal_count).and_then(|s| precedence.get(s));
+        let level = match overrides.get(pi).and_then(|o| o.as_ref()) {
+            Some(name) => match declared.get(name) {
+                Some(&(level, _)) => Some(level),
+                None => return Err(format!("%prec {} in rule '{}' names a symbol with no declared precedence", name, p)),
+            },
+            None => from_last_terminal.map(|&(level, _)| level),
+        };
+        rules.push(LrRule { lhs: id[p.lhs.as_str()], rhs, production: Some(pi), precedence: level });
+    }
+
+    let mut by_lhs = vec![Vec::new(); symbols.len()];
+    for (r, rule) in rules.iter().enumerate() {
+        by_lhs[rule.lhs].push(r);
+    }
+    let mut nullable = vec![false; symbols.len()];
+    let mut first: Vec<BTreeSet<usize>> =
+        (0..symbols.len()).map(|s| if s < terminal_count { BTreeSet::from([s]) } else { BTreeSet::new() }).collect();
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for rule in &rules {
+            if !nullable[rule.lhs] && rule.rhs.iter().all(|&s| nullable[s]) {
+                nullable[rule.lhs] = true;
+                changed = true;
+            }
+            for &s in &rule.rhs {
+                let add: Vec<usize> = first[s].difference(&first[rule.lhs]).copied().collect();
+                if !add.is_empty() {
+                    first[rule.lhs].extend(add);
+                    changed = true;
+                }
+                if !nullable[s] {
+                    break;
+                }
+            }
+        }
+    }
+    let builder = Builder { rules, terminal_count, by_lhs, nullable, first };
+
+    // LR(0) collection.
+    let mut kernels: Vec<Vec<Item>> = vec![vec![(0, 0)]];
+    let mut index: BTreeMap<Vec<Item>, usize> = BTreeMap::from([(vec![(0, 0)], 0)]);
+    let mut transitions: Vec<BTreeMap<usize, usize>> = Vec::new();
+    let mut accessing_symbol = vec![0];
+    let mut i = 0;
+    while i < kernels.len() {
+        let mut successors: BTreeMap<usize, Vec<Item>> = BTreeMap::new();
+        for (r, d) in builder.closure0(&kernels[i]) {
+            if let Some(&sym) = builder.rules[r].rhs.get(d) {
+                successors.entry(sym).or_default().push((r, d + 1));
+            }
+        }
+        let mut row = BTreeMap::new();
+        for (sym, mut kernel) in successors {
+            kernel.sort();
+            let next = *index.entry(kernel.clone()).or_insert_with(|| {
+                kernels.push(kernel);
+                accessing_symbol.push(sym);
+                kernels.len() - 1
+            });
+            row.insert(sym, next);
+        }
+        transitions.push(row);
+        i += 1;
+    }
+
+    // Lookaheads: spontaneous ones seeded, inherited ones propagated.
+    let mut lookaheads: Vec<Vec<BTreeSet<usize>>> = kernels.iter().map(|k| vec![BTreeSet::new(); k.len()]).collect();
+    let mut propagate: Vec<Vec<Vec<(usize, usize)>>> = kernels.iter().map(|k| vec![Vec::new(); k.len()]).collect();
+    for (s, kernel) in kernels.iter().enumerate() {
+        for (ki, &item) in kernel.iter().enumerate() {
+            for ((r, d), las) in builder.closure1(vec![(item, BTreeSet::from([DUMMY]))]) {
+                let Some(&sym) = builder.rules[r].rhs.get(d) else { continue };
+                let t = transitions[s][&sym];
+                let ti = kernels[t].binary_search(&(r, d + 1)).unwrap();
+                for la in las {
+                    if la == DUMMY {
+                        propagate[s][ki].push((t, ti));
+                    } else {
+                        lookaheads[t][ti].insert(la);
+                    }
+                }
+            }
+        }
+    }
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for s in 0..kernels.len() {
+            for ki in 0..kernels[s].len() {
+                let from = lookaheads[s][ki].clone();
+                for &(t, ti) in &propagate[s][ki] {
+                    let before = lookaheads[t][ti].len();
+                    lookaheads[t][ti].extend(from.iter().copied());
+                    changed |= lookaheads[t][ti].len() != before;
+                }
+            }
+        }
+    }
+
+    let final_state = transitions[transitions[0][&id[grammar.start.as_str()]]][&0];
+    let mut actions = Vec::with_capacity(kernels.len());
+    let mut default_reductions = Vec::with_capacity(kernels.len());
+    let mut gotos = Vec::with_capacity(kernels.len());
+    let mut conflicts = Vec::new();
+    for (s, kernel) in kernels.iter().enumerate() {
+        let seed = kernel.iter().zip(&lookaheads[s]).map(|(&item, las)| (item, las.clone())).collect();
+        let mut reductions: BTreeMap<usize, Vec<usize>> = BTreeMap::new();
+        for ((r, d), las) in builder.closure1(seed) {
+            if r != 0 && d == builder.rules[r].rhs.len() {
+                for la in las {
+                    reductions.entry(la).or_default().push(r);
+                }
+            }
+        }
+        let mut row: BTreeMap<usize, LrAction> = BTreeMap::new();
+        for (&sym, &t) in &transitions[s] {
+            if sym < terminal_count {
+                row.insert(sym, LrAction::Shift { state: t });
+            }
+        }
+        for (la, mut rs) in reductions {
+            rs.sort();
+            rs.dedup();
+            let rule = rs[0];
+            if rs.len() > 1 {
+                conflicts.push(LrConflict {
+                    state: s,
+                    symbol: symbols[la].clone(),
+                    kind: "reduce/reduce".to_string(),
+                    rules: rs.clone(),
+                    resolution: format!("reduce using rule {}", rule),
+                });
+            }
+            let Some(&shift) = row.get(&la) else {
+                row.insert(la, LrAction::Reduce { rule });
+                continue;
+            };
+            let resolved = match (builder.rules[rule].precedence, precedence.get(&la)) {
+                (Some(rp), Some(&(tp, assoc))) if rp != tp || assoc != Associativity::Precedence => Some(if rp > tp {
+                    LrAction::Reduce { rule }
+                } else if rp < tp {
+                    shift
+                } else {
+                    match assoc {
+                        Associativity::Left => LrAction::Reduce { rule },
+                        Associativity::Right => shift,
+                        _ => LrAction::Error,
+                    }
+                }),
+                _ => None,
+            };
+            match resolved {
+                Some(action) => {
+                    row.insert(la, action);
+                }
+                None => conflicts.push(LrConflict {
+                    state: s,
+                    symbol: symbols[la].clone(),
+                    kind: "shift/reduce".to_string(),
+                    rules: vec![rule],
+                    resolution: "shift".to_string(),
+                }),
+            }
+        }
+
+        // Most frequent reduction becomes the default, unless `error` can be shifted.
+        let mut counts: BTreeMap<usize, usize> = BTreeMap::new();
+        for action in row.values() {
+            if let LrAction::Reduce { rule } = action {
+                *counts.entry(*rule).or_default() += 1;
+            }
+        }
+        let shifts_error = matches!(row.get(&ERROR_SYMBOL), Some(LrAction::Shift { .. }));
+        let default = if shifts_error { None } else { counts.iter().max_by_key(|&(&r, &n)| (n, std::cmp::Reverse(r))).map(|(&r, _)| r) };
+        if let Some(d) = default {
+            row.retain(|_, a| *a != LrAction::Reduce { rule: d });
+        }
+        actions.push(row);
+        default_reductions.push(default);
+        gotos.push(transitions[s].iter().filter(|&(&sym, _)| sym >= terminal_count).map(|(&sym, &t)| (sym, t)).collect());
+    }
+
+    Ok(LalrTables {
+        grammar,
+        symbols,
+        terminal_count,
+        precedence,
+        rules: builder.rules,
+        actions,
+        default_reductions,
+        gotos,
+        accessing_symbol,
+        final_state,
+        conflicts,
+    })
+}
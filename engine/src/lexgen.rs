@@ -0,0 +1,877 @@
+use std::collections::BTreeSet;
+
+use crate::automaton::{compile_flex, compile_pattern, AutomatonRule, Dfa, LexerAutomaton};
+use crate::regex::Pattern;
+use crate::utf8::{byte_dfa, fixed_byte_length};
+use crate::{ASTNode, CodegenOptions};
+
+// --- LEX.YY.C GENERATION ---
+// Emits a table-driven scanner shaped like GNU flex output. The byte DFA is
+// packed into flex's tables: `yy_ec` folds bytes into equivalence classes and
+// `yy_base`/`yy_def`/`yy_nxt`/`yy_chk` store each state's row as a comb
+// vector, sharing whatever it has in common with a recent row through
+// `yy_def`. The runtime keeps the whole input in one buffer, so yyless,
+// yymore and unput can move freely around yytext.
+
+/// Name of the generated scanner, as flex writes it.
+pub const SCANNER_OUTPUT: &str = "lex.yy.c";
+
+/// Spec named in `#line` when the caller gives no file name.
+pub const SCANNER_SOURCE: &str = "scanner.l";
+
+/// Recent rows considered as a default for the next one.
+const PROTO_QUEUE: usize = 32;
+
+/// Rows packed into comb vectors: a transition on class `c` from state `s`
+/// lives in `nxt[base[s] + c]` when `chk[base[s] + c] == s`, otherwise it is
+/// looked up in state `def[s]` (`-1`: no transition).
+struct PackedTables {
+    base: Vec<i64>,
+    def: Vec<i64>,
+    nxt: Vec<i64>,
+    chk: Vec<i64>,
+}
+
+fn pack(rows: &[Vec<Option<usize>>], classes: usize) -> PackedTables {
+    let mut base = Vec::with_capacity(rows.len());
+    let mut def = Vec::with_capacity(rows.len());
+    let (mut nxt, mut chk): (Vec<i64>, Vec<i64>) = (Vec::new(), Vec::new());
+    let mut first_free: usize = 0;
+    for (s, row) in rows.iter().enumerate() {
+        let target = |t: &Option<usize>| t.map_or(-1, |t| t as i64);
+        let own: Vec<(usize, i64)> = row.iter().enumerate().filter(|(_, t)| t.is_some()).map(|(c, t)| (c, target(t))).collect();
+        // Share the closest recent row when that stores fewer cells.
+        let proto = (s.saturating_sub(PROTO_QUEUE)..s).map(|p| (rows[p].iter().zip(row).filter(|(a, b)| a != b).count(), p)).min();
+        let (d, cells) = match proto {
+            Some((diff, p)) if diff < own.len() => {
+                let cells = row.iter().zip(&rows[p]).enumerate().filter(|(_, (a, b))| a != b).map(|(c, (a, _))| (c, target(a))).collect();
+                (p as i64, cells)
+            }
+            _ => (-1, own),
+        };
+        let lowest = cells.first().map_or(0, |&(c, _)| c);
+        let b = (first_free.saturating_sub(lowest)..)
+            .find(|&b| cells.iter().all(|&(c, _)| chk.get(b + c).is_none_or(|&owner| owner == -1)))
+            .unwrap_or(0);
+        for &(c, t) in &cells {
+            if chk.len() <= b + c {
+                nxt.resize(b + c + 1, -1);
+                chk.resize(b + c + 1, -1);
+            }
+            nxt[b + c] = t;
+            chk[b + c] = s as i64;
+        }
+        while chk.get(first_free).is_some_and(|&owner| owner != -1) {
+            first_free += 1;
+        }
+        base.push(b as i64);
+        def.push(d);
+    }
+    // Every lookup `base[s] + c` must stay in bounds.
+    let len = base.iter().map(|&b| b as usize + classes).max().unwrap_or(classes);
+    nxt.resize(len, -1);
+    chk.resize(len, -1);
+    PackedTables { base, def, nxt, chk }
+}
+
+pub(crate) fn int_type(values: &[i64]) -> &'static str {
+    if values.iter().all(|&v| (i16::MIN as i64..=i16::MAX as i64).contains(&v)) {
+        "short"
+    } else {
+        "int"
+    }
+}
+
+pub(crate) fn c_array(code: &mut String, ty: &str, name: &str, values: &[i64]) {
+    let values = if values.is_empty() { &[0][..] } else { values };
+    code.push_str(&format!("static const {} {}[{}] =\n    {{", ty, name, values.len()));
+    for (i, v) in values.iter().enumerate() {
+        if i % 10 == 0 {
+            code.push_str("\n    ");
+        }
+        code.push_str(&format!("{:5}", v));
+        if i + 1 < values.len() {
+            code.push(',');
+        }
+    }
+    code.push_str("\n    };\n\n");
+}
+
+/// Names given to `%option` in a FlexFile.
+pub fn flex_options(flex: &ASTNode) -> BTreeSet<String> {
+    let mut out = BTreeSet::new();
+    if let ASTNode::FlexFile { definitions, .. } = flex {
+        for d in definitions {
+            if let ASTNode::FlexOption { names, .. } = d {
+                out.extend(names.iter().cloned());
+            }
+        }
+    }
+    out
+}
+
+/// The value of `%option name=value`, the last one given.
+fn flex_option_value(flex: &ASTNode, name: &str) -> Option<String> {
+    let ASTNode::FlexFile { definitions, .. } = flex else { return None };
+    definitions.iter().rev().find_map(|d| match d {
+        ASTNode::FlexOption { values, .. } => values.get(name).cloned(),
+        _ => None,
+    })
+}
+
+/// Whether `code` uses `name` as a whole identifier.
+pub(crate) fn mentions(code: &str, name: &str) -> bool {
+    let is_ident = |c: char| c.is_alphanumeric() || c == '_';
+    code.match_indices(name).any(|(i, _)| {
+        !code[..i].ends_with(is_ident) && !code[i + name.len()..].starts_with(is_ident)
+    })
+}
+
+/// Whether `code` defines or declares the function `name`.
+pub(crate) fn defines_function(code: &str, name: &str) -> bool {
+    code.match_indices(name).any(|(i, _)| {
+        let before = code[..i].trim_end();
+        let after = code[i + name.len()..].trim_start();
+        after.starts_with('(') && before.ends_with(|c: char| c.is_alphanumeric() || c == '_' || c == '*')
+    })
+}
+
+/// C string literal for `text`.
+pub(crate) fn c_string(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Stands for "back to the generated file" until `number_output_lines`
+/// knows the line it lands on.
+const OUTPUT_LINE_MARK: &str = "#line @output@";
+
+/// User code between `#line` directives, so compiler messages about it
+/// point at the spec instead of the generated file.
+pub(crate) fn user_code(codegen: &CodegenOptions, default_source: &str, line: usize, code: &str) -> String {
+    if codegen.no_line || line == 0 || code.trim().is_empty() {
+        return format!("{}\n", code);
+    }
+    let source = codegen.source_name.as_deref().unwrap_or(default_source);
+    format!("#line {} {}\n{}\n{}\n", line, c_string(source), code, OUTPUT_LINE_MARK)
+}
+
+/// Resolves the marks `user_code` leaves to `#line` directives naming the
+/// generated file.
+pub(crate) fn number_output_lines(code: String, output: &str) -> String {
+    if !code.contains(OUTPUT_LINE_MARK) {
+        return code;
+    }
+    code.split('\n')
+        .enumerate()
+        .map(|(i, l)| if l == OUTPUT_LINE_MARK { format!("#line {} {}", i + 2, c_string(output)) } else { l.to_string() })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Keeps user text from closing a C comment early.
+pub(crate) fn comment_safe(text: &str) -> String {
+    text.replace("*/", "*\\/").replace("/*", "/\\*").replace('\n', " ")
+}
+
+/// flex's tables for a byte DFA, plus each start condition's entry states
+/// (normal, then beginning of line) and `<<EOF>>` rule.
+pub(crate) struct ScannerTables {
+    pub classes: usize,
+    pub ec: Vec<i64>,
+    pub accept: Vec<i64>,
+    pub base: Vec<i64>,
+    pub def: Vec<i64>,
+    pub nxt: Vec<i64>,
+    pub chk: Vec<i64>,
+    pub start_state: Vec<i64>,
+    pub eof_rule: Vec<i64>,
+}
+
+impl ScannerTables {
+    /// Every table but `yy_ec`, in the order lex.yy.c emits them.
+    pub fn arrays(&self) -> [(&'static str, &[i64]); 7] {
+        [
+            ("yy_accept", &self.accept),
+            ("yy_base", &self.base),
+            ("yy_def", &self.def),
+            ("yy_nxt", &self.nxt),
+            ("yy_chk", &self.chk),
+            ("yy_start_state", &self.start_state),
+            ("yy_eof_rule", &self.eof_rule),
+        ]
+    }
+}
+
+pub(crate) fn scanner_tables(automaton: &LexerAutomaton, dfa: &Dfa) -> Result<ScannerTables, String> {
+    let rules = &automaton.rules;
+    let classes = dfa.classes.iter().take_while(|&&(lo, _)| lo <= '\u{ff}').count();
+    let ec: Vec<i64> = (0..=255u8).map(|b| dfa.class_of(b as char) as i64).collect();
+    let rows: Vec<Vec<Option<usize>>> =
+        dfa.states.iter().map(|st| (0..classes).map(|c| st.transitions.get(&c).copied()).collect()).collect();
+    let packed = pack(&rows, classes);
+    let accept: Vec<i64> = dfa.states.iter().map(|st| st.accept.map_or(0, |r| r as i64 + 1)).collect();
+    let mut start_state = Vec::new();
+    for sc in &automaton.start_conditions {
+        let st = dfa.starts.iter().find(|st| st.condition == sc.name).ok_or("Missing start state")?;
+        start_state.extend([st.normal as i64, st.bol as i64]);
+    }
+    // A condition's own <<EOF>> rule wins over a bare one.
+    let eof_rule: Vec<i64> = automaton
+        .start_conditions
+        .iter()
+        .map(|sc| {
+            let eof = |explicit: bool| {
+                rules.iter().position(|r| r.parsed.eof && explicit != r.parsed.start_conditions.is_empty() && (!explicit || r.active_in(sc)))
+            };
+            eof(true).or(eof(false)).map_or(0, |ri| ri as i64 + 1)
+        })
+        .collect();
+    let PackedTables { base, def, nxt, chk } = packed;
+    Ok(ScannerTables { classes, ec, accept, base, def, nxt, chk, start_state, eof_rule })
+}
+
+/// How many bytes of a trailing-context match belong to yytext.
+pub(crate) enum HeadLength {
+    /// Trailing context of a fixed byte length.
+    MinusTrailing(usize),
+    /// Head of a fixed byte length.
+    Fixed(usize),
+    /// Both vary: split with dedicated automata for head and tail.
+    Split { head: Dfa, tail: Dfa },
+}
+
+pub(crate) fn head_length(rule: &AutomatonRule) -> Result<Option<HeadLength>, String> {
+    let Some(trailing) = &rule.parsed.trailing else { return Ok(None) };
+    if let Some(n) = fixed_byte_length(trailing) {
+        return Ok(Some(HeadLength::MinusTrailing(n)));
+    }
+    if let Some(n) = fixed_byte_length(&rule.parsed.regex) {
+        return Ok(Some(HeadLength::Fixed(n)));
+    }
+    let part = |regex: &crate::regex::Regex| -> Result<Dfa, String> {
+        let pattern = Pattern { start_conditions: Vec::new(), eof: false, bol: false, regex: regex.clone(), trailing: None };
+        byte_dfa(&compile_pattern(&rule.pattern, &pattern)?)
+    };
+    Ok(Some(HeadLength::Split { head: part(&rule.parsed.regex)?, tail: part(trailing)? }))
+}
+
+/// Uncompressed byte table of a small automaton: `(transitions, accepting, start)`.
+pub(crate) fn dense_table(dfa: &Dfa) -> (Vec<i64>, Vec<i64>, usize) {
+    let mut table = Vec::with_capacity(dfa.states.len() * 256);
+    for s in 0..dfa.states.len() {
+        table.extend((0..=255u8).map(|b| dfa.next(s, b as char).map_or(-1, |t| t as i64)));
+    }
+    let accepting = dfa.states.iter().map(|st| st.accept.is_some() as i64).collect();
+    (table, accepting, dfa.start("INITIAL", false).unwrap_or(0))
+}
+
+/// `yy_head_length()`: trims trailing context off a match.
+fn emit_head_length(code: &mut String, automaton: &LexerAutomaton) -> Result<(), String> {
+    let mut cases = String::new();
+    let mut split_helper = false;
+    for (ri, rule) in automaton.rules.iter().enumerate() {
+        let Some(split) = head_length(rule)? else { continue };
+        let act = ri + 1;
+        match split {
+            HeadLength::MinusTrailing(n) => cases.push_str(&format!("    case {}: yy_i = yy_len - {}; break;\n", act, n)),
+            HeadLength::Fixed(n) => cases.push_str(&format!("    case {}: yy_i = {}; break;\n", act, n)),
+            HeadLength::Split { head, tail } => {
+                if !split_helper {
+                    code.push_str(TC_ACCEPTS);
+                    split_helper = true;
+                }
+                for (part, dfa) in [("head", &head), ("tail", &tail)] {
+                    let (table, accepting, _) = dense_table(dfa);
+                    c_array(code, "int", &format!("yy_tc{}_{}", act, part), &table);
+                    c_array(code, "int", &format!("yy_tc{}_{}_accept", act, part), &accepting);
+                }
+                let (head_start, tail_start) = (dense_table(&head).2, dense_table(&tail).2);
+                cases.push_str(&format!("    case {}:\n", act));
+                cases.push_str("        for (yy_i = yy_len; yy_i > 0; --yy_i)\n");
+                cases.push_str(&format!(
+                    "            if (yy_tc_accepts(yy_tc{0}_head, yy_tc{0}_head_accept, {1}, yy_text, yy_i) &&\n                yy_tc_accepts(yy_tc{0}_tail, yy_tc{0}_tail_accept, {2}, yy_text + yy_i, yy_len - yy_i))\n                break;\n",
+                    act, head_start, tail_start
+                ));
+                cases.push_str("        break;\n");
+            }
+        }
+    }
+    code.push_str("/* Bytes of a match that belong to yytext; the rest is trailing context. */\n");
+    code.push_str("static size_t yy_head_length(int yy_act, const char *yy_text, size_t yy_len)\n{\n    size_t yy_i = yy_len;\n    (void) yy_text;\n");
+    code.push_str("    switch (yy_act) {\n");
+    code.push_str(&cases);
+    code.push_str("    default: return yy_len;\n    }\n    return yy_i > 0 ? yy_i : 1;\n}\n\n");
+    Ok(())
+}
+
+const TC_ACCEPTS: &str = "static int yy_tc_accepts(const int *yy_table, const int *yy_accepting, int yy_state, const char *yy_s, size_t yy_n)
+{
+    size_t yy_i;
+    for (yy_i = 0; yy_i < yy_n && yy_state >= 0; ++yy_i)
+        yy_state = yy_table[yy_state * 256 + (unsigned char) yy_s[yy_i]];
+    return yy_state >= 0 && yy_accepting[yy_state];
+}
+
+";
+
+/// flex's declaration of the scanner handle, under flex's own guard.
+pub(crate) const SCANNER_TYPE: &str = "#ifndef YY_TYPEDEF_YY_SCANNER_T
+#define YY_TYPEDEF_YY_SCANNER_T
+typedef void *yyscan_t;
+#endif
+";
+
+/// `%option reentrant`: the globals become members of `struct yyguts_t`,
+/// reached through `yyg` under their usual names, and every scanner function
+/// takes the `yyscan_t`. `bison-bridge` (which implies it) hands yylex the
+/// parser's `yylval`, and `bison-locations` its `yylloc`, as pointers.
+struct Reentrancy {
+    reentrant: bool,
+    bridge: bool,
+    locations: bool,
+    /// `%option extra-type="T"`: the type of `yyextra`.
+    extra_type: Option<String>,
+}
+
+impl Reentrancy {
+    fn new(flex: &ASTNode, options: &BTreeSet<String>) -> Self {
+        let locations = options.contains("bison-locations");
+        let bridge = locations || options.contains("bison-bridge");
+        Reentrancy {
+            reentrant: bridge || options.contains("reentrant"),
+            bridge,
+            locations,
+            extra_type: flex_option_value(flex, "extra-type"),
+        }
+    }
+
+    /// Members of `struct yyguts_t`: (type, the name actions use).
+    fn guts(&self) -> Vec<(&'static str, &'static str)> {
+        let mut fields = vec![
+            ("YY_EXTRA_TYPE ", "yyextra"),
+            ("FILE *", "yyin"),
+            ("FILE *", "yyout"),
+            ("char *", "yytext"),
+            ("int ", "yyleng"),
+            ("int ", "yylineno"),
+            ("char *", "yy_buf"),
+            ("size_t ", "yy_buf_len"),
+            ("size_t ", "yy_buf_cap"),
+            ("size_t ", "yy_pos"),
+            ("char ", "yy_hold_char"),
+            ("int ", "yy_init"),
+            ("int ", "yy_start"),
+            ("int ", "yy_more_flag"),
+            ("size_t ", "yy_col_pos"),
+            ("int ", "yy_col"),
+        ];
+        if self.bridge {
+            fields.push(("YYSTYPE *", "yylval"));
+        }
+        if self.locations {
+            fields.push(("YYLTYPE *", "yylloc"));
+        }
+        fields
+    }
+
+    /// Fills in a skeleton's `@void@`, `@param@`, `@arg0@`, `@arg@`, `@guts@`,
+    /// `@yylex@`, `@bridge@` and `@lloc@`.
+    fn fill(&self, skeleton: &str) -> String {
+        let r = self.reentrant;
+        let mut yylex_params = Vec::new();
+        let mut bridge = String::new();
+        if self.bridge {
+            yylex_params.push("YYSTYPE *yylval_param");
+            bridge.push_str("    yylval = yylval_param;\n");
+        }
+        if self.locations {
+            yylex_params.push("YYLTYPE *yylloc_param");
+            bridge.push_str("    yylloc = yylloc_param;\n");
+        }
+        yylex_params.push(if r { "yyscan_t yyscanner" } else { "void" });
+        skeleton
+            .replace("@void@", if r { "yyscan_t yyscanner" } else { "void" })
+            .replace("@param@", if r { ", yyscan_t yyscanner" } else { "" })
+            .replace("@arg0@", if r { "yyscanner" } else { "" })
+            .replace("@arg@", if r { ", yyscanner" } else { "" })
+            .replace("@guts@", if r { "    struct yyguts_t *yyg = (struct yyguts_t *) yyscanner;\n" } else { "" })
+            .replace("@yylex@", &yylex_params.join(", "))
+            .replace("@bridge@", &bridge)
+            .replace("@lloc@", if self.locations { "yylloc->" } else { "yylloc." })
+    }
+
+    /// `YY_LOCATIONS`: whether yylex keeps yylloc on the token it matched.
+    /// bison-locations hands it one; otherwise y.tab.h may declare a global.
+    fn locations_switch(&self) -> &'static str {
+        if self.locations {
+            "#define YY_LOCATIONS 1\n"
+        } else if self.reentrant {
+            "#define YY_LOCATIONS 0\n"
+        } else {
+            "#ifdef YYLLOC_IS_GLOBAL\n#define YY_LOCATIONS 1\n#else\n#define YY_LOCATIONS 0\n#endif\n"
+        }
+    }
+
+    /// `struct yyguts_t` and the macros that reach into it.
+    fn emit_guts(&self, code: &mut String) {
+        if let Some(ty) = &self.extra_type {
+            code.push_str(&format!("#define YY_EXTRA_TYPE {}\n", ty));
+        }
+        code.push_str("#ifndef YY_EXTRA_TYPE\n#define YY_EXTRA_TYPE void *\n#endif\n\n");
+        code.push_str("/* A scanner's whole state; a yyscan_t points at one. */\nstruct yyguts_t\n{\n");
+        for (ty, name) in self.guts() {
+            code.push_str(&format!("    {}{}_r;\n", ty, name));
+        }
+        code.push_str("};\n\n");
+        for (_, name) in self.guts() {
+            code.push_str(&format!("#define {0} yyg->{0}_r\n", name));
+        }
+        code.push('\n');
+    }
+
+    /// yylex_init/yylex_destroy and the yyget_/yyset_ accessors.
+    fn emit_api(&self, code: &mut String) {
+        code.push_str(SCANNER_API);
+        let mut accessors = vec![
+            ("YY_EXTRA_TYPE ", "extra", "yyextra"),
+            ("FILE *", "in", "yyin"),
+            ("FILE *", "out", "yyout"),
+            ("int ", "lineno", "yylineno"),
+        ];
+        if self.bridge {
+            accessors.push(("YYSTYPE *", "lval", "yylval"));
+        }
+        if self.locations {
+            accessors.push(("YYLTYPE *", "lloc", "yylloc"));
+        }
+        for (ty, name, field) in accessors {
+            code.push_str(&format!(
+                "{0}yyget_{1}(yyscan_t yyscanner)\n{{\n    struct yyguts_t *yyg = (struct yyguts_t *) yyscanner;\n    return {2};\n}}\n\n",
+                ty, name, field
+            ));
+            code.push_str(&format!(
+                "void yyset_{1}({0}yy_value, yyscan_t yyscanner)\n{{\n    struct yyguts_t *yyg = (struct yyguts_t *) yyscanner;\n    {2} = yy_value;\n}}\n\n",
+                ty, name, field
+            ));
+        }
+        for (ty, name, field) in [("char *", "text", "yytext"), ("int ", "leng", "yyleng")] {
+            code.push_str(&format!(
+                "{0}yyget_{1}(yyscan_t yyscanner)\n{{\n    struct yyguts_t *yyg = (struct yyguts_t *) yyscanner;\n    return {2};\n}}\n\n",
+                ty, name, field
+            ));
+        }
+    }
+}
+
+const SCANNER_API: &str = "int yylex_init(yyscan_t *yy_scanner)
+{
+    struct yyguts_t *yyg;
+    if (!yy_scanner)
+        return 1;
+    yyg = (struct yyguts_t *) calloc(1, sizeof (struct yyguts_t));
+    *yy_scanner = yyg;
+    if (!yyg)
+        return 1;
+    yylineno = 1;
+    return 0;
+}
+
+int yylex_init_extra(YY_EXTRA_TYPE yy_user_defined, yyscan_t *yy_scanner)
+{
+    if (yylex_init(yy_scanner))
+        return 1;
+    ((struct yyguts_t *) *yy_scanner)->yyextra_r = yy_user_defined;
+    return 0;
+}
+
+int yylex_destroy(yyscan_t yyscanner)
+{
+    struct yyguts_t *yyg = (struct yyguts_t *) yyscanner;
+    free(yy_buf);
+    free(yyg);
+    return 0;
+}
+
+";
+
+const SCANNER_GLOBALS: &str = "FILE *yyin = NULL, *yyout = NULL;
+char *yytext = NULL;
+int yyleng = 0;
+int yylineno = 1;
+
+static char *yy_buf = NULL;       /* the whole input stream, NUL-terminated */
+static size_t yy_buf_len = 0;
+static size_t yy_buf_cap = 0;
+static size_t yy_pos = 0;         /* next byte to scan */
+static char yy_hold_char = '\\0';  /* real byte at yy_pos, where yytext's NUL sits */
+static int yy_init = 0;
+static int yy_start = 0;
+static int yy_more_flag = 0;
+static size_t yy_col_pos = 0;     /* yy_buf offset whose column yy_col holds */
+static int yy_col = 1;
+
+";
+
+const SCANNER_MACROS: &str = "#ifndef YY_BUF_SIZE
+#define YY_BUF_SIZE 16384
+#endif
+#ifndef YY_FATAL_ERROR
+#define YY_FATAL_ERROR(msg) do { fprintf(stderr, \"%s\\n\", msg); exit(2); } while (0)
+#endif
+#ifndef ECHO
+#define ECHO do { if (fwrite(yytext, (size_t) yyleng, 1, yyout)) {} } while (0)
+#endif
+#ifndef YY_DECL
+#define YY_DECL int yylex(@yylex@)
+#endif
+#ifndef YY_USER_ACTION
+#define YY_USER_ACTION
+#endif
+#ifndef YY_BREAK
+#define YY_BREAK break;
+#endif
+#define YY_RULE_SETUP YY_USER_ACTION
+#define BEGIN yy_start =
+#define YY_START yy_start
+#define YYSTATE YY_START
+#define yyterminate() return 0
+#define yymore() (yy_more_flag = 1)
+#define yyless(n) yy_less(n@arg@)
+#define unput(c) yyunput(c@arg@)
+
+";
+
+const SCANNER_LESS: &str = "static void yy_less(int yy_n@param@)
+{
+@guts@    size_t yy_bp = (size_t) (yytext - yy_buf);
+    int yy_i;
+    yy_buf[yy_pos] = yy_hold_char;
+    if (YY_USE_LINENO)
+        for (yy_i = yy_n; yy_i < yyleng; ++yy_i)
+            if (yytext[yy_i] == '\\n')
+                --yylineno;
+    yy_pos = yy_bp + (size_t) yy_n;
+    yyleng = yy_n;
+    yy_hold_char = yy_buf[yy_pos];
+    yy_buf[yy_pos] = '\\0';
+}
+
+";
+
+const SCANNER_RUNTIME: &str = "static void yy_grow(size_t yy_need@param@)
+{
+@guts@    if (yy_need <= yy_buf_cap)
+        return;
+    while (yy_buf_cap < yy_need)
+        yy_buf_cap = yy_buf_cap ? 2 * yy_buf_cap : YY_BUF_SIZE;
+    yy_buf = (char *) realloc(yy_buf, yy_buf_cap + 1);
+    if (!yy_buf)
+        YY_FATAL_ERROR(\"out of dynamic memory in yy_grow()\");
+}
+
+/* Reads all of yyin, so a token never straddles a refill. */
+static void yy_load_buffer(@void@)
+{
+@guts@    size_t yy_n;
+    yy_buf_len = 0;
+    yy_grow(YY_BUF_SIZE@arg@);
+    while ((yy_n = fread(yy_buf + yy_buf_len, 1, yy_buf_cap - yy_buf_len, yyin)) > 0) {
+        yy_buf_len += yy_n;
+        yy_grow(yy_buf_len + 1@arg@);
+    }
+    yy_buf[yy_buf_len] = '\\0';
+    yy_pos = 0;
+    yy_hold_char = yy_buf[0];
+    yy_buf[0] = '\\0';
+    yytext = yy_buf;
+    yyleng = 0;
+    yy_col_pos = 0;
+    yy_col = 1;
+}
+
+void yyrestart(FILE *input_file@param@)
+{
+@guts@    yyin = input_file ? input_file : stdin;
+    if (!yyout)
+        yyout = stdout;
+    yy_init = 1;
+    yy_load_buffer(@arg0@);
+}
+
+#ifndef YY_NO_UNPUT
+/* Pushes a byte back in front of the cursor; yytext is no longer valid. */
+static void yyunput(int yy_c@param@)
+{
+@guts@    yy_buf[yy_pos] = yy_hold_char;
+    if (yy_pos == 0) {
+        yy_grow(yy_buf_len + 1@arg@);
+        memmove(yy_buf + 1, yy_buf, yy_buf_len + 1);
+        ++yy_buf_len;
+        yy_pos = 1;
+    }
+    yy_buf[--yy_pos] = (char) yy_c;
+    if (YY_USE_LINENO && yy_c == '\\n')
+        --yylineno;
+    /* The bytes behind the cursor changed, so yy_col must be found again. */
+    yy_col_pos = (size_t) -1;
+    yy_hold_char = yy_buf[yy_pos];
+    yy_buf[yy_pos] = '\\0';
+}
+#endif
+
+#ifndef YY_NO_INPUT
+static int input(@void@)
+{
+@guts@    int yy_c;
+    yy_buf[yy_pos] = yy_hold_char;
+    if (yy_pos >= yy_buf_len)
+        return EOF;
+    yy_c = (unsigned char) yy_buf[yy_pos++];
+    if (YY_USE_LINENO && yy_c == '\\n')
+        ++yylineno;
+    yy_hold_char = yy_buf[yy_pos];
+    yy_buf[yy_pos] = '\\0';
+    return yy_c;
+}
+#endif
+
+#if YY_LOCATIONS
+/* Column of yy_buf[yy_at], counting from 1. Advances the running column,
+ * so only yyless and unput, which move the cursor back, rescan a line. */
+static int yy_column(size_t yy_at@param@)
+{
+@guts@    if (yy_at < yy_col_pos) {
+        yy_col_pos = yy_at;
+        while (yy_col_pos > 0 && yy_buf[yy_col_pos - 1] != '\\n')
+            --yy_col_pos;
+        yy_col = 1;
+    }
+    for (; yy_col_pos < yy_at; ++yy_col_pos)
+        yy_col = yy_buf[yy_col_pos] == '\\n' ? 1 : yy_col + 1;
+    return yy_col;
+}
+
+/* Points yylloc at yy_buf[yy_from..yy_to), before yylineno counts it. */
+static void yy_locate(size_t yy_from, size_t yy_to@param@)
+{
+@guts@    size_t yy_i;
+    @lloc@first_line = @lloc@last_line = yylineno;
+    @lloc@first_column = yy_column(yy_from@arg@);
+    for (yy_i = yy_from; yy_i + 1 < yy_to; ++yy_i)
+        if (yy_buf[yy_i] == '\\n')
+            ++@lloc@last_line;
+    @lloc@last_column = yy_column(yy_to - 1@arg@);
+}
+#endif
+
+static int yy_next_state(int yy_state, int yy_c)
+{
+    while (yy_chk[yy_base[yy_state] + yy_c] != yy_state) {
+        yy_state = yy_def[yy_state];
+        if (yy_state < 0)
+            return -1;
+    }
+    return yy_nxt[yy_base[yy_state] + yy_c];
+}
+
+";
+
+/// Longest match from `yy_bp`, as `yylex()` runs it.
+const SCANNER_MATCH: &str = "YY_DECL
+{
+@guts@    int yy_act, yy_state;
+    size_t yy_bp, yy_cp, yy_end, yy_more_len;
+@bridge@
+    if (!yy_init) {
+        yy_init = 1;
+        if (!yyin)
+            yyin = stdin;
+        if (!yyout)
+            yyout = stdout;
+        yy_load_buffer(@arg0@);
+    }
+
+    for (;;) {
+        yy_buf[yy_pos] = yy_hold_char;
+        yy_bp = yy_pos;
+        yy_more_len = yy_more_flag ? (size_t) yyleng : 0;
+        yy_more_flag = 0;
+
+        if (yy_bp >= yy_buf_len) {
+            if (!yywrap(@arg0@)) {
+                yy_load_buffer(@arg0@);
+                continue;
+            }
+            yy_act = yy_eof_rule[yy_start];
+            if (!yy_act)
+                yyterminate();
+            yytext = yy_buf + yy_bp;
+            yyleng = 0;
+        } else {
+            yy_state = yy_start_state[2 * yy_start + (yy_bp == 0 || yy_buf[yy_bp - 1] == '\\n')];
+            yy_act = 0;
+            yy_end = yy_bp;
+            for (yy_cp = yy_bp; yy_cp < yy_buf_len; ++yy_cp) {
+                yy_state = yy_next_state(yy_state, yy_ec[(unsigned char) yy_buf[yy_cp]]);
+                if (yy_state < 0)
+                    break;
+                if (yy_accept[yy_state]) {
+                    yy_act = yy_accept[yy_state];
+                    yy_end = yy_cp + 1;
+                }
+            }
+            if (!yy_act) {
+                /* Default rule: copy one byte to yyout. */
+                yy_act = YY_DEFAULT_RULE;
+                yy_end = yy_bp + 1;
+            } else {
+                yy_end = yy_bp + yy_head_length(yy_act, yy_buf + yy_bp, yy_end - yy_bp);
+            }
+            yytext = yy_buf + yy_bp - yy_more_len;
+            yyleng = (int) (yy_end - yy_bp + yy_more_len);
+#if YY_LOCATIONS
+            yy_locate(yy_bp, yy_end@arg@);
+#endif
+            if (YY_USE_LINENO)
+                for (yy_cp = yy_bp; yy_cp < yy_end; ++yy_cp)
+                    if (yy_buf[yy_cp] == '\\n')
+                        ++yylineno;
+            yy_pos = yy_end;
+            yy_hold_char = yy_buf[yy_pos];
+            yy_buf[yy_pos] = '\\0';
+        }
+
+        switch (yy_act) {
+";
+
+/// `main()` as libfl supplies it. Left out when the prologue includes the
+/// parser's header: yyparse's program drives the scanner then.
+const LIBFL_MAIN: &str = "
+#ifndef YY_YY_Y_TAB_H_INCLUDED
+int main(void)
+{
+    while (yylex() != 0)
+        ;
+    return 0;
+}
+#endif
+";
+
+const REENTRANT_MAIN: &str = "
+#ifndef YY_YY_Y_TAB_H_INCLUDED
+int main(void)
+{
+    yyscan_t yyscanner;
+    if (yylex_init(&yyscanner))
+        return 2;
+    while (yylex(yyscanner) != 0)
+        ;
+    yylex_destroy(yyscanner);
+    return 0;
+}
+#endif
+";
+
+pub fn generate_scanner(flex: &ASTNode, codegen: &CodegenOptions) -> Result<String, String> {
+    let ASTNode::FlexFile { prologue, prologue_line, epilogue, epilogue_line, .. } = flex else {
+        return Err("Expected a Flex specification".to_string());
+    };
+    let automaton = compile_flex(flex)?;
+    let dfa = byte_dfa(&automaton)?;
+    let options = flex_options(flex);
+    let re = Reentrancy::new(flex, &options);
+    let all_user_code = format!("{}\n{}", prologue.as_deref().unwrap_or(""), epilogue.as_deref().unwrap_or(""));
+    let rules = &automaton.rules;
+    let default_rule = rules.len() + 1;
+
+    let mut code = String::from("/* Generated by Structura.ai Lexical Engine */\n");
+    code.push_str("#include <stdio.h>\n#include <stdlib.h>\n#include <string.h>\n\n");
+    if rules.iter().any(|r| mentions(&r.action, "REJECT")) {
+        code.push_str("#error \"REJECT is not supported by this scanner generator\"\n\n");
+    }
+    code.push_str(if re.reentrant { SCANNER_TYPE } else { SCANNER_GLOBALS });
+    if re.reentrant {
+        code.push('\n');
+    }
+
+    if let Some(p) = prologue {
+        code.push_str("/* --- PROLOGUE --- */\n");
+        code.push_str(&user_code(codegen, SCANNER_SOURCE, *prologue_line, p));
+        code.push('\n');
+    }
+    if re.reentrant {
+        re.emit_guts(&mut code);
+    }
+
+    for (i, sc) in automaton.start_conditions.iter().enumerate() {
+        code.push_str(&format!("#define {} {}\n", sc.name, i));
+    }
+    code.push_str(&format!("\n#define YY_NUM_RULES {}\n#define YY_DEFAULT_RULE {}\n", rules.len(), default_rule));
+    code.push_str(re.locations_switch());
+    // Locations need line numbers, as if %option yylineno.
+    code.push_str(&format!("#define YY_USE_LINENO {}\n", if options.contains("yylineno") { "1" } else { "YY_LOCATIONS" }));
+    // Like flex, but helpers nothing calls are left out rather than unused.
+    let all_code = rules.iter().fold(all_user_code.clone(), |all, r| all + "\n" + &r.action);
+    for (option, name, guard) in [("noinput", "input", "YY_NO_INPUT"), ("nounput", "unput", "YY_NO_UNPUT")] {
+        if options.contains(option) || !mentions(&all_code, name) {
+            code.push_str(&format!("#define {}\n", guard));
+        }
+    }
+    // Without a user yywrap, behave as if linked against libfl.
+    if options.contains("noyywrap") || !defines_function(&all_user_code, "yywrap") {
+        code.push_str(if re.reentrant { "#define yywrap(yyscanner) 1\n\n" } else { "#define yywrap() 1\n\n" });
+    } else {
+        code.push_str(&re.fill("int yywrap(@void@);\n\n"));
+    }
+    code.push_str(&re.fill(SCANNER_MACROS));
+
+    // --- Tables ---
+    let tables = scanner_tables(&automaton, &dfa)?;
+    code.push_str(&format!(
+        "/* {} DFA states over {} byte classes; {} packed transition slots. */\n",
+        dfa.states.len(),
+        tables.classes,
+        tables.nxt.len()
+    ));
+    c_array(&mut code, "unsigned char", "yy_ec", &tables.ec);
+    for (name, values) in tables.arrays() {
+        c_array(&mut code, int_type(values), name, values);
+    }
+    emit_head_length(&mut code, &automaton)?;
+    if mentions(&all_code, "yyless") {
+        code.push_str(&re.fill(SCANNER_LESS));
+    }
+    code.push_str(&re.fill(SCANNER_RUNTIME));
+    if re.reentrant {
+        re.emit_api(&mut code);
+    }
+
+    // --- yylex() ---
+    code.push_str(&re.fill(SCANNER_MATCH));
+    for (ri, rule) in rules.iter().enumerate() {
+        code.push_str(&format!("        case {}: /* rule {}, line {}: {} */\n", ri + 1, rule.index + 1, rule.line, comment_safe(&rule.pattern)));
+        if !rule.parsed.eof {
+            code.push_str("            YY_RULE_SETUP\n");
+        }
+        code.push_str("            {\n");
+        code.push_str(&user_code(codegen, SCANNER_SOURCE, rule.action_line, &rule.action));
+        code.push_str("            }\n");
+        if rule.parsed.eof {
+            // Stop unless the action switched to new input.
+            code.push_str("            if (yy_pos >= yy_buf_len)\n                yyterminate();\n");
+        }
+        code.push_str("            YY_BREAK\n");
+    }
+    code.push_str(&format!("        case {}: /* default rule */\n            YY_RULE_SETUP\n            ECHO;\n            YY_BREAK\n", default_rule));
+    code.push_str("        default:\n            YY_FATAL_ERROR(\"fatal flex scanner internal error--no action found\");\n        }\n    }\n}\n");
+
+    if let Some(e) = epilogue {
+        code.push_str("\n/* --- EPILOGUE --- */\n");
+        code.push_str(&user_code(codegen, SCANNER_SOURCE, *epilogue_line, e));
+    }
+    // A bridged yylex needs the parser's YYSTYPE: only yyparse can drive it.
+    if !codegen.combined && !re.bridge && !defines_function(&all_user_code, "main") {
+        code.push_str(if re.reentrant { REENTRANT_MAIN } else { LIBFL_MAIN });
+    }
+    Ok(number_output_lines(code, SCANNER_OUTPUT))
+}
@@ -1,4 +1,29 @@
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+pub mod grammar;
+pub mod ambiguity;
+pub mod ll1;
+pub mod transform;
+pub mod cyk;
+pub mod regex;
+pub mod generator;
+pub mod automaton;
+pub mod visualize;
+pub mod interpreter;
+pub mod lint;
+pub mod explain;
+pub mod dialect;
+pub mod testbench;
+pub mod equivalence;
+pub mod utf8;
+pub mod lexgen;
+pub mod lalr;
+pub mod yaccgen;
+pub mod project;
+pub mod diagnostics;
+pub mod rustgen;
+pub mod descent;
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 pub enum TokenType {
@@ -29,16 +54,45 @@ pub struct Token {
 #[serde(tag = "type")]
 pub enum ASTNode {
     // NEW: FlexFile now holds the Prologue and Epilogue C code
-    FlexFile { prologue: Option<String>, rules: Vec<ASTNode>, epilogue: Option<String> },
-    FlexRule { pattern: String, action: String },
-    BisonFile { declarations: Vec<ASTNode>, rules: Vec<ASTNode> },
+    /// `*_line`: where the prologue and epilogue code starts (0 if absent).
+    FlexFile { prologue: Option<String>, prologue_line: usize, definitions: Vec<ASTNode>, rules: Vec<ASTNode>, epilogue: Option<String>, epilogue_line: usize },
+    FlexDefinition { name: String, pattern: String },
+    FlexStartConditionDecl { names: Vec<String>, exclusive: bool },
+    /// `%option`s on one line; `values` holds those given as `name=value`.
+    FlexOption { names: Vec<String>, values: BTreeMap<String, String> },
+    FlexRule { pattern: String, action: String, line: usize, column: usize, action_line: usize },
+    BisonFile { prologue: Option<String>, prologue_line: usize, declarations: Vec<ASTNode>, rules: Vec<ASTNode>, epilogue: Option<String>, epilogue_line: usize },
     BisonTokenDecl { names: Vec<String> },
+    /// `%left`, `%right`, `%nonassoc` or `%precedence`: one precedence level.
+    BisonPrecedenceDecl { associativity: String, symbols: Vec<String> },
+    /// `%type <tag> ...` (also produced by `%token <tag> ...`).
+    BisonTypeDecl { type_name: String, symbols: Vec<String> },
+    BisonUnionDecl { body: String },
+    BisonStartDecl { symbol: String },
+    /// `%define name value`; `%pure-parser` is `%define api.pure`.
+    BisonDefine { name: String, value: String },
+    /// `%parse-param`, `%lex-param` or `%param` (`kind` "parse", "lex" or
+    /// "param"), each param a declaration like `int *count`.
+    BisonParamDecl { kind: String, params: Vec<String> },
+    /// `%locations`: track `yylloc` even if no action uses `@$` or `@n`.
+    BisonLocations,
     BisonGrammarRule { name: String, alternatives: Vec<ASTNode> },
-    BisonAlternative { symbols: Vec<String>, action: Option<String> },
+    BisonAlternative { symbols: Vec<String>, action: Option<String>, prec: Option<String>, action_line: usize },
     Error { message: String, line: usize, column: usize },
 }
 
-pub fn lexer(input: &str) -> Vec<Token> {
+pub fn lexer(input: &str) -> Vec<Token> { lex(input, false) }
+
+/// Flex-aware variant: definition lines and rule patterns are read up to the
+/// first unquoted whitespace, so `[ \t]+` or `"if"` stay one Regex token.
+pub fn flex_lexer(input: &str) -> Vec<Token> { lex(input, true) }
+
+/// Line where `code.trim()` starts, for code that starts on `line`.
+fn content_line(code: &str, line: usize) -> usize {
+    line + code[..code.len() - code.trim_start().len()].matches('\n').count()
+}
+
+fn lex(input: &str, flex: bool) -> Vec<Token> {
     let mut tokens = Vec::new();
     let mut chars = input.chars().peekable();
     let mut line = 1;
@@ -47,6 +101,103 @@ pub fn lexer(input: &str) -> Vec<Token> {
 
     while let Some(&c) = chars.peek() {
         let start_col = column;
+
+        // Flex definitions section: `NAME   pattern`
+        if flex && section_count == 0 && column == 1 && (c.is_alphabetic() || c == '_') {
+            let mut name = String::new();
+            while let Some(&nc) = chars.peek() {
+                if !nc.is_alphanumeric() && nc != '_' && nc != '-' { break; }
+                name.push(nc);
+                chars.next(); column += 1;
+            }
+            tokens.push(Token { token_type: TokenType::Identifier, value: name, line, column: start_col });
+            while let Some(&nc) = chars.peek() {
+                if nc != ' ' && nc != '\t' { break; }
+                chars.next(); column += 1;
+            }
+            let pattern_col = column;
+            let mut pattern = String::new();
+            while let Some(&nc) = chars.peek() {
+                if nc == '\n' { break; }
+                pattern.push(nc);
+                chars.next(); column += 1;
+            }
+            let pattern = pattern.trim_end().to_string();
+            if !pattern.is_empty() {
+                tokens.push(Token { token_type: TokenType::Regex, value: pattern, line, column: pattern_col });
+            }
+            continue;
+        }
+
+        // Flex rules section: a pattern starts in column 1 and runs to the
+        // first whitespace outside quotes and brackets.
+        let is_directive = c == '%' && {
+            let mut look = chars.clone();
+            look.next();
+            matches!(look.peek(), Some('%') | Some('{'))
+        };
+        if flex && section_count == 1 && column == 1 && !c.is_whitespace() && !is_directive {
+            let mut pattern = String::new();
+            let mut in_quotes = false;
+            let mut in_class = false;
+            while let Some(&nc) = chars.peek() {
+                if nc == '\n' || (!in_quotes && !in_class && (nc == ' ' || nc == '\t')) { break; }
+                pattern.push(nc);
+                chars.next(); column += 1;
+                match nc {
+                    '\\' => {
+                        if let Some(&esc) = chars.peek() {
+                            if esc == '\n' { break; }
+                            pattern.push(esc);
+                            chars.next(); column += 1;
+                        }
+                    }
+                    '"' if !in_class => in_quotes = !in_quotes,
+                    '[' if !in_quotes && !in_class => {
+                        in_class = true;
+                        // A leading `^` or `]` is part of the class, not its end.
+                        if let Some(&'^') = chars.peek() { pattern.push('^'); chars.next(); column += 1; }
+                        if let Some(&']') = chars.peek() { pattern.push(']'); chars.next(); column += 1; }
+                    }
+                    '[' if in_class && chars.peek() == Some(&':') => {
+                        // POSIX class like [:alpha:] inside a bracket expression
+                        while let Some(&pc) = chars.peek() {
+                            pattern.push(pc);
+                            chars.next(); column += 1;
+                            if pc == ']' && pattern.ends_with(":]") { break; }
+                        }
+                    }
+                    ']' if in_class => in_class = false,
+                    _ => {}
+                }
+            }
+            tokens.push(Token { token_type: TokenType::Regex, value: pattern, line, column: start_col });
+
+            while let Some(&nc) = chars.peek() {
+                if nc != ' ' && nc != '\t' { break; }
+                chars.next(); column += 1;
+            }
+            // Actions without braces run to the end of the line; a bare
+            // pattern gets an empty action (the match is discarded).
+            match chars.peek() {
+                Some('{') | Some('|') => {}
+                None | Some('\n') | Some('\r') => {
+                    tokens.push(Token { token_type: TokenType::ActionBlock, value: String::new(), line, column });
+                }
+                Some(_) => {
+                    let action_col = column;
+                    let mut action = String::new();
+                    while let Some(&ac) = chars.peek() {
+                        if ac == '\n' { break; }
+                        action.push(ac);
+                        chars.next(); column += 1;
+                    }
+                    tokens.push(Token { token_type: TokenType::ActionBlock, value: action.trim().to_string(), line, column: action_col });
+                }
+            }
+            continue;
+        }
+
         match c {
             '\n' => { line += 1; column = 1; chars.next(); }
             ' ' | '\t' | '\r' => { chars.next(); column += 1; }
@@ -70,6 +221,7 @@ pub fn lexer(input: &str) -> Vec<Token> {
                 if let Some(&'{') = chars.peek() {
                     // PARSE PROLOGUE: %{ ... %}
                     chars.next(); column += 1;
+                    let start_line = line;
                     let mut code = String::new();
                     while let Some(&nc) = chars.peek() {
                         if nc == '%' {
@@ -86,7 +238,7 @@ pub fn lexer(input: &str) -> Vec<Token> {
                             chars.next();
                         }
                     }
-                    tokens.push(Token { token_type: TokenType::Prologue, value: code.trim().to_string(), line, column: start_col });
+                    tokens.push(Token { token_type: TokenType::Prologue, value: code.trim().to_string(), line: content_line(&code, start_line), column: start_col });
                 } else if let Some(&'%') = chars.peek() {
                     chars.next(); column += 1;
                     tokens.push(Token { token_type: TokenType::SectionSeparator, value: "%%".to_string(), line, column: start_col });
@@ -94,6 +246,7 @@ pub fn lexer(input: &str) -> Vec<Token> {
                     // Count sections to know when the Epilogue starts
                     section_count += 1;
                     if section_count == 2 {
+                        let start_line = line;
                         let mut epilogue_code = String::new();
                         while let Some(&nc) = chars.peek() {
                             if nc == '\n' { line += 1; column = 1; } else { column += 1; }
@@ -101,13 +254,14 @@ pub fn lexer(input: &str) -> Vec<Token> {
                             chars.next();
                         }
                         if !epilogue_code.trim().is_empty() {
-                            tokens.push(Token { token_type: TokenType::Epilogue, value: epilogue_code.trim().to_string(), line, column: start_col });
+                            tokens.push(Token { token_type: TokenType::Epilogue, value: epilogue_code.trim().to_string(), line: content_line(&epilogue_code, start_line), column: start_col });
                         }
                     }
                 } else {
+                    // `%parse-param` and `%pure-parser` are one keyword.
                     let mut kw = String::from("%");
                     while let Some(&nc) = chars.peek() {
-                        if !nc.is_alphabetic() { break; }
+                        if !(nc.is_alphabetic() || (nc == '-' && kw.len() > 1)) { break; }
                         kw.push(nc);
                         chars.next(); column += 1;
                     }
@@ -115,16 +269,37 @@ pub fn lexer(input: &str) -> Vec<Token> {
                 }
             }
             '{' => {
+                let start_line = line;
                 let mut code = String::new();
                 chars.next(); column += 1;
+                // Track nesting so `{ if (x) { y(); } }` is one block; braces
+                // inside C string and char literals do not count.
+                let mut depth = 0;
+                let mut quote: Option<char> = None;
                 while let Some(&nc) = chars.peek() {
-                    if nc == '}' { break; }
+                    if quote.is_none() && nc == '}' {
+                        if depth == 0 { break; }
+                        depth -= 1;
+                    }
                     if nc == '\n' { line += 1; column = 1; } else { column += 1; }
                     code.push(nc);
                     chars.next();
+                    match (quote, nc) {
+                        (None, '{') => depth += 1,
+                        (None, '"') | (None, '\'') => quote = Some(nc),
+                        (Some(q), _) if nc == q => quote = None,
+                        (Some(_), '\\') => {
+                            if let Some(&esc) = chars.peek() {
+                                if esc == '\n' { line += 1; column = 1; } else { column += 1; }
+                                code.push(esc);
+                                chars.next();
+                            }
+                        }
+                        _ => {}
+                    }
                 }
                 chars.next(); column += 1;
-                tokens.push(Token { token_type: TokenType::ActionBlock, value: code.trim().to_string(), line, column: start_col });
+                tokens.push(Token { token_type: TokenType::ActionBlock, value: code.trim().to_string(), line: content_line(&code, start_line), column: start_col });
             }
             c if c.is_alphabetic() || c == '_' => {
                 let mut ident = String::new();
@@ -151,6 +326,22 @@ pub fn lexer(input: &str) -> Vec<Token> {
     tokens
 }
 
+/// Appends a `%{ %}` block to `code`, padding with newlines so the text keeps
+/// the source's line numbering from `code_line` on.
+fn append_code_block(code: &mut Option<String>, code_line: &mut usize, block: &str, line: usize) {
+    match code {
+        Some(c) => {
+            let end = *code_line + c.matches('\n').count();
+            c.push_str(&"\n".repeat(line.saturating_sub(end).max(1)));
+            c.push_str(block);
+        }
+        None => {
+            *code = Some(block.to_string());
+            *code_line = line;
+        }
+    }
+}
+
 pub struct Parser {
     tokens: Vec<Token>,
     current: usize,
@@ -167,35 +358,132 @@ impl Parser {
     pub fn parse_bison_program(&mut self) -> ASTNode {
         let mut declarations = Vec::new();
         let mut rules = Vec::new();
-        while let Some(t) = self.peek() {
+        let mut prologue: Option<String> = None;
+        let mut prologue_line = 0;
+        let mut epilogue = None;
+        let mut epilogue_line = 0;
+        while let Some(t) = self.peek().cloned() {
             if t.token_type == TokenType::SectionSeparator {
                 self.advance();
                 break;
             }
-            if t.token_type == TokenType::BisonKeyword && t.value == "%token" {
-                self.advance();
-                let mut names = Vec::new();
-                while let Some(nt) = self.peek() {
-                    if nt.token_type == TokenType::Identifier {
-                        names.push(nt.value.clone());
+            self.advance();
+            match (t.token_type, t.value.as_str()) {
+                // Several %{ %} blocks are emitted in order.
+                (TokenType::Prologue, _) => append_code_block(&mut prologue, &mut prologue_line, &t.value, t.line),
+                (TokenType::BisonKeyword, "%token") => {
+                    let (type_name, names) = self.parse_bison_symbol_list();
+                    if let Some(type_name) = type_name {
+                        declarations.push(ASTNode::BisonTypeDecl { type_name, symbols: names.clone() });
+                    }
+                    declarations.push(ASTNode::BisonTokenDecl { names });
+                }
+                (TokenType::BisonKeyword, "%left" | "%right" | "%nonassoc" | "%precedence") => {
+                    let (type_name, symbols) = self.parse_bison_symbol_list();
+                    if let Some(type_name) = type_name {
+                        declarations.push(ASTNode::BisonTypeDecl { type_name, symbols: symbols.clone() });
+                    }
+                    declarations.push(ASTNode::BisonPrecedenceDecl { associativity: t.value[1..].to_string(), symbols });
+                }
+                (TokenType::BisonKeyword, "%type" | "%nterm") => {
+                    if let (Some(type_name), symbols) = self.parse_bison_symbol_list() {
+                        declarations.push(ASTNode::BisonTypeDecl { type_name, symbols });
+                    }
+                }
+                (TokenType::BisonKeyword, "%union") => {
+                    // `%union name { ... }`: the optional name is not needed.
+                    if self.peek().is_some_and(|n| n.token_type == TokenType::Identifier) {
                         self.advance();
-                    } else {
-                        break;
+                    }
+                    if let Some(body) = self.peek().filter(|n| n.token_type == TokenType::ActionBlock).cloned() {
+                        self.advance();
+                        declarations.push(ASTNode::BisonUnionDecl { body: body.value });
                     }
                 }
-                declarations.push(ASTNode::BisonTokenDecl { names });
-            } else {
-                self.advance();
+                (TokenType::BisonKeyword, "%define") => {
+                    let mut words = self.same_line_words(t.line).into_iter();
+                    if let Some(name) = words.next() {
+                        let value = words.collect::<Vec<_>>().join(" ");
+                        declarations.push(ASTNode::BisonDefine { name, value });
+                    }
+                }
+                (TokenType::BisonKeyword, "%locations") => {
+                    declarations.push(ASTNode::BisonLocations);
+                }
+                (TokenType::BisonKeyword, "%pure-parser") => {
+                    declarations.push(ASTNode::BisonDefine { name: "api.pure".to_string(), value: String::new() });
+                }
+                (TokenType::BisonKeyword, "%parse-param" | "%lex-param" | "%param") => {
+                    let mut params = Vec::new();
+                    while let Some(block) = self.peek().filter(|n| n.token_type == TokenType::ActionBlock).cloned() {
+                        self.advance();
+                        params.push(block.value);
+                    }
+                    let kind = match t.value.as_str() {
+                        "%parse-param" => "parse",
+                        "%lex-param" => "lex",
+                        _ => "param",
+                    };
+                    declarations.push(ASTNode::BisonParamDecl { kind: kind.to_string(), params });
+                }
+                (TokenType::BisonKeyword, "%start") => {
+                    if let Some(symbol) = self.peek().filter(|n| n.token_type == TokenType::Identifier).cloned() {
+                        self.advance();
+                        declarations.push(ASTNode::BisonStartDecl { symbol: symbol.value });
+                    }
+                }
+                _ => {}
             }
         }
-        while self.current < self.tokens.len() {
-            if self.peek().unwrap().token_type == TokenType::Identifier {
-                rules.push(self.parse_bison_rule());
-            } else {
-                self.advance();
+        while let Some(t) = self.peek() {
+            match t.token_type {
+                TokenType::Identifier => rules.push(self.parse_bison_rule()),
+                TokenType::Epilogue => {
+                    epilogue = Some(t.value.clone());
+                    epilogue_line = t.line;
+                    self.advance();
+                }
+                _ => { self.advance(); }
             }
         }
-        ASTNode::BisonFile { declarations, rules }
+        ASTNode::BisonFile { prologue, prologue_line, declarations, rules, epilogue, epilogue_line }
+    }
+
+    /// Tokens left on `line`, glued into words where nothing separates them
+    /// (`api` `.pure` is `api.pure`); `{...}` and `"..."` lose their delimiters.
+    fn same_line_words(&mut self, line: usize) -> Vec<String> {
+        let mut words: Vec<String> = Vec::new();
+        let mut end = None;
+        while let Some(t) = self.peek().filter(|n| n.line == line).cloned() {
+            self.advance();
+            let value = match t.token_type {
+                TokenType::Literal => t.value.trim_matches('"').to_string(),
+                _ => t.value.clone(),
+            };
+            match words.last_mut() {
+                Some(last) if end == Some(t.column) && t.token_type != TokenType::ActionBlock => last.push_str(&value),
+                _ => words.push(value),
+            }
+            end = Some(t.column + t.value.chars().count());
+        }
+        words
+    }
+
+    /// `<tag>? symbol...` after a declaration keyword.
+    fn parse_bison_symbol_list(&mut self) -> (Option<String>, Vec<String>) {
+        let mut type_name = None;
+        let mut symbols = Vec::new();
+        while let Some(t) = self.peek() {
+            match t.token_type {
+                TokenType::Identifier | TokenType::Literal => symbols.push(t.value.clone()),
+                TokenType::Regex if t.value.starts_with('<') && t.value.ends_with('>') && type_name.is_none() => {
+                    type_name = Some(t.value[1..t.value.len() - 1].to_string());
+                }
+                _ => break,
+            }
+            self.advance();
+        }
+        (type_name, symbols)
     }
 
     fn parse_bison_rule(&mut self) -> ASTNode {
@@ -208,24 +496,35 @@ impl Parser {
         let mut alternatives = Vec::new();
         let mut current_symbols = Vec::new();
         let mut current_action = None;
+        let mut current_prec = None;
+        let mut current_action_line = 0;
         while let Some(t) = self.peek() {
             match t.token_type {
+                TokenType::BisonKeyword if t.value == "%prec" => {
+                    self.advance();
+                    if let Some(sym) = self.peek().filter(|n| matches!(n.token_type, TokenType::Identifier | TokenType::Literal)) {
+                        current_prec = Some(sym.value.clone());
+                        self.advance();
+                    }
+                }
                 TokenType::Identifier | TokenType::Literal => {
                     current_symbols.push(t.value.clone());
                     self.advance();
                 }
                 TokenType::ActionBlock => {
                     current_action = Some(t.value.clone());
+                    current_action_line = t.line;
                     self.advance();
                 }
                 TokenType::Pipe => {
-                    alternatives.push(ASTNode::BisonAlternative { symbols: current_symbols.clone(), action: current_action.clone() });
+                    alternatives.push(ASTNode::BisonAlternative { symbols: current_symbols.clone(), action: current_action.clone(), prec: current_prec.clone(), action_line: current_action_line });
                     current_symbols.clear();
                     current_action = None;
+                    current_prec = None;
                     self.advance();
                 }
                 TokenType::Semicolon => {
-                    alternatives.push(ASTNode::BisonAlternative { symbols: current_symbols.clone(), action: current_action.clone() });
+                    alternatives.push(ASTNode::BisonAlternative { symbols: current_symbols.clone(), action: current_action.clone(), prec: current_prec.clone(), action_line: current_action_line });
                     self.advance();
                     break;
                 }
@@ -238,59 +537,144 @@ impl Parser {
     // UPDATED: Now captures Prologue and Epilogue correctly
     pub fn parse_flex_program(&mut self) -> ASTNode {
         let mut rules = Vec::new();
+        let mut definitions = Vec::new();
         let mut prologue = None;
+        let mut prologue_line = 0;
         let mut epilogue = None;
+        let mut epilogue_line = 0;
+        // Files without any `%%` are treated as a bare rules section.
+        let mut section = if self.tokens.iter().any(|t| t.token_type == TokenType::SectionSeparator) { 0 } else { 1 };
+        // Patterns whose action is `|` share the next rule's action.
+        let mut pending: Vec<(String, usize, usize)> = Vec::new();
 
         while self.current < self.tokens.len() {
             if let Some(t) = self.peek() {
                 if t.token_type == TokenType::Prologue {
-                    prologue = Some(t.value.clone());
+                    let (value, line) = (t.value.clone(), t.line);
+                    append_code_block(&mut prologue, &mut prologue_line, &value, line);
                     self.advance();
                     continue;
                 }
                 if t.token_type == TokenType::Epilogue {
                     epilogue = Some(t.value.clone());
+                    epilogue_line = t.line;
                     self.advance();
                     continue;
                 }
                 if t.token_type == TokenType::SectionSeparator {
+                    section += 1;
                     self.advance();
                     continue;
                 }
             }
-            
+
+            if section == 0 {
+                if let Some(decl) = self.parse_flex_definition() {
+                    definitions.push(decl);
+                }
+                continue;
+            }
+
             if self.current < self.tokens.len() {
-                let rule = self.parse_flex_rules();
-                rules.push(rule);
+                match self.parse_flex_rules() {
+                    ASTNode::FlexRule { pattern, action, line, column, .. } if action == "|" => pending.push((pattern, line, column)),
+                    ASTNode::FlexRule { pattern, action, line, column, action_line } => {
+                        for (p, l, c) in pending.drain(..) {
+                            rules.push(ASTNode::FlexRule { pattern: p, action: action.clone(), line: l, column: c, action_line });
+                        }
+                        rules.push(ASTNode::FlexRule { pattern, action, line, column, action_line });
+                    }
+                    other => rules.push(other),
+                }
+            }
+        }
+        if !pending.is_empty() {
+            let (l, c) = self.tokens.last().map(|t| (t.line, t.column)).unwrap_or((0, 0));
+            rules.push(ASTNode::Error { message: "Expected Action Block after '|'".to_string(), line: l, column: c });
+        }
+        ASTNode::FlexFile { prologue, prologue_line, definitions, rules, epilogue, epilogue_line }
+    }
+
+    /// One line of the flex definitions section: `NAME pattern`, `%x A B` or `%option ...`.
+    fn parse_flex_definition(&mut self) -> Option<ASTNode> {
+        let t = self.advance()?.clone();
+        let same_line = |p: &Parser| p.peek().filter(|n| n.line == t.line).cloned();
+        match t.token_type {
+            TokenType::Identifier => {
+                let next = same_line(self)?;
+                if next.token_type != TokenType::Regex { return None; }
+                self.advance();
+                Some(ASTNode::FlexDefinition { name: t.value, pattern: next.value })
+            }
+            TokenType::BisonKeyword if t.value == "%x" || t.value == "%s" => {
+                let mut names = Vec::new();
+                while let Some(n) = same_line(self) {
+                    if n.token_type != TokenType::Identifier { break; }
+                    names.push(n.value);
+                    self.advance();
+                }
+                Some(ASTNode::FlexStartConditionDecl { names, exclusive: t.value == "%x" })
+            }
+            TokenType::BisonKeyword if t.value == "%option" => {
+                // `outfile="x.c"` is named `outfile`, its value kept aside.
+                // A quoted value with spaces in it comes in several words.
+                let mut words: Vec<String> = Vec::new();
+                for word in self.same_line_words(t.line) {
+                    match words.last_mut() {
+                        Some(last) if last.matches('"').count() % 2 == 1 => {
+                            last.push(' ');
+                            last.push_str(&word);
+                        }
+                        _ => words.push(word),
+                    }
+                }
+                let mut names = Vec::new();
+                let mut values = BTreeMap::new();
+                for word in words {
+                    let (name, value) = match word.split_once('=') {
+                        Some((name, value)) => (name.to_string(), Some(value.trim_matches('"').to_string())),
+                        None => (word, None),
+                    };
+                    if !name.starts_with(|c: char| c.is_alphabetic()) { continue; }
+                    if let Some(value) = value { values.insert(name.clone(), value); }
+                    names.push(name);
+                }
+                Some(ASTNode::FlexOption { names, values })
+            }
+            _ => {
+                // %pointer and friends: skip the rest of the line.
+                while same_line(self).is_some() { self.advance(); }
+                None
             }
         }
-        ASTNode::FlexFile { prologue, rules, epilogue }
     }
 
     fn parse_flex_rules(&mut self) -> ASTNode {
         let pattern_token = self.advance();
-        let pattern = match pattern_token {
-            Some(t) if t.token_type == TokenType::Regex || t.token_type == TokenType::Identifier => t.value.clone(),
+        let (pattern, line, column) = match pattern_token {
+            Some(t) if matches!(t.token_type, TokenType::Regex | TokenType::Identifier | TokenType::Literal) => (t.value.clone(), t.line, t.column),
             _ => {
                 let (l, c) = self.peek().map(|t| (t.line, t.column)).unwrap_or((0, 0));
                 return ASTNode::Error { message: "Expected Regex Pattern".to_string(), line: l, column: c };
             },
         };
         let action_token = self.advance();
+        let action_line = action_token.map_or(line, |t| t.line);
         let action = match action_token {
             Some(t) if t.token_type == TokenType::ActionBlock => t.value.clone(),
+            Some(t) if t.token_type == TokenType::Pipe => "|".to_string(),
             _ => {
                 let (l, c) = self.peek().map(|t| (t.line, t.column)).unwrap_or((0, 0));
                 return ASTNode::Error { message: "Expected Action Block {...}".to_string(), line: l, column: c };
             },
         };
-        ASTNode::FlexRule { pattern, action }
+        ASTNode::FlexRule { pattern, action, line, column, action_line }
     }
 }
 
 pub fn scan_code(input: &str) -> Vec<Token> { lexer(input) }
 pub fn parse_flex(input: &str) -> ASTNode {
-    let tokens = lexer(input);
+    let tokens = flex_lexer(input);
     let mut parser = Parser::new(tokens);
     parser.parse_flex_program()
 }
@@ -300,95 +684,112 @@ pub fn parse_bison(input: &str) -> ASTNode {
     parser.parse_bison_program()
 }
 
+/// Token a flex action hands to the parser, e.g. `NUMBER` for
+/// `{ yylval = atoi(yytext); return NUMBER; }` or `'+'` for `return '+';`.
+pub fn action_return_token(action: &str) -> Option<String> {
+    let mut rest = action;
+    while let Some(idx) = rest.find("return") {
+        let before_ok = rest[..idx].chars().last().is_none_or(|c| !c.is_alphanumeric() && c != '_');
+        let after = &rest[idx + "return".len()..];
+        rest = after;
+        if !before_ok || after.starts_with(|c: char| c.is_alphanumeric() || c == '_') {
+            continue;
+        }
+        let after = after.trim_start().trim_start_matches('(').trim_start();
+        if let Some(quoted) = after.strip_prefix('\'') {
+            let end = quoted.find('\'')?;
+            return Some(format!("'{}'", &quoted[..end]));
+        }
+        let ident: String = after.chars().take_while(|c| c.is_alphanumeric() || *c == '_').collect();
+        if !ident.is_empty() && !ident.starts_with(|c: char| c.is_ascii_digit()) {
+            return Some(ident);
+        }
+    }
+    None
+}
+
 // --- PHASE 5 & 6: ADVANCED CODE GENERATION ---
+#[derive(Deserialize, Clone, Debug, Default)]
+#[serde(default)]
+pub struct CodegenOptions {
+    /// Scanner and parser are linked together: the scanner leaves `main` to
+    /// the parser, and the parser never substitutes its own `yylex`.
+    pub combined: bool,
+    /// Leave out the `#line` directives tying user code to the spec.
+    pub no_line: bool,
+    /// Spec file name for `#line`; defaults to scanner.l or parser.y.
+    pub source_name: Option<String>,
+}
+
+/// Language the generators write.
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Target {
+    #[default]
+    C,
+    Rust,
+}
+
 pub fn generate_c_code(ast: &ASTNode) -> String {
-    let execution_stub = "\n/* --- EXECUTION ENTRY POINT --- */\nint main() {\n    char input[1024];\n    printf(\"Structura.ai Execution Sandbox Initialized.\\n\");\n    printf(\"Awaiting input stream...\\n\");\n    if (fgets(input, sizeof(input), stdin) != NULL) {\n        printf(\"\\n[Received Input]: %s\", input);\n        printf(\"[System]: Parsing input against generated grammar...\\n\");\n        /* Real yylex() / yyparse() integration happens here */\n        printf(\"[Success]: Execution finished with exit code 0.\\n\");\n    }\n    return 0;\n}\n";
+    generate_c_code_with(ast, &CodegenOptions::default())
+}
 
+pub fn generate_c_code_with(ast: &ASTNode, options: &CodegenOptions) -> String {
     match ast {
-        ASTNode::FlexFile { prologue, rules, epilogue } => {
-            let mut code = String::from("/* Generated by Structura.ai Lexical Engine */\n");
-            code.push_str("#include <stdio.h>\n#include <stdlib.h>\n#include <string.h>\n#include <regex.h>\n\n");
-            
-            // 1. Inject User Prologue (Fixes undeclared variables)
-            if let Some(p) = prologue {
-                code.push_str("/* --- PROLOGUE --- */\n");
-                code.push_str(p);
-                code.push_str("\n\n");
-            }
-            
-            code.push_str("int yylex() {\n");
-            code.push_str("    char yytext_buf[1024];\n");
-            code.push_str("    if (fgets(yytext_buf, sizeof(yytext_buf), stdin) == NULL) return 0;\n");
-            code.push_str("    char* yytext = yytext_buf;\n");
-            code.push_str("    regex_t reg;\n\n");
-            
-            // 2. Generate actual C-Regex loops (Fixes Expected Expression error)
-            for rule in rules {
-                if let ASTNode::FlexRule { pattern, action } = rule {
-                    // Escape slashes and quotes so C strings don't break
-                    let c_pattern = pattern.replace("\\", "\\\\").replace("\"", "\\\"");
-                    code.push_str(&format!("    /* Match Pattern: {} */\n", pattern));
-                    code.push_str(&format!("    if (regcomp(&reg, \"{}\", REG_EXTENDED) == 0) {{\n", c_pattern));
-                    code.push_str("        regmatch_t match;\n");
-                    code.push_str("        char* cursor = yytext;\n");
-                    code.push_str("        while (regexec(&reg, cursor, 1, &match, 0) == 0) {\n");
-                    code.push_str(&format!("            {}\n", action));
-                    code.push_str("            cursor += match.rm_eo;\n");
-                    code.push_str("            if (match.rm_eo == 0) break;\n"); // Prevent infinite loops
-                    code.push_str("        }\n");
-                    code.push_str("        regfree(&reg);\n");
-                    code.push_str("    }\n\n");
-                }
-            }
-            code.push_str("    return 0;\n}\n");
-            
-            // 3. Inject User Epilogue (Fixes the main() block being parsed as regex)
-            if let Some(e) = epilogue {
-                code.push_str("\n/* --- EPILOGUE --- */\n");
-                code.push_str(e);
-                code.push_str("\n");
-            } else {
-                code.push_str(execution_stub);
-            }
-            code
+        ASTNode::FlexFile { .. } => {
+            lexgen::generate_scanner(ast, options).unwrap_or_else(|e| format!("/* Cannot generate scanner: {} */\n", lexgen::comment_safe(&e)))
         },
-        ASTNode::BisonFile { declarations, rules } => {
-            let mut code = String::from("/* Generated by Structura.ai Syntax Engine */\n");
-            code.push_str("#include <stdio.h>\n#include <stdlib.h>\n\n");
-            
-            let mut token_val = 258;
-            for decl in declarations {
-                if let ASTNode::BisonTokenDecl { names } = decl {
-                    for name in names {
-                        code.push_str(&format!("#define {} {}\n", name, token_val));
-                        token_val += 1;
-                    }
-                }
-            }
-            
-            code.push_str("\nint yyparse() {\n");
-            
-            for rule in rules {
-                if let ASTNode::BisonGrammarRule { name, alternatives } = rule {
-                    code.push_str(&format!("    /* Grammar Rule: {} */\n", name));
-                    for alt in alternatives {
-                        if let ASTNode::BisonAlternative { symbols, action } = alt {
-                            code.push_str(&format!("    /* -> {} */\n", symbols.join(" ")));
-                            if let Some(act) = action {
-                                code.push_str(&format!("    {{ {} }}\n", act));
-                            }
-                        }
-                    }
-                    code.push_str("\n");
-                }
-            }
-            code.push_str("    return 0;\n}\n");
-            code.push_str(execution_stub); 
-            code
+        ASTNode::BisonFile { .. } => {
+            yaccgen::generate_parser(ast, options).unwrap_or_else(|e| format!("/* Cannot generate parser: {} */\n", lexgen::comment_safe(&e)))
         },
         ASTNode::Error { .. } => {
             "/* Fix syntax errors to generate C code */".to_string()
         },
         _ => "/* No valid AST to generate code from */".to_string(),
     }
+}
+
+/// Rust counterpart of `generate_c_code`: one self-contained module with a
+/// `Scanner` or a `Parser`, whose actions are written in Rust.
+pub fn generate_rust_code(ast: &ASTNode) -> String {
+    match ast {
+        ASTNode::FlexFile { .. } => {
+            rustgen::generate_rust_scanner(ast).unwrap_or_else(|e| format!("// Cannot generate scanner: {}\n", e.replace('\n', " ")))
+        },
+        ASTNode::BisonFile { .. } => {
+            rustgen::generate_rust_parser(ast).unwrap_or_else(|e| format!("// Cannot generate parser: {}\n", e.replace('\n', " ")))
+        },
+        ASTNode::Error { .. } => {
+            "// Fix syntax errors to generate Rust code".to_string()
+        },
+        _ => "// No valid AST to generate code from".to_string(),
+    }
+}
+
+/// A recursive-descent parser in `target`'s language instead of LALR tables;
+/// only LL(1) grammars have one.
+pub fn generate_descent_code(ast: &ASTNode, target: Target, options: &CodegenOptions) -> String {
+    let generated = match (ast, target) {
+        (ASTNode::BisonFile { .. }, Target::C) => descent::generate_c_descent(ast, options),
+        (ASTNode::BisonFile { .. }, Target::Rust) => descent::generate_rust_descent(ast),
+        _ => return match target {
+            Target::C => generate_c_code_with(ast, options),
+            Target::Rust => generate_rust_code(ast),
+        },
+    };
+    generated.unwrap_or_else(|e| match target {
+        Target::C => format!("/* Cannot generate parser: {} */\n", lexgen::comment_safe(&e)),
+        Target::Rust => format!("// Cannot generate parser: {}\n", e.replace('\n', " ")),
+    })
+}
+
+/// Header shared by the generated parser and scanner (y.tab.h); only
+/// grammars have one.
+pub fn generate_c_header(ast: &ASTNode) -> Option<String> {
+    match ast {
+        ASTNode::BisonFile { .. } => Some(
+            yaccgen::generate_header(ast).unwrap_or_else(|e| format!("/* Cannot generate header: {} */\n", lexgen::comment_safe(&e))),
+        ),
+        _ => None,
+    }
 }
\ No newline at end of file
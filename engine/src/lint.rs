@@ -0,0 +1,217 @@
+use serde::Serialize;
+use std::collections::BTreeSet;
+
+use crate::automaton::{regex_matches, representative, shortest_match, AutomatonRule, LexerAutomaton};
+use crate::regex::normalize_ranges;
+use crate::visualize::ranges_label;
+
+// --- FLEX RULE LINT ---
+// Reads the compiled DFA back to find what flex itself would complain about:
+// rules no DFA state ever accepts (an earlier rule always wins), rules that
+// match the empty string, and inputs that fall through to the default ECHO.
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum RuleIssueKind {
+    /// Every string the rule matches is claimed by an earlier rule.
+    Shadowed,
+    /// Not active in any declared start condition, or matches nothing at all.
+    Unreachable,
+    MatchesEmpty,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct PatternSpan {
+    pub line: usize,
+    pub column: usize,
+    pub end_column: usize,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct RuleIssue {
+    pub kind: RuleIssueKind,
+    /// Rule index in the FlexFile.
+    pub rule: usize,
+    pub pattern: String,
+    pub span: PatternSpan,
+    /// Input demonstrating the problem (what the rule would have matched).
+    pub example: Option<String>,
+    /// Rule that wins on `example` instead.
+    pub shadowed_by: Option<usize>,
+    pub message: String,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct UncoveredInput {
+    pub start_condition: String,
+    /// Characters no rule can start a match with.
+    pub first_chars: Vec<(char, char)>,
+    pub label: String,
+    /// Shortest input on which no rule matches and the default rule echoes.
+    pub example: String,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct FlexLintReport {
+    pub issues: Vec<RuleIssue>,
+    pub uncovered: Vec<UncoveredInput>,
+}
+
+fn span(rule: &AutomatonRule) -> PatternSpan {
+    PatternSpan { line: rule.line, column: rule.column, end_column: rule.column + rule.pattern.chars().count() }
+}
+
+/// Rule position (into `rules`) accepted after reading `input` from a start
+/// state of a condition where `rule` is active.
+fn winner_on(automaton: &LexerAutomaton, rule: &AutomatonRule, input: &str) -> Option<usize> {
+    let dfa = &automaton.dfa;
+    let condition = automaton.start_conditions.iter().find(|sc| rule.active_in(sc))?;
+    let mut state = dfa.start(&condition.name, rule.parsed.bol)?;
+    for c in input.chars() {
+        state = dfa.next(state, c)?;
+    }
+    dfa.states[state].accept
+}
+
+fn rule_issues(automaton: &LexerAutomaton) -> Vec<RuleIssue> {
+    let accepted: BTreeSet<usize> = automaton.dfa.states.iter().filter_map(|st| st.accept).collect();
+    let mut issues = Vec::new();
+    for (ri, rule) in automaton.rules.iter().enumerate() {
+        if rule.parsed.eof {
+            continue;
+        }
+        let full = rule.full_regex();
+        if regex_matches(&full, &[]) {
+            issues.push(RuleIssue {
+                kind: RuleIssueKind::MatchesEmpty,
+                rule: rule.index,
+                pattern: rule.pattern.clone(),
+                span: span(rule),
+                example: Some(String::new()),
+                shadowed_by: None,
+                message: format!("Rule '{}' matches the empty string", rule.pattern),
+            });
+        }
+        if accepted.contains(&ri) {
+            continue;
+        }
+        let active = automaton.start_conditions.iter().any(|sc| rule.active_in(sc));
+        let example = shortest_match(&full);
+        let winner = example.as_deref().filter(|_| active).and_then(|ex| winner_on(automaton, rule, ex));
+        let (kind, message) = match (active, &example, winner) {
+            (false, _, _) => (
+                RuleIssueKind::Unreachable,
+                format!("Rule '{}' is not active in any declared start condition", rule.pattern),
+            ),
+            (true, None, _) => (RuleIssueKind::Unreachable, format!("Rule '{}' cannot match any input", rule.pattern)),
+            (true, Some(ex), Some(w)) if w != ri => (
+                RuleIssueKind::Shadowed,
+                format!(
+                    "Rule '{}' cannot be matched: rule {} ('{}') always wins, e.g. on {:?}",
+                    rule.pattern, automaton.rules[w].index, automaton.rules[w].pattern, ex
+                ),
+            ),
+            // Only the empty string: already reported above.
+            (true, Some(_), _) => continue,
+        };
+        issues.push(RuleIssue {
+            kind,
+            rule: rule.index,
+            pattern: rule.pattern.clone(),
+            span: span(rule),
+            example,
+            shadowed_by: winner.map(|w| automaton.rules[w].index),
+            message,
+        });
+    }
+    issues
+}
+
+/// Gaps in each start condition, scanned from the middle of a line.
+fn uncovered_inputs(automaton: &LexerAutomaton) -> Vec<UncoveredInput> {
+    let dfa = &automaton.dfa;
+    let class_char = |c: usize| representative(&[dfa.classes[c]]);
+    // Try readable characters first so examples stay printable.
+    let mut class_order: Vec<usize> = (0..dfa.classes.len()).collect();
+    class_order.sort_by_key(|&c| !(' '..='~').contains(&class_char(c)));
+
+    let mut out = Vec::new();
+    for st in &dfa.starts {
+        let start = st.normal;
+        let first_chars = normalize_ranges(
+            (0..dfa.classes.len()).filter(|c| !dfa.states[start].transitions.contains_key(c)).map(|c| dfa.classes[c]).collect(),
+        );
+
+        // A character that kills the scan, or leads to a state that accepts
+        // nothing yet (so input ending there is unmatched), is echoed.
+        let example = class_order
+            .iter()
+            .find(|c| dfa.states[start].transitions.get(c).is_none_or(|&t| dfa.states[t].accept.is_none()))
+            .map(|&c| class_char(c).to_string());
+        if let Some(example) = example {
+            let label = if first_chars.is_empty() { String::new() } else { ranges_label(&first_chars) };
+            out.push(UncoveredInput { start_condition: st.condition.clone(), first_chars, label, example });
+        }
+    }
+    out
+}
+
+pub fn lint_flex(automaton: &LexerAutomaton) -> FlexLintReport {
+    FlexLintReport { issues: rule_issues(automaton), uncovered: uncovered_inputs(automaton) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::automaton::compile_flex;
+
+    fn lint(spec: &str) -> FlexLintReport {
+        lint_flex(&compile_flex(&crate::parse_flex(spec)).unwrap())
+    }
+
+    #[test]
+    fn keyword_after_identifier_rule_is_shadowed() {
+        let report = lint("%%\n[a-z]+ { return ID; }\nif { return IF; }\n");
+        assert_eq!(report.issues.len(), 1);
+        let issue = &report.issues[0];
+        assert_eq!((issue.kind.clone(), issue.rule, issue.shadowed_by), (RuleIssueKind::Shadowed, 1, Some(0)));
+        assert_eq!(issue.example.as_deref(), Some("if"));
+        assert_eq!((issue.span.line, issue.span.column, issue.span.end_column), (3, 1, 3));
+        assert_eq!(issue.message, "Rule 'if' cannot be matched: rule 0 ('[a-z]+') always wins, e.g. on \"if\"");
+    }
+
+    #[test]
+    fn keyword_before_identifier_rule_is_fine() {
+        let report = lint("%%\nif { return IF; }\n[a-z]+ { return ID; }\n.|\\n ;\n");
+        assert!(report.issues.is_empty(), "{:?}", report.issues);
+        assert!(report.uncovered.is_empty(), "{:?}", report.uncovered);
+    }
+
+    #[test]
+    fn star_rule_matches_the_empty_string() {
+        let report = lint("%%\n[0-9]* { return NUM; }\n");
+        let issue = &report.issues[0];
+        assert_eq!((issue.kind.clone(), issue.example.as_deref()), (RuleIssueKind::MatchesEmpty, Some("")));
+        assert_eq!(issue.message, "Rule '[0-9]*' matches the empty string");
+    }
+
+    #[test]
+    fn rule_in_an_undeclared_condition_is_unreachable() {
+        let report = lint("%x STR\n%%\n<STR>[a-z]+ { return S; }\n<NOPE>x ;\n.|\\n ;\n");
+        assert_eq!(report.issues.len(), 1);
+        assert_eq!((report.issues[0].kind.clone(), report.issues[0].rule), (RuleIssueKind::Unreachable, 1));
+        assert_eq!(report.issues[0].message, "Rule '<NOPE>x' is not active in any declared start condition");
+    }
+
+    #[test]
+    fn uncovered_characters_fall_through_to_echo() {
+        let report = lint("%x STR\n%%\n<STR>[a-z]+ { return S; }\nif { return IF; }\n[a-z]+ { return ID; }\n[ \\t\\n]+ ;\n");
+        let conditions: Vec<&str> = report.uncovered.iter().map(|u| u.start_condition.as_str()).collect();
+        assert_eq!(conditions, ["INITIAL", "STR"]);
+        let initial = &report.uncovered[0];
+        assert_eq!(initial.label, "[^\\t\\n' 'a-z]");
+        assert_eq!(initial.example, "!");
+        assert!(initial.first_chars.contains(&('!', '`')));
+        assert_eq!(report.uncovered[1].label, "[^a-z]");
+    }
+}
@@ -0,0 +1,257 @@
+use serde::Serialize;
+use std::collections::{BTreeMap, VecDeque};
+
+use crate::grammar::{Grammar, SymbolSets};
+
+// --- LL(1) ANALYSIS ---
+// Builds the predictive parse table M[A, a] from FIRST/FOLLOW. A grammar is
+// LL(1) exactly when every cell holds at most one production; left-recursive
+// grammars never are, so those are reported separately with their cycle.
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub enum ConflictKind {
+    FirstFirst,
+    FirstFollow,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct Ll1Conflict {
+    pub kind: ConflictKind,
+    pub nonterminal: String,
+    pub lookahead: String,
+    /// Indices into `Grammar::productions`.
+    pub productions: Vec<usize>,
+    pub rules: Vec<String>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct LeftRecursion {
+    pub nonterminal: String,
+    /// `A, B, ..., A`: each symbol can start a derivation of the next.
+    pub cycle: Vec<String>,
+    pub direct: bool,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct Ll1Report {
+    pub is_ll1: bool,
+    pub sets: SymbolSets,
+    pub productions: Vec<String>,
+    /// nonterminal -> lookahead terminal -> production indices.
+    pub table: BTreeMap<String, BTreeMap<String, Vec<usize>>>,
+    pub conflicts: Vec<Ll1Conflict>,
+    pub left_recursion: Vec<LeftRecursion>,
+}
+
+pub fn analyze_ll1(grammar: &Grammar) -> Ll1Report {
+    let sets = grammar.symbol_sets();
+
+    // (production, entered via FOLLOW) per cell
+    let mut cells: BTreeMap<String, BTreeMap<String, Vec<(usize, bool)>>> = BTreeMap::new();
+    for nt in &grammar.nonterminals {
+        cells.insert(nt.clone(), BTreeMap::new());
+    }
+    for (i, p) in grammar.productions.iter().enumerate() {
+        let (first, nullable) = sets.first_of(grammar, &p.rhs);
+        let row = cells.get_mut(&p.lhs).unwrap();
+        for t in first {
+            row.entry(t).or_default().push((i, false));
+        }
+        if nullable {
+            for t in &sets.follow[&p.lhs] {
+                let cell = row.entry(t.clone()).or_default();
+                if !cell.iter().any(|(pi, _)| *pi == i) {
+                    cell.push((i, true));
+                }
+            }
+        }
+    }
+
+    let mut conflicts = Vec::new();
+    for (nt, row) in &cells {
+        for (t, cell) in row {
+            if cell.len() > 1 {
+                let kind = if cell.iter().any(|(_, via_follow)| *via_follow) {
+                    ConflictKind::FirstFollow
+                } else {
+                    ConflictKind::FirstFirst
+                };
+                conflicts.push(Ll1Conflict {
+                    kind,
+                    nonterminal: nt.clone(),
+                    lookahead: t.clone(),
+                    productions: cell.iter().map(|(pi, _)| *pi).collect(),
+                    rules: cell.iter().map(|(pi, _)| grammar.productions[*pi].to_string()).collect(),
+                });
+            }
+        }
+    }
+
+    let table = cells
+        .into_iter()
+        .map(|(nt, row)| (nt, row.into_iter().map(|(t, cell)| (t, cell.into_iter().map(|(pi, _)| pi).collect())).collect()))
+        .collect();
+    let left_recursion = find_left_recursion(grammar, &sets);
+
+    Ll1Report {
+        is_ll1: conflicts.is_empty() && left_recursion.is_empty(),
+        productions: grammar.productions.iter().map(|p| p.to_string()).collect(),
+        sets,
+        table,
+        conflicts,
+        left_recursion,
+    }
+}
+
+/// Nonterminals that can appear leftmost in a derivation step from `nt`,
+/// i.e. `nt -> α B β` with `α` nullable.
+pub fn left_corners(grammar: &Grammar, sets: &SymbolSets, nt: &str) -> Vec<String> {
+    let mut out: Vec<String> = Vec::new();
+    for (_, p) in grammar.productions_for(nt) {
+        for sym in &p.rhs {
+            if grammar.is_terminal(sym) {
+                break;
+            }
+            if !out.contains(sym) {
+                out.push(sym.clone());
+            }
+            if !sets.nullable.contains(sym) {
+                break;
+            }
+        }
+    }
+    out
+}
+
+pub fn find_left_recursion(grammar: &Grammar, sets: &SymbolSets) -> Vec<LeftRecursion> {
+    let edges: BTreeMap<&str, Vec<String>> =
+        grammar.nonterminals.iter().map(|nt| (nt.as_str(), left_corners(grammar, sets, nt))).collect();
+
+    let mut found = Vec::new();
+    for nt in &grammar.nonterminals {
+        // Shortest path nt ->+ nt through the left-corner graph.
+        let mut parent: BTreeMap<String, String> = BTreeMap::new();
+        let mut queue: VecDeque<String> = VecDeque::new();
+        let mut cycle = None;
+        for next in &edges[nt.as_str()] {
+            if next == nt {
+                cycle = Some(vec![nt.clone(), nt.clone()]);
+                break;
+            }
+            if !parent.contains_key(next) {
+                parent.insert(next.clone(), nt.clone());
+                queue.push_back(next.clone());
+            }
+        }
+        while cycle.is_none() {
+            let Some(cur) = queue.pop_front() else { break };
+            for next in &edges[cur.as_str()] {
+                if next == nt {
+                    let mut chain = vec![cur.clone()];
+                    let mut at = &cur;
+                    while let Some(p) = parent.get(at) {
+                        if p == nt {
+                            break;
+                        }
+                        chain.push(p.clone());
+                        at = p;
+                    }
+                    chain.reverse();
+                    let mut path = vec![nt.clone()];
+                    path.extend(chain);
+                    path.push(nt.clone());
+                    cycle = Some(path);
+                    break;
+                }
+                if !parent.contains_key(next) && next != nt {
+                    parent.insert(next.clone(), cur.clone());
+                    queue.push_back(next.clone());
+                }
+            }
+        }
+        if let Some(cycle) = cycle {
+            found.push(LeftRecursion { nonterminal: nt.clone(), direct: cycle.len() == 2, cycle });
+        }
+    }
+    found
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn grammar(rules: &str) -> Grammar {
+        Grammar::from_ast(&crate::parse_bison(&format!("%token NUM ID IF THEN ELSE OTHER\n%%\n{}", rules))).unwrap()
+    }
+
+    fn cell<'a>(report: &'a Ll1Report, nt: &str, t: &str) -> Vec<&'a str> {
+        report.table[nt].get(t).map_or(Vec::new(), |cell| cell.iter().map(|&pi| report.productions[pi].as_str()).collect())
+    }
+
+    #[test]
+    fn expression_grammar_without_left_recursion_is_ll1() {
+        let g = grammar("e: t e2 ;\ne2: '+' t e2 | ;\nt: f t2 ;\nt2: '*' f t2 | ;\nf: '(' e ')' | NUM ;\n");
+        let report = analyze_ll1(&g);
+        assert!(report.is_ll1);
+        assert!(report.conflicts.is_empty() && report.left_recursion.is_empty());
+
+        let set = |s: &std::collections::BTreeSet<String>| s.iter().cloned().collect::<Vec<_>>();
+        assert_eq!(set(&report.sets.first["e"]), ["'('", "NUM"]);
+        assert_eq!(set(&report.sets.follow["e"]), ["$end", "')'"]);
+        assert_eq!(set(&report.sets.follow["t"]), ["$end", "')'", "'+'"]);
+        assert!(report.sets.nullable.contains("e2") && !report.sets.nullable.contains("t"));
+
+        assert_eq!(cell(&report, "e", "NUM"), ["e -> t e2"]);
+        assert_eq!(cell(&report, "e2", "'+'"), ["e2 -> '+' t e2"]);
+        assert_eq!(cell(&report, "e2", "')'"), ["e2 -> ε"]);
+        assert_eq!(cell(&report, "e2", "$end"), ["e2 -> ε"]);
+        assert_eq!(cell(&report, "t2", "'+'"), ["t2 -> ε"]);
+        assert!(cell(&report, "f", "'+'").is_empty());
+    }
+
+    #[test]
+    fn left_recursive_expression_grammar_is_not_ll1() {
+        let g = grammar("e: e '+' t | t ;\nt: t '*' f | f ;\nf: '(' e ')' | NUM ;\n");
+        let report = analyze_ll1(&g);
+        assert!(!report.is_ll1);
+        let recursive: Vec<(&str, bool)> = report.left_recursion.iter().map(|r| (r.nonterminal.as_str(), r.direct)).collect();
+        assert_eq!(recursive, [("e", true), ("t", true)]);
+        assert_eq!(report.left_recursion[0].cycle, ["e", "e"]);
+        // Both alternatives of e start with whatever starts f.
+        let conflict = report.conflicts.iter().find(|c| c.nonterminal == "e" && c.lookahead == "NUM").unwrap();
+        assert_eq!(conflict.kind, ConflictKind::FirstFirst);
+        assert_eq!(conflict.rules, ["e -> e '+' t", "e -> t"]);
+    }
+
+    #[test]
+    fn dangling_else_is_a_first_follow_conflict() {
+        let g = grammar("stmt: IF ID THEN stmt else_part | OTHER ;\nelse_part: ELSE stmt | ;\n");
+        let report = analyze_ll1(&g);
+        assert!(!report.is_ll1);
+        assert!(report.left_recursion.is_empty());
+        assert_eq!(report.conflicts.len(), 1);
+        let conflict = &report.conflicts[0];
+        assert_eq!((conflict.kind.clone(), conflict.nonterminal.as_str(), conflict.lookahead.as_str()), (ConflictKind::FirstFollow, "else_part", "ELSE"));
+        assert_eq!(conflict.rules, ["else_part -> ELSE stmt", "else_part -> ε"]);
+        assert_eq!(cell(&report, "else_part", "$end"), ["else_part -> ε"]);
+    }
+
+    #[test]
+    fn indirect_left_recursion_reports_its_cycle() {
+        let g = grammar("a: b 'x' | 'y' ;\nb: a 'z' | 'w' ;\n");
+        let recursion = find_left_recursion(&g, &g.symbol_sets());
+        assert_eq!(recursion.len(), 2);
+        assert_eq!(recursion[0].cycle, ["a", "b", "a"]);
+        assert!(!recursion[0].direct);
+    }
+
+    #[test]
+    fn left_recursion_can_hide_behind_a_nullable_prefix() {
+        let g = grammar("a: n a 'x' | 'y' ;\nn: 'q' | ;\n");
+        let sets = g.symbol_sets();
+        assert_eq!(left_corners(&g, &sets, "a"), ["n", "a"]);
+        let recursion = find_left_recursion(&g, &sets);
+        assert_eq!(recursion.len(), 1);
+        assert_eq!(recursion[0].cycle, ["a", "a"]);
+    }
+}
@@ -1,17 +1,54 @@
 use axum::{Json, Router, routing::post};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::net::SocketAddr;
 use tower_http::cors::CorsLayer;
 use serde_json::json;
 use std::fs;
-use std::process::{Command, Stdio};
-use std::io::Write;
-use engine::{parse_flex, parse_bison, scan_code, Token, ASTNode};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWriteExt};
+use tokio::process::Command;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use engine::{parse_flex, parse_bison, scan_code, Token, ASTNode, CodegenOptions, Target};
+use engine::grammar::Grammar;
+use engine::ambiguity::{check_ambiguity, AmbiguityReport, DEFAULT_MAX_LENGTH, MAX_LENGTH_LIMIT};
+use engine::ll1::{analyze_ll1, Ll1Report};
+use engine::transform::{transform, Transformation, Transformed};
+use engine::cyk::{cyk, CykResult};
+use engine::generator::{generate_sentences, GenerationReport, GeneratorOptions, MAX_COUNT, MAX_DEPTH_LIMIT};
+use engine::automaton::compile_flex;
+use engine::visualize::{dfa_graph, nfa_graph, AutomatonGraph};
+use engine::interpreter::{run_flex, ScanResult, MAX_SCAN_INPUT};
+use engine::lint::{lint_flex, FlexLintReport};
+use engine::explain::{explain_pattern, explain_regex, Explanation};
+use engine::regex::Regex;
+use engine::dialect::{translate_all, translate_pattern, Dialect, Translation};
+use engine::testbench::{test_pattern, RegexTestReport};
+use engine::equivalence::{compare_lexers, EquivalenceReport};
+use engine::utf8::byte_dfa;
+use engine::lexgen::{SCANNER_OUTPUT, SCANNER_SOURCE};
+use engine::yaccgen::{HEADER_NAME, PARSER_OUTPUT, PARSER_SOURCE};
+use engine::project::{attribute_diagnostics, generate_project, GeneratedProjectFile, ProjectFile};
+use engine::diagnostics::{parse_gcc_output, Diagnostic};
 
 #[derive(Deserialize)]
 struct RequestData {
     code: String,
     language: String,
+    #[serde(default)]
+    ll1: bool,
+    /// Leave `#line` directives out of the generated code.
+    #[serde(default)]
+    no_line: bool,
+    /// Language of the generated code: "c" (default) or "rust".
+    #[serde(default)]
+    target: Target,
+    /// Generate a recursive-descent parser instead of LALR tables (LL(1)
+    /// grammars only).
+    #[serde(default)]
+    recursive_descent: bool,
 }
 
 #[derive(Deserialize)]
@@ -27,11 +64,20 @@ struct AssistResponse {
     suggestion: String,
 }
 
+#[derive(Serialize)]
+struct GeneratedFile {
+    name: String,
+    content: String,
+}
+
 #[derive(Serialize)]
 struct ResponseData {
     tokens: Vec<Token>,
     ast: ASTNode,
     generated_code: Option<String>,
+    /// y.tab.h for grammars, to be included by the scanner.
+    generated_header: Option<GeneratedFile>,
+    ll1: Option<Ll1Report>,
 }
 
 #[derive(Deserialize)]
@@ -44,6 +90,183 @@ struct RunRequest {
 struct RunResponse {
     output: String,
     error: Option<String>,
+    /// gcc's errors and warnings, placed in the .l/.y source where `#line` allows.
+    diagnostics: Vec<Diagnostic>,
+}
+
+#[derive(Deserialize)]
+struct ProjectRunRequest {
+    scanner: ProjectFile,
+    grammar: ProjectFile,
+    #[serde(default)]
+    extra_files: Vec<ProjectFile>,
+    #[serde(default)]
+    test_input: String,
+    #[serde(default)]
+    no_line: bool,
+}
+
+#[derive(Serialize)]
+struct ProjectRunResponse {
+    files: Vec<GeneratedProjectFile>,
+    output: String,
+    /// What the program wrote to stderr, e.g. yyerror messages.
+    stderr: String,
+    exit_code: Option<i32>,
+    /// Spec errors, or gcc's errors and warnings for the generated files.
+    diagnostics: Vec<Diagnostic>,
+}
+
+#[derive(Deserialize)]
+struct AmbiguityRequest {
+    code: String,
+    max_length: Option<usize>,
+}
+
+#[derive(Serialize)]
+struct AmbiguityResponse {
+    report: Option<AmbiguityReport>,
+    error: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct TransformRequest {
+    code: String,
+    transformations: Vec<Transformation>,
+}
+
+#[derive(Serialize)]
+struct TransformResponse {
+    result: Option<Transformed>,
+    error: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct CykRequest {
+    code: String,
+    tokens: Vec<String>,
+    #[serde(default)]
+    gnf: bool,
+}
+
+#[derive(Serialize)]
+struct CykResponse {
+    cnf: Option<Transformed>,
+    gnf: Option<Transformed>,
+    cyk: Option<CykResult>,
+    error: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct GenerateRequest {
+    code: String,
+    flex_code: Option<String>,
+    #[serde(default)]
+    options: GeneratorOptions,
+}
+
+#[derive(Serialize)]
+struct GenerateResponse {
+    report: Option<GenerationReport>,
+    error: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct AutomatonRequest {
+    code: String,
+    #[serde(default)]
+    nfa: bool,
+    input: Option<String>,
+    start_condition: Option<String>,
+    /// Show the UTF-8 byte-level DFA the generated C scanner runs.
+    #[serde(default)]
+    bytes: bool,
+}
+
+#[derive(Serialize)]
+struct AutomatonResponse {
+    dfa: Option<AutomatonGraph>,
+    nfa: Option<AutomatonGraph>,
+    error: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct ScanRequest {
+    code: String,
+    input: String,
+}
+
+#[derive(Serialize)]
+struct ScanResponse {
+    result: Option<ScanResult>,
+    error: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct LintRequest {
+    code: String,
+}
+
+#[derive(Serialize)]
+struct LintResponse {
+    report: Option<FlexLintReport>,
+    error: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct ExplainRequest {
+    pattern: Option<String>,
+    /// Already-parsed regex tree, used when no pattern is given.
+    regex: Option<Regex>,
+    #[serde(default)]
+    definitions: BTreeMap<String, String>,
+}
+
+#[derive(Serialize)]
+struct ExplainResponse {
+    explanation: Option<Explanation>,
+    error: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct TranslateRequest {
+    pattern: String,
+    #[serde(default)]
+    definitions: BTreeMap<String, String>,
+    /// Omit to get every dialect.
+    dialect: Option<Dialect>,
+}
+
+#[derive(Serialize)]
+struct TranslateResponse {
+    translations: Vec<Translation>,
+    error: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct RegexTestRequest {
+    pattern: String,
+    samples: Vec<String>,
+    #[serde(default)]
+    definitions: BTreeMap<String, String>,
+}
+
+#[derive(Serialize)]
+struct RegexTestResponse {
+    report: Option<RegexTestReport>,
+    error: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct EquivalenceRequest {
+    left_code: String,
+    right_code: String,
+}
+
+#[derive(Serialize)]
+struct EquivalenceResponse {
+    report: Option<EquivalenceReport>,
+    error: Option<String>,
 }
 
 async fn handle_analyze(Json(payload): Json<RequestData>) -> Json<ResponseData> {
@@ -55,13 +278,29 @@ async fn handle_analyze(Json(payload): Json<RequestData>) -> Json<ResponseData>
         parse_flex(&payload.code)
     };
 
-    let generated_code = if matches!(ast, ASTNode::Error { .. }) {
-        None
+    let (generated_code, generated_header) = if matches!(ast, ASTNode::Error { .. }) {
+        (None, None)
+    } else {
+        let header = || engine::generate_c_header(&ast).map(|content| GeneratedFile { name: HEADER_NAME.to_string(), content });
+        let options = CodegenOptions { no_line: payload.no_line, ..CodegenOptions::default() };
+        match payload.target {
+            _ if payload.recursive_descent && payload.language == "bison" => {
+                let code = engine::generate_descent_code(&ast, payload.target, &options);
+                (Some(code), if payload.target == Target::C { header() } else { None })
+            }
+            Target::C => (Some(engine::generate_c_code_with(&ast, &options)), header()),
+            Target::Rust => (Some(engine::generate_rust_code(&ast)), None),
+        }
+    };
+
+    // Optional LL(1) analysis, only meaningful for grammars
+    let ll1 = if payload.ll1 && payload.language == "bison" {
+        Grammar::from_ast(&ast).ok().map(|g| analyze_ll1(&g))
     } else {
-        Some(engine::generate_c_code(&ast))
+        None
     };
 
-    Json(ResponseData { tokens, ast, generated_code })
+    Json(ResponseData { tokens, ast, generated_code, generated_header, ll1 })
 }
 
 async fn handle_assist(Json(payload): Json<AssistRequest>) -> Json<AssistResponse> {
@@ -108,55 +347,387 @@ async fn handle_assist(Json(payload): Json<AssistRequest>) -> Json<AssistRespons
     }
 }
 
+// --- REQUEST LIMITS ---
+/// Longest grammar or flex spec (or set of definitions) a route accepts.
+const MAX_SPEC_BYTES: usize = 64 * 1024;
+/// Longest single pattern for /explain, /translate and /regex/test.
+const MAX_PATTERN_BYTES: usize = 4 * 1024;
+/// Most tokens /cyk parses; its table is cubic in their number.
+const MAX_CYK_TOKENS: usize = 256;
+/// Most samples one /regex/test request matches.
+const MAX_SAMPLES: usize = 100;
+
+/// Rejects a request field longer than `max` bytes.
+fn within(what: &str, text: &str, max: usize) -> Result<(), String> {
+    if text.len() > max {
+        return Err(format!("{} is longer than {} bytes", what, max));
+    }
+    Ok(())
+}
+
+fn definitions_within(definitions: &BTreeMap<String, String>) -> Result<(), String> {
+    let size: usize = definitions.iter().map(|(name, pattern)| name.len() + pattern.len()).sum();
+    if size > MAX_SPEC_BYTES {
+        return Err(format!("Definitions are longer than {} bytes", MAX_SPEC_BYTES));
+    }
+    Ok(())
+}
+
+/// Runs CPU-bound engine work on tokio's blocking pool, so slow requests
+/// do not stall the workers serving everyone else.
+async fn blocking<T: Send + 'static>(work: impl FnOnce() -> Result<T, String> + Send + 'static) -> Result<T, String> {
+    tokio::task::spawn_blocking(work).await.unwrap_or_else(|e| Err(format!("Engine task failed: {}", e)))
+}
+
+async fn handle_ambiguity(Json(payload): Json<AmbiguityRequest>) -> Json<AmbiguityResponse> {
+    let result = blocking(move || {
+        within("Grammar", &payload.code, MAX_SPEC_BYTES)?;
+        let grammar = Grammar::from_ast(&parse_bison(&payload.code))?;
+        let max_length = payload.max_length.unwrap_or(DEFAULT_MAX_LENGTH).min(MAX_LENGTH_LIMIT);
+        Ok(check_ambiguity(&grammar, max_length))
+    })
+    .await;
+    match result {
+        Ok(report) => Json(AmbiguityResponse { report: Some(report), error: None }),
+        Err(e) => Json(AmbiguityResponse { report: None, error: Some(e) }),
+    }
+}
+
+async fn handle_transform(Json(payload): Json<TransformRequest>) -> Json<TransformResponse> {
+    let result = blocking(move || {
+        within("Grammar", &payload.code, MAX_SPEC_BYTES)?;
+        Grammar::from_ast(&parse_bison(&payload.code)).and_then(|g| transform(&g, &payload.transformations))
+    })
+    .await;
+    match result {
+        Ok(t) => Json(TransformResponse { result: Some(t), error: None }),
+        Err(e) => Json(TransformResponse { result: None, error: Some(e) }),
+    }
+}
+
+async fn handle_cyk(Json(payload): Json<CykRequest>) -> Json<CykResponse> {
+    let result = blocking(move || {
+        within("Grammar", &payload.code, MAX_SPEC_BYTES)?;
+        if payload.tokens.len() > MAX_CYK_TOKENS {
+            return Err(format!("More than {} tokens", MAX_CYK_TOKENS));
+        }
+        let grammar = Grammar::from_ast(&parse_bison(&payload.code))?;
+        let cnf = transform(&grammar, &[Transformation::ChomskyNormalForm])?;
+        // GNF is informational only; a failure there should not hide the CYK result.
+        let gnf = if payload.gnf {
+            transform(&grammar, &[Transformation::GreibachNormalForm]).ok()
+        } else {
+            None
+        };
+        let parsed = cyk(&cnf.grammar, &payload.tokens);
+        Ok((cnf, gnf, parsed))
+    })
+    .await;
+    match result {
+        Ok((cnf, gnf, Ok(result))) => Json(CykResponse { cnf: Some(cnf), gnf, cyk: Some(result), error: None }),
+        Ok((cnf, gnf, Err(e))) => Json(CykResponse { cnf: Some(cnf), gnf, cyk: None, error: Some(e) }),
+        Err(e) => Json(CykResponse { cnf: None, gnf: None, cyk: None, error: Some(e) }),
+    }
+}
+
+async fn handle_generate(Json(payload): Json<GenerateRequest>) -> Json<GenerateResponse> {
+    let result = blocking(move || {
+        within("Grammar", &payload.code, MAX_SPEC_BYTES)?;
+        within("Flex spec", payload.flex_code.as_deref().unwrap_or(""), MAX_SPEC_BYTES)?;
+        let flex = payload.flex_code.as_deref().map(parse_flex);
+        let mut options = payload.options;
+        options.count = options.count.min(MAX_COUNT);
+        options.max_depth = options.max_depth.min(MAX_DEPTH_LIMIT);
+        Grammar::from_ast(&parse_bison(&payload.code)).and_then(|g| generate_sentences(&g, flex.as_ref(), &options))
+    })
+    .await;
+    match result {
+        Ok(report) => Json(GenerateResponse { report: Some(report), error: None }),
+        Err(e) => Json(GenerateResponse { report: None, error: Some(e) }),
+    }
+}
+
+async fn handle_automaton(Json(payload): Json<AutomatonRequest>) -> Json<AutomatonResponse> {
+    let result = blocking(move || {
+        within("Flex spec", &payload.code, MAX_SPEC_BYTES)?;
+        within("Input", payload.input.as_deref().unwrap_or(""), MAX_SCAN_INPUT)?;
+        let automaton = compile_flex(&parse_flex(&payload.code))?;
+        let condition = payload.start_condition.as_deref().unwrap_or("INITIAL");
+        let dfa = if payload.bytes {
+            // Byte automata read each UTF-8 byte as the char with that value.
+            let input: Option<String> = payload.input.as_ref().map(|i| i.bytes().map(char::from).collect());
+            byte_dfa(&automaton).and_then(|dfa| dfa_graph(&automaton, &dfa, input.as_deref(), condition))?
+        } else {
+            dfa_graph(&automaton, &automaton.dfa, payload.input.as_deref(), condition)?
+        };
+        let nfa = if payload.nfa { Some(nfa_graph(&automaton)) } else { None };
+        Ok((dfa, nfa))
+    })
+    .await;
+    match result {
+        Ok((dfa, nfa)) => Json(AutomatonResponse { dfa: Some(dfa), nfa, error: None }),
+        Err(e) => Json(AutomatonResponse { dfa: None, nfa: None, error: Some(e) }),
+    }
+}
+
+async fn handle_scan(Json(payload): Json<ScanRequest>) -> Json<ScanResponse> {
+    let result = blocking(move || {
+        within("Flex spec", &payload.code, MAX_SPEC_BYTES)?;
+        within("Input", &payload.input, MAX_SCAN_INPUT)?;
+        compile_flex(&parse_flex(&payload.code)).map(|automaton| run_flex(&automaton, &payload.input))
+    })
+    .await;
+    match result {
+        Ok(result) => Json(ScanResponse { result: Some(result), error: None }),
+        Err(e) => Json(ScanResponse { result: None, error: Some(e) }),
+    }
+}
+
+async fn handle_lint(Json(payload): Json<LintRequest>) -> Json<LintResponse> {
+    let result = blocking(move || {
+        within("Flex spec", &payload.code, MAX_SPEC_BYTES)?;
+        compile_flex(&parse_flex(&payload.code)).map(|automaton| lint_flex(&automaton))
+    })
+    .await;
+    match result {
+        Ok(report) => Json(LintResponse { report: Some(report), error: None }),
+        Err(e) => Json(LintResponse { report: None, error: Some(e) }),
+    }
+}
+
+async fn handle_explain(Json(payload): Json<ExplainRequest>) -> Json<ExplainResponse> {
+    let result = blocking(move || {
+        definitions_within(&payload.definitions)?;
+        match (&payload.pattern, &payload.regex) {
+            (Some(pattern), _) => {
+                within("Pattern", pattern, MAX_PATTERN_BYTES)?;
+                explain_pattern(pattern, &payload.definitions)
+            }
+            (None, Some(regex)) => explain_regex(regex),
+            (None, None) => Err("Expected a 'pattern' or a 'regex'".to_string()),
+        }
+    })
+    .await;
+    match result {
+        Ok(explanation) => Json(ExplainResponse { explanation: Some(explanation), error: None }),
+        Err(e) => Json(ExplainResponse { explanation: None, error: Some(e) }),
+    }
+}
+
+async fn handle_translate(Json(payload): Json<TranslateRequest>) -> Json<TranslateResponse> {
+    let result = blocking(move || {
+        within("Pattern", &payload.pattern, MAX_PATTERN_BYTES)?;
+        definitions_within(&payload.definitions)?;
+        match payload.dialect {
+            Some(dialect) => translate_pattern(&payload.pattern, &payload.definitions, dialect).map(|t| vec![t]),
+            None => translate_all(&payload.pattern, &payload.definitions),
+        }
+    })
+    .await;
+    match result {
+        Ok(translations) => Json(TranslateResponse { translations, error: None }),
+        Err(e) => Json(TranslateResponse { translations: Vec::new(), error: Some(e) }),
+    }
+}
+
+async fn handle_regex_test(Json(payload): Json<RegexTestRequest>) -> Json<RegexTestResponse> {
+    let result = blocking(move || {
+        within("Pattern", &payload.pattern, MAX_PATTERN_BYTES)?;
+        definitions_within(&payload.definitions)?;
+        if payload.samples.len() > MAX_SAMPLES {
+            return Err(format!("More than {} samples", MAX_SAMPLES));
+        }
+        payload.samples.iter().try_for_each(|sample| within("Sample", sample, MAX_SCAN_INPUT))?;
+        test_pattern(&payload.pattern, &payload.definitions, &payload.samples)
+    })
+    .await;
+    match result {
+        Ok(report) => Json(RegexTestResponse { report: Some(report), error: None }),
+        Err(e) => Json(RegexTestResponse { report: None, error: Some(e) }),
+    }
+}
+
+async fn handle_equivalence(Json(payload): Json<EquivalenceRequest>) -> Json<EquivalenceResponse> {
+    let result = blocking(move || {
+        within("Left spec", &payload.left_code, MAX_SPEC_BYTES)?;
+        within("Right spec", &payload.right_code, MAX_SPEC_BYTES)?;
+        let left = compile_flex(&parse_flex(&payload.left_code)).map_err(|e| format!("Left spec: {}", e))?;
+        let right = compile_flex(&parse_flex(&payload.right_code)).map_err(|e| format!("Right spec: {}", e))?;
+        Ok(compare_lexers(&left, &right))
+    })
+    .await;
+    match result {
+        Ok(report) => Json(EquivalenceResponse { report: Some(report), error: None }),
+        Err(e) => Json(EquivalenceResponse { report: None, error: Some(e) }),
+    }
+}
+
 // --- PHASE 6: EXECUTION HANDLER ---
+static SCRATCH_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// How long a compiled program may run before it is killed.
+const RUN_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How long gcc may take on one request's sources.
+const COMPILE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Bytes kept from each of a process's stdout and stderr; the rest is read
+/// and dropped, so a chatty program still runs to completion.
+const MAX_OUTPUT_BYTES: usize = 1024 * 1024;
+
+/// Reads `pipe` to the end, keeping the first MAX_OUTPUT_BYTES.
+async fn read_capped(pipe: Option<impl AsyncRead + Unpin>) -> Vec<u8> {
+    let mut out = Vec::new();
+    let Some(mut pipe) = pipe else { return out };
+    let mut buf = [0u8; 8192];
+    let mut truncated = false;
+    while let Ok(n @ 1..) = pipe.read(&mut buf).await {
+        let room = MAX_OUTPUT_BYTES - out.len();
+        truncated |= n > room;
+        out.extend_from_slice(&buf[..n.min(room)]);
+    }
+    if truncated {
+        out.extend_from_slice(format!("\n[output truncated after {} bytes]\n", MAX_OUTPUT_BYTES).as_bytes());
+    }
+    out
+}
+
+/// Runs `command` with `input` on stdin. Input is fed while the process
+/// runs, so one that never reads it cannot stall the handler, output is
+/// capped at MAX_OUTPUT_BYTES per stream, and the process is killed once
+/// `limit` has passed. `what` names the step in the timeout error.
+async fn run_with_timeout(command: &mut Command, input: &str, limit: Duration, what: &str) -> Result<std::process::Output, std::io::Error> {
+    let mut child = command.stdin(Stdio::piped()).stdout(Stdio::piped()).stderr(Stdio::piped()).kill_on_drop(true).spawn()?;
+    let (stdin, stdout, stderr) = (child.stdin.take(), child.stdout.take(), child.stderr.take());
+    let input = input.as_bytes().to_vec();
+    let feed = async move {
+        if let Some(mut stdin) = stdin {
+            // A program that exits without reading everything is fine.
+            let _ = stdin.write_all(&input).await;
+        }
+    };
+    let run = async {
+        let (_, stdout, stderr, status) = tokio::join!(feed, read_capped(stdout), read_capped(stderr), child.wait());
+        status.map(|status| std::process::Output { status, stdout, stderr })
+    };
+    match tokio::time::timeout(limit, run).await {
+        Ok(output) => output,
+        Err(_) => Err(std::io::Error::new(std::io::ErrorKind::TimedOut, format!("{} timed out after {} seconds", what, limit.as_secs()))),
+    }
+}
+
+/// Compiles `sources` in `dir` into `output` under COMPILE_TIMEOUT.
+async fn run_gcc(dir: &Path, sources: &[&str], output: &str) -> Result<std::process::Output, String> {
+    match run_with_timeout(Command::new("gcc").current_dir(dir).args(sources).args(["-o", output]), "", COMPILE_TIMEOUT, "Compilation").await {
+        Ok(output) => Ok(output),
+        Err(e) if e.kind() == std::io::ErrorKind::TimedOut => Err(e.to_string()),
+        Err(_) => Err("GCC not found! Ensure 'gcc' is installed on your system.".to_string()),
+    }
+}
+
+/// Runs a compiled `program` in `dir` under RUN_TIMEOUT.
+async fn run_program(program: &Path, dir: &Path, input: &str) -> Result<std::process::Output, String> {
+    run_with_timeout(Command::new(program).current_dir(dir), input, RUN_TIMEOUT, "Execution").await.map_err(|e| match e.kind() {
+        std::io::ErrorKind::TimedOut => e.to_string(),
+        _ => format!("Failed to run binary: {}", e),
+    })
+}
+
+/// A fresh directory for one request's files, so concurrent runs never
+/// share a file name.
+fn scratch_dir(kind: &str) -> std::io::Result<PathBuf> {
+    let dir = std::env::temp_dir().join(format!("structura-{}-{}-{}", kind, std::process::id(), SCRATCH_COUNTER.fetch_add(1, Ordering::Relaxed)));
+    fs::create_dir_all(&dir)?;
+    Ok(dir)
+}
+
 async fn handle_run(Json(payload): Json<RunRequest>) -> Json<RunResponse> {
+    let dir = match scratch_dir("run") {
+        Ok(dir) => dir,
+        Err(e) => return Json(RunResponse { output: "".into(), diagnostics: Vec::new(), error: Some(format!("Failed to create scratch directory: {}", e)) }),
+    };
+    let response = compile_and_run(&dir, &payload).await;
+    // Whatever happened, nothing is left behind.
+    let _ = fs::remove_dir_all(&dir);
+    Json(response)
+}
+
+async fn compile_and_run(dir: &Path, payload: &RunRequest) -> RunResponse {
     let file_name = "temp_compiler.c";
-    let exe_name = if cfg!(windows) { "temp_compiler.exe" } else { "./temp_compiler.out" };
+    let exe_name = if cfg!(windows) { "temp_compiler.exe" } else { "temp_compiler.out" };
 
-    if let Err(e) = fs::write(file_name, &payload.c_code) {
-        return Json(RunResponse { output: "".into(), error: Some(format!("Failed to write file: {}", e)) });
+    if let Err(e) = fs::write(dir.join(file_name), &payload.c_code) {
+        return RunResponse { output: "".into(), diagnostics: Vec::new(), error: Some(format!("Failed to write file: {}", e)) };
     }
 
-    let compile_status = Command::new("gcc")
-        .arg(file_name)
-        .arg("-o")
-        .arg(exe_name)
-        .output();
-
-    let compile_output = match compile_status {
+    let compile_output = match run_gcc(dir, &[file_name], exe_name).await {
         Ok(output) => output,
-        Err(_) => return Json(RunResponse { output: "".into(), error: Some("GCC not found! Ensure 'gcc' is installed on your system.".to_string()) }),
+        Err(e) => return RunResponse { output: "".into(), diagnostics: Vec::new(), error: Some(e) },
     };
+    // `#line` names the spec for user code and lex.yy.c / y.tab.c for the rest.
+    let stderr = String::from_utf8_lossy(&compile_output.stderr).to_string();
+    let diagnostics = parse_gcc_output(&stderr, &[(file_name, file_name), (SCANNER_OUTPUT, SCANNER_SOURCE), (PARSER_OUTPUT, PARSER_SOURCE)]);
 
     if !compile_output.status.success() {
-        let stderr = String::from_utf8_lossy(&compile_output.stderr).to_string();
-        return Json(RunResponse { output: "".into(), error: Some(format!("Compilation Error:\n{}", stderr)) });
+        return RunResponse { output: "".into(), diagnostics, error: Some(format!("Compilation Error:\n{}", stderr)) };
     }
 
-    let mut child = match Command::new(exe_name)
-        .stdin(Stdio::piped())
-        .stdout(Stdio::piped())
-        .stderr(Stdio::piped())
-        .spawn() {
-            Ok(child) => child,
-            Err(e) => return Json(RunResponse { output: "".into(), error: Some(format!("Failed to run binary: {}", e)) }),
-        };
+    let exec_output = match run_program(&dir.join(exe_name), dir, &payload.test_input).await {
+        Ok(output) => output,
+        Err(e) => return RunResponse { output: "".into(), diagnostics, error: Some(e) },
+    };
+
+    let stdout_str = String::from_utf8_lossy(&exec_output.stdout).to_string();
+    RunResponse { output: stdout_str, error: None, diagnostics }
+}
+
+async fn handle_project_run(Json(payload): Json<ProjectRunRequest>) -> Json<ProjectRunResponse> {
+    let failed = |files: Vec<GeneratedProjectFile>, diagnostics: Vec<Diagnostic>| {
+        Json(ProjectRunResponse { files, output: String::new(), stderr: String::new(), exit_code: None, diagnostics })
+    };
+    let host_error = |message: String| Diagnostic::error("host", None, None, None, message);
+
+    let files = match generate_project(&payload.scanner, &payload.grammar, &payload.extra_files, payload.no_line) {
+        Ok(files) => files,
+        Err(e) => return failed(Vec::new(), vec![e]),
+    };
 
-    if let Some(mut stdin) = child.stdin.take() {
-        stdin.write_all(payload.test_input.as_bytes()).unwrap_or_default();
+    // Each project gets its own directory, so the files keep their names.
+    let dir = match scratch_dir("project") {
+        Ok(dir) => dir,
+        Err(e) => return failed(files, vec![host_error(format!("Failed to create project directory: {}", e))]),
+    };
+    if let Err(e) = files.iter().try_for_each(|f| fs::write(dir.join(&f.name), &f.content)) {
+        let _ = fs::remove_dir_all(&dir);
+        return failed(files, vec![host_error(format!("Failed to write project files: {}", e))]);
     }
 
-    let exec_output = match child.wait_with_output() {
+    let sources: Vec<&str> = files.iter().map(|f| f.name.as_str()).filter(|n| n.ends_with(".c")).collect();
+    let compile_output = match run_gcc(&dir, &sources, "project.out").await {
         Ok(output) => output,
-        Err(e) => return Json(RunResponse { output: "".into(), error: Some(format!("Execution failed: {}", e)) }),
+        Err(e) => {
+            let _ = fs::remove_dir_all(&dir);
+            return failed(files, vec![host_error(e)]);
+        }
     };
+    let diagnostics = attribute_diagnostics(&String::from_utf8_lossy(&compile_output.stderr), &files);
+    if !compile_output.status.success() {
+        let _ = fs::remove_dir_all(&dir);
+        return failed(files, diagnostics);
+    }
 
-    let stdout_str = String::from_utf8_lossy(&exec_output.stdout).to_string();
-    
-    let _ = fs::remove_file(file_name);
-    let _ = fs::remove_file(exe_name);
-
-    Json(RunResponse { output: stdout_str, error: None })
+    let run = run_program(&dir.join("project.out"), &dir, &payload.test_input).await;
+    let _ = fs::remove_dir_all(&dir);
+    match run {
+        Ok(output) => Json(ProjectRunResponse {
+            files,
+            output: String::from_utf8_lossy(&output.stdout).to_string(),
+            stderr: String::from_utf8_lossy(&output.stderr).to_string(),
+            exit_code: output.status.code(),
+            diagnostics,
+        }),
+        Err(e) => failed(files, vec![host_error(e)]),
+    }
 }
 
 #[tokio::main]
@@ -165,6 +736,18 @@ async fn main() {
         .route("/analyze", post(handle_analyze))
         .route("/assist", post(handle_assist))
         .route("/run", post(handle_run)) // Mounted Run Route
+        .route("/ambiguity", post(handle_ambiguity))
+        .route("/transform", post(handle_transform))
+        .route("/cyk", post(handle_cyk))
+        .route("/generate", post(handle_generate))
+        .route("/automaton", post(handle_automaton))
+        .route("/scan", post(handle_scan))
+        .route("/lint", post(handle_lint))
+        .route("/explain", post(handle_explain))
+        .route("/translate", post(handle_translate))
+        .route("/regex/test", post(handle_regex_test))
+        .route("/equivalence", post(handle_equivalence))
+        .route("/project/run", post(handle_project_run))
         .layer(CorsLayer::permissive());
 
     let addr = SocketAddr::from(([127, 0, 0, 1], 4000));
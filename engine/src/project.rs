@@ -0,0 +1,102 @@
+use serde::{Deserialize, Serialize};
+
+use crate::diagnostics::{parse_gcc_output, Diagnostic};
+use crate::lexgen::{generate_scanner, SCANNER_OUTPUT};
+use crate::yaccgen::{generate_header, generate_parser, HEADER_NAME, PARSER_OUTPUT};
+use crate::{parse_bison, parse_flex, ASTNode, CodegenOptions};
+
+// --- FLEX + BISON PROJECTS ---
+// A scanner and a grammar generated side by side, as a Makefile would run
+// `flex` and `bison -d`: lex.yy.c and y.tab.c are compiled together against
+// y.tab.h, plus any extra C sources. Each output remembers the source it was
+// generated from, so compiler and linker errors land on the right file.
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ProjectFile {
+    pub name: String,
+    pub content: String,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct GeneratedProjectFile {
+    pub name: String,
+    pub content: String,
+    /// Source the file was generated from; extra files are their own.
+    pub source: String,
+}
+
+fn in_source(source: &ProjectFile, message: String, line: Option<usize>, column: Option<usize>) -> Diagnostic {
+    Diagnostic::error(&source.name, Some(&source.name), line, column, message)
+}
+
+/// File names are written into one directory: no paths, no dot files.
+fn check_name(name: &str) -> Result<(), String> {
+    let plain = !name.is_empty()
+        && !name.starts_with('.')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.'));
+    if !plain {
+        return Err(format!("Invalid file name '{}': use letters, digits, '_', '-' and '.' only", name));
+    }
+    if [SCANNER_OUTPUT, PARSER_OUTPUT, HEADER_NAME].contains(&name) {
+        return Err(format!("'{}' is generated and cannot be supplied", name));
+    }
+    Ok(())
+}
+
+/// `<stem>.tab.h` headers the scanner includes, as named by `bison -d`.
+fn included_headers(code: &str) -> Vec<String> {
+    code.lines()
+        .filter_map(|line| {
+            let rest = line.trim_start().strip_prefix('#')?.trim_start().strip_prefix("include")?.trim();
+            let name = rest.strip_prefix('"')?.split('"').next()?;
+            (name.ends_with(".tab.h") && name != HEADER_NAME && check_name(name).is_ok()).then(|| name.to_string())
+        })
+        .collect()
+}
+
+fn generate_from(source: &ProjectFile, ast: &ASTNode, generate: impl Fn(&ASTNode) -> Result<String, String>) -> Result<String, Diagnostic> {
+    match ast {
+        ASTNode::Error { message, line, column } => Err(in_source(source, message.clone(), Some(*line), Some(*column))),
+        _ => generate(ast).map_err(|e| in_source(source, e, None, None)),
+    }
+}
+
+/// lex.yy.c, y.tab.c and y.tab.h for a scanner and grammar, followed by the
+/// extra files as given. Unless `no_line` is set, user code carries `#line`
+/// directives back to the scanner and grammar.
+pub fn generate_project(scanner: &ProjectFile, grammar: &ProjectFile, extra: &[ProjectFile], no_line: bool) -> Result<Vec<GeneratedProjectFile>, Diagnostic> {
+    for (i, f) in extra.iter().enumerate() {
+        check_name(&f.name).map_err(|e| in_source(f, e, None, None))?;
+        if extra[..i].iter().any(|g| g.name == f.name) {
+            return Err(in_source(f, format!("'{}' is supplied more than once", f.name), None, None));
+        }
+    }
+    let codegen = |source: &ProjectFile| CodegenOptions { combined: true, no_line, source_name: Some(source.name.clone()) };
+    let scanner_ast = parse_flex(&scanner.content);
+    let grammar_ast = parse_bison(&grammar.content);
+    let lex = generate_from(scanner, &scanner_ast, |ast| generate_scanner(ast, &codegen(scanner)))?;
+    let parser = generate_from(grammar, &grammar_ast, |ast| generate_parser(ast, &codegen(grammar)))?;
+    let header = generate_from(grammar, &grammar_ast, generate_header)?;
+
+    let generated = |name: &str, content: &str, source: &ProjectFile| GeneratedProjectFile {
+        name: name.to_string(),
+        content: content.to_string(),
+        source: source.name.clone(),
+    };
+    let mut files = vec![generated(SCANNER_OUTPUT, &lex, scanner), generated(PARSER_OUTPUT, &parser, grammar), generated(HEADER_NAME, &header, grammar)];
+    // `#include "calc.tab.h"` gets the same header under that name.
+    for name in included_headers(&scanner.content) {
+        if !extra.iter().any(|f| f.name == name) && !files.iter().any(|f| f.name == name) {
+            files.push(generated(&name, &header, grammar));
+        }
+    }
+    files.extend(extra.iter().map(|f| generated(&f.name, &f.content, f)));
+    Ok(files)
+}
+
+/// One diagnostic per gcc or linker message, attributed to the file it
+/// names. Errors `#line` points into the scanner or grammar are filed under it.
+pub fn attribute_diagnostics(stderr: &str, files: &[GeneratedProjectFile]) -> Vec<Diagnostic> {
+    let names: Vec<(&str, &str)> = files.iter().map(|f| (f.name.as_str(), f.source.as_str())).collect();
+    parse_gcc_output(stderr, &names)
+}
@@ -0,0 +1,586 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::OnceLock;
+
+// --- FLEX PATTERN PARSER ---
+// Parses the pattern half of a flex rule into a regex tree. Character classes
+// are resolved to sorted, non-overlapping char ranges up front (negation
+// included) so later stages never need to know about `[^...]` or `.`.
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(tag = "kind")]
+pub enum Regex {
+    Empty,
+    Literal { c: char },
+    Class { ranges: Vec<(char, char)> },
+    Concat { items: Vec<Regex> },
+    Alt { items: Vec<Regex> },
+    Repeat { inner: Box<Regex>, min: u32, max: Option<u32> },
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct Pattern {
+    /// `<SC1,SC2>` prefix; empty means the rule is active in INITIAL (and inclusive conditions).
+    pub start_conditions: Vec<String>,
+    /// `<<EOF>>` rule.
+    pub eof: bool,
+    /// `^` anchor.
+    pub bol: bool,
+    pub regex: Regex,
+    /// `r/s` trailing context (includes a trailing `$`, which is `/\n`).
+    pub trailing: Option<Regex>,
+}
+
+const MAX_CHAR: char = '\u{10FFFF}';
+
+/// Checks a tree that did not come from the pattern parser, which never
+/// builds an empty alternation, a reversed range or a `{m,n}` with m > n.
+pub fn validate_regex(regex: &Regex) -> Result<(), String> {
+    match regex {
+        Regex::Empty | Regex::Literal { .. } => Ok(()),
+        Regex::Class { ranges } => match ranges.iter().find(|(lo, hi)| lo > hi) {
+            Some((lo, hi)) => Err(format!("Class range {:?}-{:?} is reversed", lo, hi)),
+            None => Ok(()),
+        },
+        Regex::Concat { items } => items.iter().try_for_each(validate_regex),
+        Regex::Alt { items } if items.is_empty() => Err("Alternation has no alternatives".to_string()),
+        Regex::Alt { items } => items.iter().try_for_each(validate_regex),
+        Regex::Repeat { max: Some(max), min, .. } if max < min => Err(format!("Repetition {{{},{}}} has its maximum below its minimum", min, max)),
+        Regex::Repeat { inner, .. } => validate_regex(inner),
+    }
+}
+
+/// Sorts and merges overlapping/adjacent ranges.
+pub fn normalize_ranges(mut ranges: Vec<(char, char)>) -> Vec<(char, char)> {
+    ranges.sort();
+    let mut out: Vec<(char, char)> = Vec::new();
+    for (lo, hi) in ranges {
+        if let Some(last) = out.last_mut() {
+            if (lo as u32) <= (last.1 as u32).saturating_add(1) {
+                if hi > last.1 {
+                    last.1 = hi;
+                }
+                continue;
+            }
+        }
+        out.push((lo, hi));
+    }
+    out
+}
+
+pub(crate) fn char_before(c: char) -> Option<char> {
+    match c as u32 {
+        0 => None,
+        0xE000 => Some('\u{D7FF}'),
+        n => char::from_u32(n - 1),
+    }
+}
+
+pub(crate) fn char_after(c: char) -> Option<char> {
+    match c as u32 {
+        0x10FFFF => None,
+        0xD7FF => Some('\u{E000}'),
+        n => char::from_u32(n + 1),
+    }
+}
+
+pub fn complement_ranges(ranges: &[(char, char)]) -> Vec<(char, char)> {
+    let mut out = Vec::new();
+    let mut next = Some('\0');
+    for &(lo, hi) in &normalize_ranges(ranges.to_vec()) {
+        if let Some(n) = next {
+            if n < lo {
+                out.push((n, char_before(lo).unwrap()));
+            }
+        }
+        next = char_after(hi);
+    }
+    if let Some(n) = next {
+        out.push((n, MAX_CHAR));
+    }
+    out
+}
+
+fn posix_class(name: &str) -> Option<Vec<(char, char)>> {
+    let ranges = match name {
+        "alpha" => vec![('A', 'Z'), ('a', 'z')],
+        "digit" => vec![('0', '9')],
+        "alnum" => vec![('0', '9'), ('A', 'Z'), ('a', 'z')],
+        "upper" => vec![('A', 'Z')],
+        "lower" => vec![('a', 'z')],
+        "space" => vec![('\t', '\r'), (' ', ' ')],
+        "blank" => vec![('\t', '\t'), (' ', ' ')],
+        "xdigit" => vec![('0', '9'), ('A', 'F'), ('a', 'f')],
+        "punct" => vec![('!', '/'), (':', '@'), ('[', '`'), ('{', '~')],
+        "print" => vec![(' ', '~')],
+        "graph" => vec![('!', '~')],
+        "cntrl" => vec![('\0', '\x1f'), ('\x7f', '\x7f')],
+        _ => return None,
+    };
+    Some(ranges)
+}
+
+type UnicodeProperty = (&'static str, &'static [&'static str], fn(char) -> bool);
+type PropertyRanges = (&'static str, Vec<(char, char)>);
+
+/// Unicode properties usable as `\p{Name}`, built from the predicates std
+/// exposes: `(canonical name, aliases, predicate)`. `L` is served by the
+/// Alphabetic property, a superset that also holds letter numbers and a few
+/// combining marks.
+const UNICODE_PROPERTIES: &[UnicodeProperty] = &[
+    ("Alphabetic", &["L", "Letter", "Alpha"], char::is_alphabetic),
+    ("Lowercase", &["Ll", "Lower"], char::is_lowercase),
+    ("Uppercase", &["Lu", "Upper"], char::is_uppercase),
+    ("N", &["Number", "Numeric"], char::is_numeric),
+    ("White_Space", &["Space", "WSpace", "Z"], char::is_whitespace),
+    ("Cc", &["Control"], char::is_control),
+    ("Alnum", &["LN"], char::is_alphanumeric),
+    ("ASCII", &[], |c| c.is_ascii()),
+    ("Any", &[], |_| true),
+];
+
+/// Ranges of every entry in `UNICODE_PROPERTIES`, computed once.
+fn property_table() -> &'static [PropertyRanges] {
+    static TABLE: OnceLock<Vec<PropertyRanges>> = OnceLock::new();
+    TABLE.get_or_init(|| {
+        UNICODE_PROPERTIES
+            .iter()
+            .map(|&(name, _, pred)| {
+                let mut ranges: Vec<(char, char)> = Vec::new();
+                for c in ('\0'..='\u{10FFFF}').filter(|&c| pred(c)) {
+                    match ranges.last_mut() {
+                        Some(last) if char_after(last.1) == Some(c) => last.1 = c,
+                        _ => ranges.push((c, c)),
+                    }
+                }
+                (name, ranges)
+            })
+            .collect()
+    })
+}
+
+/// Ranges for a `\p{Name}` property (names and aliases are case-insensitive).
+pub fn unicode_property(name: &str) -> Option<Vec<(char, char)>> {
+    let idx = UNICODE_PROPERTIES.iter().position(|(canonical, aliases, _)| {
+        canonical.eq_ignore_ascii_case(name) || aliases.iter().any(|a| a.eq_ignore_ascii_case(name))
+    })?;
+    Some(property_table()[idx].1.clone())
+}
+
+/// Canonical name of the property whose ranges are exactly `ranges`.
+pub fn property_name(ranges: &[(char, char)]) -> Option<&'static str> {
+    // Every property has more than a handful of ranges; skip the table for small classes.
+    if ranges.len() < 4 {
+        return None;
+    }
+    property_table().iter().find(|(_, r)| r == ranges).map(|(name, _)| *name)
+}
+
+struct PatternParser<'a> {
+    chars: Vec<char>,
+    pos: usize,
+    src: &'a str,
+}
+
+impl PatternParser<'_> {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn peek_at(&self, offset: usize) -> Option<char> {
+        self.chars.get(self.pos + offset).copied()
+    }
+
+    fn error(&self, message: &str) -> String {
+        format!("{} at offset {} in pattern '{}'", message, self.pos, self.src)
+    }
+
+    /// `\p{Name}` / `\P{Name}` at the cursor (backslash not yet consumed).
+    fn parse_property(&mut self) -> Option<Result<Vec<(char, char)>, String>> {
+        let kind = self.peek_at(1)?;
+        if !matches!(kind, 'p' | 'P') || self.peek_at(2) != Some('{') {
+            return None;
+        }
+        let start = self.pos + 3;
+        let Some(len) = self.chars[start..].iter().position(|&c| c == '}') else {
+            return Some(Err(self.error("Unterminated '\\p{'")));
+        };
+        let name: String = self.chars[start..start + len].iter().collect();
+        let Some(ranges) = unicode_property(name.trim()) else {
+            return Some(Err(self.error(&format!("Unsupported Unicode property '{}'", name))));
+        };
+        self.pos = start + len + 1;
+        Some(Ok(if kind == 'P' { complement_ranges(&ranges) } else { ranges }))
+    }
+
+    fn parse_escape(&mut self) -> Result<char, String> {
+        // Called with the backslash already consumed.
+        let c = self.peek().ok_or_else(|| self.error("Dangling '\\'"))?;
+        self.pos += 1;
+        let out = match c {
+            'n' => '\n',
+            't' => '\t',
+            'r' => '\r',
+            'f' => '\x0c',
+            'v' => '\x0b',
+            'a' => '\x07',
+            'b' => '\x08',
+            'u' if self.peek() == Some('{') => {
+                let start = self.pos + 1;
+                let len = self.chars[start..].iter().position(|&c| c == '}').ok_or_else(|| self.error("Unterminated '\\u{'"))?;
+                let hex: String = self.chars[start..start + len].iter().collect();
+                self.pos = start + len + 1;
+                u32::from_str_radix(&hex, 16)
+                    .ok()
+                    .and_then(char::from_u32)
+                    .ok_or_else(|| self.error(&format!("Invalid code point '\\u{{{}}}'", hex)))?
+            }
+            'x' => {
+                let mut val = 0u32;
+                let mut digits = 0;
+                while let Some(d) = self.peek().and_then(|d| d.to_digit(16)) {
+                    if digits == 2 {
+                        break;
+                    }
+                    val = val * 16 + d;
+                    digits += 1;
+                    self.pos += 1;
+                }
+                if digits == 0 {
+                    return Err(self.error("Expected hex digits after '\\x'"));
+                }
+                char::from_u32(val).unwrap()
+            }
+            '0'..='7' => {
+                let mut val = c.to_digit(8).unwrap();
+                let mut digits = 1;
+                while let Some(d) = self.peek().and_then(|d| d.to_digit(8)) {
+                    if digits == 3 {
+                        break;
+                    }
+                    val = val * 8 + d;
+                    digits += 1;
+                    self.pos += 1;
+                }
+                char::from_u32(val).unwrap()
+            }
+            other => other,
+        };
+        Ok(out)
+    }
+
+    fn parse_alt(&mut self) -> Result<Regex, String> {
+        let mut items = vec![self.parse_concat()?];
+        while self.peek() == Some('|') {
+            self.pos += 1;
+            items.push(self.parse_concat()?);
+        }
+        Ok(if items.len() == 1 { items.pop().unwrap() } else { Regex::Alt { items } })
+    }
+
+    fn at_concat_end(&self) -> bool {
+        match self.peek() {
+            None | Some('|') | Some(')') | Some('/') => true,
+            // `$` is only an anchor as the very last character.
+            Some('$') => self.pos + 1 == self.chars.len(),
+            _ => false,
+        }
+    }
+
+    fn parse_concat(&mut self) -> Result<Regex, String> {
+        let mut items = Vec::new();
+        while !self.at_concat_end() {
+            let atom = self.parse_atom()?;
+            let atom = self.parse_postfix(atom)?;
+            items.push(atom);
+        }
+        Ok(match items.len() {
+            0 => Regex::Empty,
+            1 => items.pop().unwrap(),
+            _ => Regex::Concat { items },
+        })
+    }
+
+    fn parse_postfix(&mut self, mut atom: Regex) -> Result<Regex, String> {
+        loop {
+            match self.peek() {
+                Some('*') => {
+                    self.pos += 1;
+                    atom = Regex::Repeat { inner: Box::new(atom), min: 0, max: None };
+                }
+                Some('+') => {
+                    self.pos += 1;
+                    atom = Regex::Repeat { inner: Box::new(atom), min: 1, max: None };
+                }
+                Some('?') => {
+                    self.pos += 1;
+                    atom = Regex::Repeat { inner: Box::new(atom), min: 0, max: Some(1) };
+                }
+                Some('{') if self.peek_at(1).is_some_and(|c| c.is_ascii_digit()) => {
+                    self.pos += 1;
+                    let min = self.parse_number()?;
+                    let max = if self.peek() == Some(',') {
+                        self.pos += 1;
+                        if self.peek() == Some('}') { None } else { Some(self.parse_number()?) }
+                    } else {
+                        Some(min)
+                    };
+                    if self.peek() != Some('}') {
+                        return Err(self.error("Expected '}' to close repetition"));
+                    }
+                    self.pos += 1;
+                    if max.is_some_and(|m| m < min) {
+                        return Err(self.error("Repetition maximum is below its minimum"));
+                    }
+                    atom = Regex::Repeat { inner: Box::new(atom), min, max };
+                }
+                _ => return Ok(atom),
+            }
+        }
+    }
+
+    fn parse_number(&mut self) -> Result<u32, String> {
+        let start = self.pos;
+        while self.peek().is_some_and(|c| c.is_ascii_digit()) {
+            self.pos += 1;
+        }
+        let text: String = self.chars[start..self.pos].iter().collect();
+        text.parse().map_err(|_| self.error("Expected a number"))
+    }
+
+    fn parse_atom(&mut self) -> Result<Regex, String> {
+        let c = self.peek().ok_or_else(|| self.error("Unexpected end of pattern"))?;
+        self.pos += 1;
+        match c {
+            '(' => {
+                let inner = self.parse_alt()?;
+                if self.peek() != Some(')') {
+                    return Err(self.error("Expected ')'"));
+                }
+                self.pos += 1;
+                Ok(inner)
+            }
+            '[' => self.parse_class(),
+            '"' => {
+                let mut items = Vec::new();
+                loop {
+                    match self.peek() {
+                        None => return Err(self.error("Unterminated string")),
+                        Some('"') => {
+                            self.pos += 1;
+                            break;
+                        }
+                        Some('\\') => {
+                            self.pos += 1;
+                            items.push(Regex::Literal { c: self.parse_escape()? });
+                        }
+                        Some(ch) => {
+                            self.pos += 1;
+                            items.push(Regex::Literal { c: ch });
+                        }
+                    }
+                }
+                Ok(match items.len() {
+                    0 => Regex::Empty,
+                    1 => items.pop().unwrap(),
+                    _ => Regex::Concat { items },
+                })
+            }
+            '.' => Ok(Regex::Class { ranges: complement_ranges(&[('\n', '\n')]) }),
+            '\\' => {
+                self.pos -= 1;
+                if let Some(ranges) = self.parse_property() {
+                    return Ok(Regex::Class { ranges: ranges? });
+                }
+                self.pos += 1;
+                Ok(Regex::Literal { c: self.parse_escape()? })
+            }
+            '{' => {
+                let start = self.pos;
+                while self.peek().is_some_and(|c| c != '}') {
+                    self.pos += 1;
+                }
+                let name: String = self.chars[start..self.pos].iter().collect();
+                Err(self.error(&format!("Undefined definition '{{{}}}'", name)))
+            }
+            '*' | '+' | '?' => Err(self.error(&format!("Nothing to repeat before '{}'", c))),
+            other => Ok(Regex::Literal { c: other }),
+        }
+    }
+
+    fn parse_class(&mut self) -> Result<Regex, String> {
+        let mut negated = false;
+        if self.peek() == Some('^') {
+            negated = true;
+            self.pos += 1;
+        }
+        let mut ranges = Vec::new();
+        let mut first = true;
+        loop {
+            let c = self.peek().ok_or_else(|| self.error("Unterminated character class"))?;
+            if c == ']' && !first {
+                self.pos += 1;
+                break;
+            }
+            first = false;
+            if c == '[' && self.peek_at(1) == Some(':') {
+                let rest: String = self.chars[self.pos + 2..].iter().collect();
+                if let Some(end) = rest.find(":]") {
+                    let name = &rest[..end];
+                    let class = posix_class(name).ok_or_else(|| self.error(&format!("Unknown class '[:{}:]'", name)))?;
+                    ranges.extend(class);
+                    self.pos += 2 + name.chars().count() + 2;
+                    continue;
+                }
+            }
+            if c == '\\' {
+                if let Some(property) = self.parse_property() {
+                    ranges.extend(property?);
+                    continue;
+                }
+            }
+            self.pos += 1;
+            let lo = if c == '\\' { self.parse_escape()? } else { c };
+            if self.peek() == Some('-') && self.peek_at(1).is_some_and(|n| n != ']') {
+                self.pos += 1;
+                let h = self.peek().unwrap();
+                self.pos += 1;
+                let hi = if h == '\\' { self.parse_escape()? } else { h };
+                if hi < lo {
+                    return Err(self.error("Reversed range in character class"));
+                }
+                ranges.push((lo, hi));
+            } else {
+                ranges.push((lo, lo));
+            }
+        }
+        let ranges = if negated { complement_ranges(&ranges) } else { normalize_ranges(ranges) };
+        Ok(Regex::Class { ranges })
+    }
+}
+
+/// Parses a flex rule pattern, including start conditions, anchors and
+/// trailing context.
+pub fn parse_pattern(src: &str) -> Result<Pattern, String> {
+    let mut parser = PatternParser { chars: src.chars().collect(), pos: 0, src };
+    let mut start_conditions = Vec::new();
+
+    if parser.peek() == Some('<') && !src.starts_with("<<EOF>>") {
+        let end = src.find('>').ok_or_else(|| parser.error("Unterminated start condition list"))?;
+        start_conditions = src[1..end].split(',').map(|s| s.trim().to_string()).filter(|s| !s.is_empty()).collect();
+        parser.pos = src[..=end].chars().count();
+    }
+    let rest: String = parser.chars[parser.pos..].iter().collect();
+    if rest == "<<EOF>>" {
+        return Ok(Pattern { start_conditions, eof: true, bol: false, regex: Regex::Empty, trailing: None });
+    }
+
+    let mut bol = false;
+    if parser.peek() == Some('^') {
+        bol = true;
+        parser.pos += 1;
+    }
+    let regex = parser.parse_alt()?;
+    let mut trailing = None;
+    if parser.peek() == Some('/') {
+        parser.pos += 1;
+        trailing = Some(parser.parse_alt()?);
+    }
+    if parser.peek() == Some('$') {
+        parser.pos += 1;
+        if trailing.is_some() {
+            return Err(parser.error("'$' cannot follow trailing context"));
+        }
+        trailing = Some(Regex::Literal { c: '\n' });
+    }
+    if parser.peek().is_some() {
+        return Err(parser.error("Unexpected character"));
+    }
+    Ok(Pattern { start_conditions, eof: false, bol, regex, trailing })
+}
+
+/// Nesting limit for `{NAME}` expansion (guards against self-referencing definitions).
+const MAX_DEFINITION_DEPTH: usize = 32;
+
+/// Size limit for an expanded pattern: definitions that each use the last
+/// twice double with every level.
+const MAX_EXPANDED_LENGTH: usize = 1 << 16;
+
+/// Replaces `{NAME}` references with `(definition)`, as flex does, leaving
+/// repetitions like `{2,3}` and anything quoted or bracketed untouched.
+pub fn expand_definitions(src: &str, definitions: &BTreeMap<String, String>) -> Result<String, String> {
+    expand_at_depth(src, definitions, 0)
+}
+
+fn expand_at_depth(src: &str, definitions: &BTreeMap<String, String>, depth: usize) -> Result<String, String> {
+    if depth > MAX_DEFINITION_DEPTH {
+        return Err(format!("Definitions nest too deeply in '{}'", src));
+    }
+    let chars: Vec<char> = src.chars().collect();
+    let mut out = String::new();
+    let mut in_quotes = false;
+    let mut in_class = false;
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        match c {
+            '\\' => {
+                out.push(c);
+                if let Some(&n) = chars.get(i + 1) {
+                    out.push(n);
+                }
+                i += 2;
+                // `\p{L}` and `\u{e9}` braces are not definition references.
+                if matches!(chars.get(i - 1), Some('p' | 'P' | 'u')) && chars.get(i) == Some(&'{') {
+                    while let Some(&b) = chars.get(i) {
+                        out.push(b);
+                        i += 1;
+                        if b == '}' {
+                            break;
+                        }
+                    }
+                }
+                continue;
+            }
+            '"' if !in_class => in_quotes = !in_quotes,
+            '[' if !in_quotes => in_class = true,
+            ']' if in_class => in_class = false,
+            '{' if !in_quotes && !in_class && chars.get(i + 1).is_some_and(|n| n.is_alphabetic() || *n == '_') => {
+                let end = chars[i..].iter().position(|&x| x == '}').map(|p| p + i);
+                let Some(end) = end else { return Err(format!("Unterminated '{{' in '{}'", src)) };
+                let name: String = chars[i + 1..end].iter().collect();
+                let body = definitions.get(&name).ok_or_else(|| format!("Undefined definition '{{{}}}'", name))?;
+                out.push('(');
+                out.push_str(&expand_at_depth(body, definitions, depth + 1)?);
+                out.push(')');
+                if out.len() > MAX_EXPANDED_LENGTH {
+                    return Err(format!("Definitions expand to more than {} characters in '{}'", MAX_EXPANDED_LENGTH, src));
+                }
+                i = end + 1;
+                continue;
+            }
+            _ => {}
+        }
+        out.push(c);
+        i += 1;
+    }
+    Ok(out)
+}
+
+/// `NAME -> pattern` table from a FlexFile's definitions section.
+pub fn flex_definitions(flex: &crate::ASTNode) -> BTreeMap<String, String> {
+    let mut out = BTreeMap::new();
+    if let crate::ASTNode::FlexFile { definitions, .. } = flex {
+        for d in definitions {
+            if let crate::ASTNode::FlexDefinition { name, pattern } = d {
+                out.insert(name.clone(), pattern.clone());
+            }
+        }
+    }
+    out
+}
+
+/// Expands definitions, then parses the pattern.
+pub fn parse_flex_pattern(src: &str, definitions: &BTreeMap<String, String>) -> Result<Pattern, String> {
+    parse_pattern(&expand_definitions(src, definitions)?)
+}
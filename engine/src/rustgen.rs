@@ -0,0 +1,910 @@
+use std::collections::BTreeMap;
+
+use crate::automaton::compile_flex;
+use crate::grammar::Production;
+use crate::lalr::{build_lalr, ERROR_SYMBOL};
+use crate::lexgen::{dense_table, head_length, mentions, scanner_tables, HeadLength};
+use crate::utf8::byte_dfa;
+use crate::yaccgen::{char_literal_code, pack_parser, symbol_types, translate_action, union_body, RuleContext, ValueAccess};
+use crate::ASTNode;
+
+// --- RUST MODULE GENERATION ---
+// The tables behind lex.yy.c and y.tab.c, driven from Rust instead of C. A
+// FlexFile becomes a `Scanner` over an in-memory buffer whose `yylex()`
+// returns `Token`s; a BisonFile becomes a `Parser` over any `Lexer`, where
+// every `<tag>` names a Rust type and actions are Rust. Each output is one
+// self-contained module, meant to be included with `mod scanner;` or
+// `mod parser;`.
+
+/// Lints the generated code would otherwise trip over: tables and helpers the
+/// spec never uses, yacc-style names, and user actions that end in `return`.
+pub(crate) const MODULE_ATTRIBUTES: &str = "#![allow(dead_code, unused_imports, unused_variables, unused_mut, unreachable_code)]
+#![allow(non_snake_case, non_upper_case_globals, clippy::all)]
+";
+
+/// Narrowest Rust integer type holding every value.
+fn rust_int_type(values: &[i64]) -> &'static str {
+    if values.iter().all(|&v| (0..=u8::MAX as i64).contains(&v)) {
+        "u8"
+    } else if values.iter().all(|&v| (i16::MIN as i64..=i16::MAX as i64).contains(&v)) {
+        "i16"
+    } else {
+        "i32"
+    }
+}
+
+fn rust_array(code: &mut String, ty: &str, name: &str, values: &[i64]) {
+    code.push_str(&format!("const {}: [{}; {}] = [", name.to_uppercase(), ty, values.len()));
+    for (i, v) in values.iter().enumerate() {
+        if i % 10 == 0 {
+            code.push_str("\n   ");
+        }
+        code.push_str(&format!("{:6},", v));
+    }
+    code.push_str("\n];\n\n");
+}
+
+fn is_rust_identifier(sym: &str) -> bool {
+    sym.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+        && sym.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        && !matches!(sym, "Eof" | "Char" | "Self" | "self" | "super" | "crate")
+}
+
+// --- SCANNER ---
+
+/// Unit tokens the actions return, as `return NAME;` or `return Token::NAME;`.
+fn returned_tokens<'a>(actions: impl Iterator<Item = &'a str>) -> Vec<String> {
+    let mut out: Vec<String> = Vec::new();
+    for action in actions {
+        for (i, _) in action.match_indices("return") {
+            if !mentions(&action[i..i + 6], "return") || action[..i].ends_with(|c: char| c.is_alphanumeric() || c == '_') {
+                continue;
+            }
+            let rest = action[i + 6..].trim_start();
+            let rest = rest.strip_prefix("Token::").unwrap_or(rest);
+            let end = rest.find(|c: char| !(c.is_alphanumeric() || c == '_')).unwrap_or(rest.len());
+            let (name, after) = rest.split_at(end);
+            let unit = after.trim_start().starts_with([';', '}']) || after.trim().is_empty();
+            if unit && is_rust_identifier(name) && !out.iter().any(|n| n == name) {
+                out.push(name.to_string());
+            }
+        }
+    }
+    out
+}
+
+/// `yy_head_length()`: trims trailing context off a match.
+fn emit_head_length(code: &mut String, rules: &[crate::automaton::AutomatonRule]) -> Result<(), String> {
+    let mut cases = String::new();
+    for (ri, rule) in rules.iter().enumerate() {
+        let Some(split) = head_length(rule)? else { continue };
+        let act = ri + 1;
+        match split {
+            HeadLength::MinusTrailing(n) => cases.push_str(&format!("        {} => len.saturating_sub({}),\n", act, n)),
+            HeadLength::Fixed(n) => cases.push_str(&format!("        {} => {},\n", act, n)),
+            HeadLength::Split { head, tail } => {
+                let mut starts = Vec::new();
+                for (part, dfa) in [("head", &head), ("tail", &tail)] {
+                    let (table, accepting, start) = dense_table(dfa);
+                    rust_array(code, "i32", &format!("yy_tc{}_{}", act, part), &table);
+                    rust_array(code, "i32", &format!("yy_tc{}_{}_accept", act, part), &accepting);
+                    starts.push(start);
+                }
+                cases.push_str(&format!(
+                    "        {0} => (1..=len)\n            .rev()\n            .find(|&i| {{\n                yy_tc_accepts(&YY_TC{0}_HEAD, &YY_TC{0}_HEAD_ACCEPT, {1}, &text[..i])\n                    && yy_tc_accepts(&YY_TC{0}_TAIL, &YY_TC{0}_TAIL_ACCEPT, {2}, &text[i..])\n            }})\n            .unwrap_or(0),\n",
+                    act, starts[0], starts[1]
+                ));
+            }
+        }
+    }
+    code.push_str("/// Bytes of a match that belong to yytext; the rest is trailing context.\n");
+    code.push_str("fn yy_head_length(act: usize, text: &[u8]) -> usize {\n    let len = text.len();\n    let head: usize = match act {\n");
+    code.push_str(&cases);
+    code.push_str("        _ => return len,\n    };\n    head.max(1)\n}\n\n");
+    Ok(())
+}
+
+/// Whether user code brings its own `name`: `enum`, `struct` or `type name`,
+/// or a `use` importing it under that name. Merely using it does not count.
+fn declares_item(code: &str, name: &str) -> bool {
+    let is_ident = |c: char| c.is_alphanumeric() || c == '_';
+    let is_keyword = |text: &str, keyword: &str| text.ends_with(keyword) && !text[..text.len() - keyword.len()].ends_with(is_ident);
+    code.match_indices(name).any(|(i, _)| {
+        let (before, after) = (&code[..i], &code[i + name.len()..]);
+        if before.ends_with(is_ident) || after.starts_with(is_ident) {
+            return false;
+        }
+        let before = before.trim_end();
+        if ["enum", "struct", "type"].iter().any(|k| is_keyword(before, k)) {
+            return true;
+        }
+        // The path of a `use` runs from the keyword to here, and `as` renames.
+        let statement = &before[before.rfind(';').map_or(0, |p| p + 1)..];
+        let path_char = |c: char| is_ident(c) || c.is_whitespace() || ":{},*".contains(c);
+        let in_use = statement.match_indices("use").any(|(u, _)| {
+            is_keyword(&statement[..u + 3], "use") && statement[u + 3..].starts_with(char::is_whitespace) && statement[u + 3..].chars().all(path_char)
+        });
+        in_use && !after.trim_start().starts_with("as ")
+    })
+}
+
+pub fn generate_rust_scanner(flex: &ASTNode) -> Result<String, String> {
+    let ASTNode::FlexFile { prologue, epilogue, .. } = flex else {
+        return Err("Expected a Flex specification".to_string());
+    };
+    let automaton = compile_flex(flex)?;
+    let dfa = byte_dfa(&automaton)?;
+    let rules = &automaton.rules;
+    if rules.iter().any(|r| mentions(&r.action, "REJECT")) {
+        return Err("REJECT is not supported by the Rust target".to_string());
+    }
+    let tables = scanner_tables(&automaton, &dfa)?;
+    let user_code = format!("{}\n{}", prologue.as_deref().unwrap_or(""), epilogue.as_deref().unwrap_or(""));
+
+    let mut code = String::from("// Generated by Structura.ai Lexical Engine\n");
+    code.push_str(MODULE_ATTRIBUTES);
+    code.push_str(SCANNER_DOC);
+    if let Some(p) = prologue {
+        code.push_str("\n// --- PROLOGUE ---\n");
+        code.push_str(p);
+        code.push('\n');
+    }
+    // A prologue that brings its own Token (say, the parser's) keeps it.
+    if !declares_item(&user_code, "Token") {
+        code.push_str("\n/// What `yylex()` returns: `Char` for single characters, a unit variant\n/// for every name an action returns.\n");
+        code.push_str("#[derive(Debug, Clone, Copy, PartialEq, Eq)]\npub enum Token {\n    Eof,\n    Char(char),\n");
+        for name in returned_tokens(rules.iter().map(|r| r.action.as_str())) {
+            code.push_str(&format!("    {},\n", name));
+        }
+        code.push_str("}\n");
+    }
+    code.push('\n');
+    for (i, sc) in automaton.start_conditions.iter().enumerate() {
+        code.push_str(&format!("pub const {}: usize = {};\n", sc.name, i));
+    }
+    code.push_str(&format!("\nconst YY_NUM_RULES: usize = {};\nconst YY_DEFAULT_RULE: usize = {};\n\n", rules.len(), rules.len() + 1));
+
+    // --- Tables ---
+    code.push_str(&format!(
+        "// {} DFA states over {} byte classes; {} packed transition slots.\n",
+        dfa.states.len(),
+        tables.classes,
+        tables.nxt.len()
+    ));
+    rust_array(&mut code, "u8", "yy_ec", &tables.ec);
+    for (name, values) in tables.arrays() {
+        rust_array(&mut code, rust_int_type(values), name, values);
+    }
+    emit_head_length(&mut code, rules)?;
+    code.push_str(SCANNER_RUNTIME);
+
+    // --- yylex() ---
+    code.push_str(SCANNER_MATCH);
+    for (ri, rule) in rules.iter().enumerate() {
+        code.push_str(&format!("                {} => {{\n", ri + 1));
+        code.push_str(&format!("                    // rule {}, line {}: {}\n", rule.index + 1, rule.line, rule.pattern.replace('\n', " ")));
+        if mentions(&rule.action, "yytext") {
+            code.push_str("                    let yytext = self.yytext.clone();\n");
+        }
+        if mentions(&rule.action, "yyleng") {
+            code.push_str("                    let yyleng = self.yyleng();\n");
+        }
+        if rule.action.trim() != ";" {
+            code.push_str(&format!("                    {{ {} }}\n", rule.action.trim()));
+        }
+        if rule.parsed.eof {
+            // Stop unless the action brought in new input.
+            code.push_str("                    if self.pos >= self.buf.len() {\n                        return Token::Eof;\n                    }\n");
+        }
+        code.push_str("                }\n");
+    }
+    code.push_str("                YY_DEFAULT_RULE => self.echo(),\n                _ => unreachable!(\"fatal flex scanner internal error--no action found\"),\n            }\n        }\n    }\n}\n");
+    code.push_str(SCANNER_ITERATOR);
+
+    if let Some(e) = epilogue {
+        code.push_str("\n// --- EPILOGUE ---\n");
+        code.push_str(e);
+        code.push('\n');
+    }
+    Ok(code)
+}
+
+const SCANNER_DOC: &str = "
+// Actions are Rust and run inside `Scanner::yylex()`: `yytext` and `yyleng`
+// are bound for them, `return Token::X` ends the call, and flex's macros are
+// methods: `self.begin(COND)`, `self.yyless(n)`, `self.yymore()`,
+// `self.echo()`, `self.input()`, `self.unput(c)`, `self.yylineno`.
+";
+
+const SCANNER_RUNTIME: &str = r#"fn yy_tc_accepts(table: &[i32], accepting: &[i32], mut state: i32, s: &[u8]) -> bool {
+    for &b in s {
+        if state < 0 {
+            return false;
+        }
+        state = table[state as usize * 256 + b as usize];
+    }
+    state >= 0 && accepting[state as usize] != 0
+}
+
+/// Bytes in the UTF-8 sequence `s` starts with: what the default rule copies.
+fn yy_char_len(s: &[u8]) -> usize {
+    let n = match s[0] {
+        0xC0..=0xDF => 2,
+        0xE0..=0xEF => 3,
+        0xF0..=0xF7 => 4,
+        _ => 1,
+    };
+    n.min(s.len())
+}
+
+/// Scans a whole input held in memory, so yyless, yymore and unput can move
+/// freely around yytext.
+pub struct Scanner {
+    buf: Vec<u8>,
+    /// Next byte to scan.
+    pos: usize,
+    start: usize,
+    more: bool,
+    text_start: usize,
+    text_end: usize,
+    /// The current match.
+    pub yytext: String,
+    pub yylineno: usize,
+}
+
+impl Scanner {
+    pub fn new(input: &str) -> Self {
+        Scanner::from_bytes(input.as_bytes().to_vec())
+    }
+
+    pub fn from_bytes(buf: Vec<u8>) -> Self {
+        Scanner { buf, pos: 0, start: INITIAL, more: false, text_start: 0, text_end: 0, yytext: String::new(), yylineno: 1 }
+    }
+
+    /// `BEGIN(condition)`.
+    pub fn begin(&mut self, condition: usize) {
+        self.start = condition;
+    }
+
+    /// `YY_START`.
+    pub fn yy_start(&self) -> usize {
+        self.start
+    }
+
+    /// Length of the current match in bytes.
+    pub fn yyleng(&self) -> usize {
+        self.text_end - self.text_start
+    }
+
+    /// `ECHO`: copies the match to stdout.
+    pub fn echo(&self) {
+        use std::io::Write;
+        let _ = std::io::stdout().write_all(&self.buf[self.text_start..self.text_end]);
+    }
+
+    /// `yymore()`: the next match is appended to this one.
+    pub fn yymore(&mut self) {
+        self.more = true;
+    }
+
+    /// `yyless(n)`: keeps the first `n` bytes of the match and scans the rest again.
+    pub fn yyless(&mut self, n: usize) {
+        let end = (self.text_start + n).min(self.text_end);
+        self.yylineno -= self.buf[end..self.text_end].iter().filter(|&&b| b == b'\n').count();
+        self.pos = end;
+        self.set_text(self.text_start, end);
+    }
+
+    /// `input()`: the next byte, consumed without being matched.
+    pub fn input(&mut self) -> Option<u8> {
+        let c = *self.buf.get(self.pos)?;
+        self.pos += 1;
+        if c == b'\n' {
+            self.yylineno += 1;
+        }
+        Some(c)
+    }
+
+    /// `unput(c)`: pushes a byte back in front of the cursor.
+    pub fn unput(&mut self, c: u8) {
+        self.buf.insert(self.pos, c);
+        if c == b'\n' {
+            self.yylineno = self.yylineno.saturating_sub(1);
+        }
+    }
+
+    fn set_text(&mut self, start: usize, end: usize) {
+        self.text_start = start;
+        self.text_end = end;
+        self.yytext = String::from_utf8_lossy(&self.buf[start..end]).into_owned();
+    }
+
+    fn next_state(mut state: i32, class: i32) -> i32 {
+        loop {
+            let slot = (YY_BASE[state as usize] as i32 + class) as usize;
+            if YY_CHK[slot] as i32 == state {
+                return YY_NXT[slot] as i32;
+            }
+            state = YY_DEF[state as usize] as i32;
+            if state < 0 {
+                return -1;
+            }
+        }
+    }
+
+    /// Longest match from `bp`: the rule (0 if none) and where it ends.
+    fn longest_match(&self, bp: usize) -> (usize, usize) {
+        let bol = bp == 0 || self.buf[bp - 1] == b'\n';
+        let mut state = YY_START_STATE[2 * self.start + bol as usize] as i32;
+        let (mut act, mut end) = (0, bp);
+        for cp in bp..self.buf.len() {
+            state = Self::next_state(state, YY_EC[self.buf[cp] as usize] as i32);
+            if state < 0 {
+                break;
+            }
+            if YY_ACCEPT[state as usize] != 0 {
+                act = YY_ACCEPT[state as usize] as usize;
+                end = cp + 1;
+            }
+        }
+        (act, end)
+    }
+"#;
+
+const SCANNER_MATCH: &str = r#"
+    /// The next token; `Token::Eof` once the input is used up.
+    pub fn yylex(&mut self) -> Token {
+        use Token::*;
+        loop {
+            let bp = self.pos;
+            let more = std::mem::take(&mut self.more);
+            let act = if bp >= self.buf.len() {
+                let act = YY_EOF_RULE[self.start] as usize;
+                if act == 0 {
+                    return Token::Eof;
+                }
+                self.set_text(bp, bp);
+                act
+            } else {
+                let (act, end) = match self.longest_match(bp) {
+                    // Default rule: copy one character to stdout.
+                    (0, _) => (YY_DEFAULT_RULE, bp + yy_char_len(&self.buf[bp..])),
+                    (act, end) => (act, bp + yy_head_length(act, &self.buf[bp..end])),
+                };
+                self.yylineno += self.buf[bp..end].iter().filter(|&&b| b == b'\n').count();
+                self.pos = end;
+                self.set_text(if more { self.text_start } else { bp }, end);
+                act
+            };
+            match act {
+"#;
+
+const SCANNER_ITERATOR: &str = r#"
+impl Iterator for Scanner {
+    type Item = Token;
+
+    fn next(&mut self) -> Option<Token> {
+        match self.yylex() {
+            Token::Eof => None,
+            token => Some(token),
+        }
+    }
+}
+"#;
+
+// --- PARSER ---
+
+/// Rust type of every `<tag>`: the field's type when a `%union` lists
+/// `name: Type` fields, otherwise the tag itself.
+fn tag_types(bison: &ASTNode) -> Result<BTreeMap<String, String>, String> {
+    let Some(body) = union_body(bison) else { return Ok(BTreeMap::new()) };
+    let mut fields = Vec::new();
+    let (mut depth, mut start) = (0i32, 0);
+    for (i, c) in body.char_indices() {
+        match c {
+            '<' | '(' | '[' => depth += 1,
+            '>' | ')' | ']' => depth -= 1,
+            ',' | ';' if depth == 0 => {
+                fields.push(&body[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    fields.push(&body[start..]);
+    let mut out = BTreeMap::new();
+    for field in fields.iter().map(|f| f.trim()).filter(|f| !f.is_empty()) {
+        let (name, ty) = field
+            .split_once(':')
+            .filter(|(name, _)| is_rust_identifier(name.trim()))
+            .ok_or_else(|| format!("%union field '{}' is not Rust: write 'name: Type'", field))?;
+        out.insert(name.trim().to_string(), ty.trim().to_string());
+    }
+    Ok(out)
+}
+
+/// The stack's sum type: one variant per distinct Rust type, `Unit` for
+/// symbols without a value.
+pub(crate) struct ValueTypes {
+    variants: Vec<String>,
+}
+
+impl ValueTypes {
+    pub(crate) fn accessor(&self, ty: &str) -> String {
+        match self.variants.iter().position(|v| v == ty) {
+            Some(i) => format!("into_v{}", i),
+            None => "into_unit".to_string(),
+        }
+    }
+
+    /// `ty` wrapped as a stack value.
+    pub(crate) fn wrap(&self, ty: &str, expr: &str) -> String {
+        match self.variants.iter().position(|v| v == ty) {
+            Some(i) => format!("YYValue::V{}({})", i, expr),
+            None => "YYValue::Unit".to_string(),
+        }
+    }
+}
+
+/// Rust types of a grammar's symbols, numbered as in `symbols`: tokens
+/// first, then nonterminals.
+pub(crate) struct RustSymbols<'a> {
+    pub symbols: &'a [String],
+    pub ntokens: usize,
+    /// `%type`/`%token` tag of each symbol that has one.
+    pub tags: BTreeMap<String, String>,
+    pub types: Vec<String>,
+    pub values: ValueTypes,
+}
+
+impl<'a> RustSymbols<'a> {
+    pub(crate) fn new(bison: &ASTNode, symbols: &'a [String], ntokens: usize) -> Result<Self, String> {
+        let tags = symbol_types(bison);
+        let fields = tag_types(bison)?;
+        let symbol_type = |sym: &str| -> String {
+            if char_literal_code(sym).is_some() {
+                return "char".to_string();
+            }
+            match tags.get(sym) {
+                Some(tag) => fields.get(tag).cloned().unwrap_or_else(|| tag.clone()),
+                None => "()".to_string(),
+            }
+        };
+        let types: Vec<String> = symbols.iter().map(|s| symbol_type(s)).collect();
+        let mut variants: Vec<String> = types.iter().filter(|t| *t != "()").cloned().collect();
+        variants.sort();
+        variants.dedup();
+        Ok(RustSymbols { symbols, ntokens, tags, types, values: ValueTypes { variants } })
+    }
+
+    pub(crate) fn type_of(&self, sym: &str) -> &str {
+        self.symbols.iter().position(|s| s == sym).map_or("()", |i| self.types[i].as_str())
+    }
+
+    /// Named tokens, which get their own `Token` variant.
+    fn named(&self) -> Vec<usize> {
+        (ERROR_SYMBOL + 2..self.ntokens).filter(|&s| is_rust_identifier(&self.symbols[s])).collect()
+    }
+
+    /// The `Token` a lexer returns for terminal `s`, as a pattern; `None`
+    /// for tokens no `Token` can carry, like `"<="`.
+    pub(crate) fn token_pattern(&self, s: usize) -> Option<String> {
+        let name = &self.symbols[s];
+        if s == 0 {
+            Some("Token::Eof".to_string())
+        } else if let Some(c) = char_literal_code(name).and_then(|c| char::from_u32(c as u32)) {
+            Some(format!("Token::Char({:?})", c))
+        } else if s <= ERROR_SYMBOL + 1 || !is_rust_identifier(name) {
+            None
+        } else if self.types[s] == "()" {
+            Some(format!("Token::{}", name))
+        } else {
+            Some(format!("Token::{}(_)", name))
+        }
+    }
+}
+
+/// `type clash on default action` unless `$1` can become `$$`.
+pub(crate) fn check_default_action(p: &Production, lhs_type: &str, first: Option<&str>) -> Result<(), String> {
+    match first {
+        Some(first) if first != lhs_type && lhs_type != "()" => {
+            Err(format!("type clash on default action: <{}> != <{}> in rule for '{}'", lhs_type, first, p.lhs))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// `yyval`'s declaration and the action, once `_1`..`_n` are bound.
+pub(crate) fn rust_action_body(p: &Production, action: &str, syms: &RustSymbols, lhs_type: &str, first: Option<&str>, indent: &str) -> Result<String, String> {
+    let ctx = RuleContext { lhs: &p.lhs, rhs: &p.rhs, types: &syms.tags, typed: false, access: ValueAccess::Rust };
+    let (body, _) = translate_action(action, &ctx)?;
+    // Without `$$`, the value defaults to `$1` as in bison.
+    let initial = if mentions(&body, "yyval") {
+        String::new()
+    } else if lhs_type == "()" {
+        " = ()".to_string()
+    } else if first == Some(lhs_type) {
+        " = _1.clone()".to_string()
+    } else {
+        " = Default::default()".to_string()
+    };
+    Ok(format!("{0}let mut yyval: {1}{2};\n{0}{{ {3} }}\n", indent, lhs_type, initial, body))
+}
+
+/// The `Token` enum, the types shared with the lexer, `YYValue` and
+/// `yytoken()`, which maps a token to its symbol number and value.
+pub(crate) fn emit_token_types(code: &mut String, syms: &RustSymbols) {
+    let named = syms.named();
+    code.push_str("\n/// What a `Lexer` hands the parser: a variant per named token, carrying\n/// its semantic value, and `Char` for single-character tokens like `'+'`.\n");
+    code.push_str("#[derive(Debug, Clone)]\npub enum Token {\n    Eof,\n    Char(char),\n");
+    for &s in &named {
+        match syms.types[s].as_str() {
+            "()" => code.push_str(&format!("    {},\n", syms.symbols[s])),
+            ty => code.push_str(&format!("    {}({}),\n", syms.symbols[s], ty)),
+        }
+    }
+    code.push_str("}\n");
+    code.push_str(PARSER_TYPES);
+
+    let values = &syms.values;
+    code.push_str("\n/// One semantic value per stack slot, tagged with its Rust type.\nenum YYValue {\n    Unit,\n");
+    for (i, ty) in values.variants.iter().enumerate() {
+        code.push_str(&format!("    V{}({}),\n", i, ty));
+    }
+    code.push_str("}\n\nimpl YYValue {\n    fn into_unit(self) {}\n");
+    for (i, ty) in values.variants.iter().enumerate() {
+        code.push_str(&format!(
+            "\n    fn into_v{0}(self) -> {1} {{\n        match self {{\n            YYValue::V{0}(v) => v,\n            _ => unreachable!(\"semantic value is not a {2}\"),\n        }}\n    }}\n",
+            i,
+            ty,
+            ty.replace('"', "'")
+        ));
+    }
+    code.push_str("}\n\n");
+
+    // Internal symbol numbers: tokens map straight to them, no yytranslate.
+    code.push_str("/// Symbol number and semantic value of a token.\nfn yytoken(token: Token) -> (usize, YYValue) {\n    match token {\n        Token::Eof => (0, YYValue::Unit),\n");
+    code.push_str("        Token::Char(c) => {\n            let symbol = match c {\n");
+    for s in ERROR_SYMBOL + 2..syms.ntokens {
+        if let Some(c) = char_literal_code(&syms.symbols[s]).and_then(|c| char::from_u32(c as u32)) {
+            code.push_str(&format!("                {:?} => {},\n", c, s));
+        }
+    }
+    code.push_str(&format!("                _ => {},\n            }};\n            (symbol, {})\n        }}\n", ERROR_SYMBOL + 1, values.wrap("char", "c")));
+    for &s in &named {
+        let name = &syms.symbols[s];
+        match syms.types[s].as_str() {
+            "()" => code.push_str(&format!("        Token::{} => ({}, YYValue::Unit),\n", name, s)),
+            ty => code.push_str(&format!("        Token::{}(v) => ({}, {}),\n", name, s, values.wrap(ty, "v"))),
+        }
+    }
+    code.push_str("    }\n}\n\n");
+}
+
+pub fn generate_rust_parser(bison: &ASTNode) -> Result<String, String> {
+    let ASTNode::BisonFile { prologue, epilogue, .. } = bison else {
+        return Err("Expected a Bison specification".to_string());
+    };
+    let tables = build_lalr(bison)?;
+    let packed = pack_parser(&tables);
+    let ntokens = tables.terminal_count;
+    let syms = RustSymbols::new(bison, &tables.symbols, ntokens)?;
+    let (types, values) = (&syms.types, &syms.values);
+
+    // --- Actions ---
+    let mut cases = String::new();
+    for (r, rule) in tables.rules.iter().enumerate() {
+        let Some(pi) = rule.production else { continue };
+        let p = &tables.grammar.productions[pi];
+        let lhs_type = &types[rule.lhs];
+        let rhs_types: Vec<&String> = rule.rhs.iter().map(|&s| &types[s]).collect();
+        let first = rhs_types.first().map(|t| t.as_str());
+        let Some(action) = &p.action else {
+            check_default_action(p, lhs_type, first)?;
+            match first {
+                None if lhs_type != "()" => {
+                    cases.push_str(&format!("            {} => {{\n                // {}\n", r + 1, p));
+                    cases.push_str(&format!("                Ok(({}, yyloc))\n            }}\n", values.wrap(lhs_type, "Default::default()")));
+                }
+                _ => {}
+            }
+            continue;
+        };
+        cases.push_str(&format!("            {} => {{\n                // {}\n", r + 1, p));
+        for (i, ty) in rhs_types.iter().enumerate() {
+            cases.push_str(&format!("                let _{} = yyrhs.next().unwrap().{}();\n", i + 1, values.accessor(ty)));
+        }
+        cases.push_str(&rust_action_body(p, action, &syms, lhs_type, first, "                ")?);
+        cases.push_str(&format!("                Ok(({}, yyloc))\n            }}\n", values.wrap(lhs_type, "yyval")));
+    }
+
+    let mut code = String::from("// Generated by Structura.ai Syntax Engine\n");
+    code.push_str(MODULE_ATTRIBUTES);
+    code.push_str(PARSER_DOC);
+    if !tables.conflicts.is_empty() {
+        let count = |kind: &str| tables.conflicts.iter().filter(|c| c.kind == kind).count();
+        code.push_str(&format!(
+            "\n// conflicts: {} shift/reduce, {} reduce/reduce (resolved as bison would)\n",
+            count("shift/reduce"),
+            count("reduce/reduce")
+        ));
+    }
+    if let Some(p) = prologue {
+        code.push_str("\n// --- PROLOGUE ---\n");
+        code.push_str(p);
+        code.push('\n');
+    }
+
+    emit_token_types(&mut code, &syms);
+
+    // --- Tables ---
+    code.push_str(&format!(
+        "const YYFINAL: usize = {};\nconst YYLAST: i32 = {};\nconst YYNTOKENS: usize = {};\nconst YYPACT_NINF: i32 = {};\nconst YYTABLE_NINF: i32 = {};\nconst YYTERROR: i32 = {};\nconst YYMAXDEPTH: usize = 10000;\n\n",
+        tables.final_state,
+        packed.table.len() as i64 - 1,
+        ntokens,
+        packed.pact_ninf,
+        packed.table_ninf,
+        ERROR_SYMBOL
+    ));
+    code.push_str(&format!(
+        "// {} states, {} rules; {} packed action and goto slots.\n",
+        tables.actions.len(),
+        tables.rules.len(),
+        packed.table.len()
+    ));
+    for (name, values) in packed.arrays() {
+        rust_array(&mut code, rust_int_type(values), name, values);
+    }
+
+    // --- Driver ---
+    let start_type = &types[tables.rules[0].rhs[0]];
+    code.push_str(&PARSER_DRIVER.replace("START_TYPE", start_type).replace("START_VALUE", &format!("yyvs.swap_remove(1).{}()", values.accessor(start_type))));
+    code.push_str(&cases);
+    code.push_str(PARSER_DRIVER_TAIL);
+
+    if let Some(e) = epilogue {
+        code.push_str("\n// --- EPILOGUE ---\n");
+        code.push_str(e);
+        code.push('\n');
+    }
+    Ok(code)
+}
+
+const PARSER_DOC: &str = "
+// Actions are Rust and run as `Parser` methods: `$$` is `yyval`, `$n` is the
+// n-th value moved off the stack, `@$`/`@n` are `Location`s. yacc's macros
+// become `return Err(YYERROR)`, `return Err(YYABORT)`, `return Err(YYACCEPT)`,
+// `self.yyerrok()`, `self.yyclearin()` and `self.yyerror(msg)`.
+";
+
+const PARSER_TYPES: &str = r#"
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Location {
+    pub first_line: usize,
+    pub first_column: usize,
+    pub last_line: usize,
+    pub last_column: usize,
+}
+
+/// Where the parser's locations start, as bison initializes `yylloc`.
+const YYLOC_INITIAL: Location = Location { first_line: 1, first_column: 1, last_line: 1, last_column: 1 };
+
+/// What the parser needs from a scanner.
+pub trait Lexer {
+    fn yylex(&mut self) -> Token;
+
+    /// Location of the token `yylex()` just returned.
+    fn yylloc(&self) -> Location {
+        YYLOC_INITIAL
+    }
+
+    fn yyerror(&mut self, message: &str) {
+        eprintln!("{}", message);
+    }
+}
+
+/// Why `parse()` gave up.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParseError {
+    /// A syntax error it could not recover from, or `YYABORT`.
+    Aborted,
+    /// The stack outgrew `YYMAXDEPTH`.
+    Exhausted,
+}
+
+/// How an action leaves the parser early.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Control {
+    Accept,
+    Abort,
+    Error,
+}
+
+pub const YYACCEPT: Control = Control::Accept;
+pub const YYABORT: Control = Control::Abort;
+pub const YYERROR: Control = Control::Error;
+"#;
+
+const PARSER_DRIVER: &str = r#"pub struct Parser<L: Lexer> {
+    pub lexer: L,
+    pub yynerrs: usize,
+    yyerrstatus: u8,
+    /// Lookahead: symbol, value and location, `None` when empty.
+    yychar: Option<(usize, YYValue, Location)>,
+}
+
+impl<L: Lexer> Parser<L> {
+    pub fn new(lexer: L) -> Self {
+        Parser { lexer, yynerrs: 0, yyerrstatus: 0, yychar: None }
+    }
+
+    /// `yyerrok`: leave error recovery at once.
+    pub fn yyerrok(&mut self) {
+        self.yyerrstatus = 0;
+    }
+
+    /// `yyclearin`: discard the lookahead.
+    pub fn yyclearin(&mut self) {
+        self.yychar = None;
+    }
+
+    /// `YYRECOVERING()`.
+    pub fn yyrecovering(&self) -> bool {
+        self.yyerrstatus != 0
+    }
+
+    pub fn yyerror(&mut self, message: &str) {
+        self.lexer.yyerror(message);
+    }
+
+    fn lookahead(&mut self) -> usize {
+        if self.yychar.is_none() {
+            let (symbol, value) = yytoken(self.lexer.yylex());
+            self.yychar = Some((symbol, value, self.lexer.yylloc()));
+        }
+        self.yychar.as_ref().map_or(0, |c| c.0)
+    }
+
+    /// Pops states until one can shift `error`, then shifts it.
+    fn recover(&mut self, yyss: &mut Vec<usize>, yyvs: &mut Vec<YYValue>, yyls: &mut Vec<Location>) -> Result<(), ParseError> {
+        self.yyerrstatus = 3;
+        loop {
+            let yyn = YYPACT[yyss[yyss.len() - 1]] as i32;
+            if yyn != YYPACT_NINF {
+                let yyi = yyn + YYTERROR;
+                if 0 <= yyi && yyi <= YYLAST && YYCHECK[yyi as usize] as i32 == YYTERROR && YYTABLE[yyi as usize] > 0 {
+                    yyss.push(YYTABLE[yyi as usize] as usize);
+                    yyvs.push(YYValue::Unit);
+                    yyls.push(self.lexer.yylloc());
+                    return Ok(());
+                }
+            }
+            if yyss.len() == 1 {
+                return Err(ParseError::Aborted);
+            }
+            yyss.pop();
+            yyvs.pop();
+            yyls.pop();
+        }
+    }
+
+    /// Parses the whole input: the start symbol's value, or `None` when an
+    /// action accepted early with `YYACCEPT`.
+    pub fn parse(&mut self) -> Result<Option<START_TYPE>, ParseError> {
+        let mut yystate: usize = 0;
+        let mut yyss: Vec<usize> = vec![0];
+        let mut yyvs: Vec<YYValue> = vec![YYValue::Unit];
+        let mut yyls: Vec<Location> = vec![YYLOC_INITIAL];
+        self.yychar = None;
+        self.yyerrstatus = 0;
+        self.yynerrs = 0;
+        'parse: loop {
+            if yyss.len() > YYMAXDEPTH {
+                self.yyerror("memory exhausted");
+                return Err(ParseError::Exhausted);
+            }
+            if yystate == YYFINAL {
+                return Ok(Some(START_VALUE));
+            }
+
+            // Shift, reduce or fail, reading a lookahead only if the state needs one.
+            let mut yyn = YYPACT[yystate] as i32;
+            let yyrule = 'decide: {
+                if yyn != YYPACT_NINF {
+                    let yytoken = self.lookahead() as i32;
+                    yyn += yytoken;
+                    if 0 <= yyn && yyn <= YYLAST && YYCHECK[yyn as usize] as i32 == yytoken {
+                        match YYTABLE[yyn as usize] as i32 {
+                            YYTABLE_NINF => break 'decide 0,
+                            yyn if yyn <= 0 => break 'decide (-yyn) as usize,
+                            yyn => {
+                                if self.yyerrstatus > 0 {
+                                    self.yyerrstatus -= 1;
+                                }
+                                let (_, value, loc) = self.yychar.take().expect("lookahead to shift");
+                                yyvs.push(value);
+                                yyls.push(loc);
+                                yystate = yyn as usize;
+                                yyss.push(yystate);
+                                continue 'parse;
+                            }
+                        }
+                    }
+                }
+                YYDEFACT[yystate] as usize
+            };
+
+            if yyrule == 0 {
+                // A syntax error.
+                if self.yyerrstatus == 0 {
+                    self.yynerrs += 1;
+                    self.yyerror("syntax error");
+                }
+                if self.yyerrstatus == 3 {
+                    // Just failed to recover: discard the lookahead, but not the end.
+                    match self.yychar {
+                        Some((0, ..)) => return Err(ParseError::Aborted),
+                        Some(_) => self.yychar = None,
+                        None => {}
+                    }
+                }
+                self.recover(&mut yyss, &mut yyvs, &mut yyls)?;
+                yystate = yyss[yyss.len() - 1];
+                continue;
+            }
+
+            // Reduce, with bison's default location: from the first to the last
+            // symbol, or the end of the previous one for an empty rule.
+            let yylen = YYR2[yyrule] as usize;
+            let yyrhs = yyvs.split_off(yyvs.len() - yylen);
+            let yylocs = yyls.split_off(yyls.len() - yylen);
+            let yyloc = match (yylocs.first(), yylocs.last()) {
+                (Some(first), Some(last)) => Location {
+                    first_line: first.first_line,
+                    first_column: first.first_column,
+                    last_line: last.last_line,
+                    last_column: last.last_column,
+                },
+                _ => {
+                    let prev = yyls[yyls.len() - 1];
+                    Location { first_line: prev.last_line, first_column: prev.last_column, ..prev }
+                }
+            };
+            yyss.truncate(yyss.len() - yylen);
+            match self.yyaction(yyrule, yyrhs, &yylocs, yyloc) {
+                Ok((value, loc)) => {
+                    yyvs.push(value);
+                    yyls.push(loc);
+                    let top = yyss[yyss.len() - 1];
+                    let yylhs = YYR1[yyrule] as usize - YYNTOKENS;
+                    let yyi = YYPGOTO[yylhs] as i32 + top as i32;
+                    yystate = if 0 <= yyi && yyi <= YYLAST && YYCHECK[yyi as usize] as i32 == top as i32 {
+                        YYTABLE[yyi as usize] as usize
+                    } else {
+                        YYDEFGOTO[yylhs] as usize
+                    };
+                    yyss.push(yystate);
+                }
+                Err(Control::Accept) => return Ok(None),
+                Err(Control::Abort) => return Err(ParseError::Aborted),
+                Err(Control::Error) => {
+                    // YYERROR: the rule's symbols are gone, recover from below them.
+                    self.recover(&mut yyss, &mut yyvs, &mut yyls)?;
+                    yystate = yyss[yyss.len() - 1];
+                }
+            }
+        }
+    }
+
+    /// Runs rule `yyn`'s action; rules without one pass `$1` up.
+    fn yyaction(&mut self, yyn: usize, yyrhs: Vec<YYValue>, yylocs: &[Location], mut yyloc: Location) -> Result<(YYValue, Location), Control> {
+        let mut yyrhs = yyrhs.into_iter();
+        match yyn {
+"#;
+
+const PARSER_DRIVER_TAIL: &str = r#"            _ => Ok((yyrhs.next().unwrap_or(YYValue::Unit), yyloc)),
+        }
+    }
+}
+"#;
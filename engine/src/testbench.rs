@@ -0,0 +1,156 @@
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+use crate::automaton::{compile_pattern, LexerAutomaton};
+use crate::regex::parse_flex_pattern;
+use crate::visualize::{trace_dfa, DfaTrace};
+
+// --- REGEX TEST BENCH ---
+// Runs one pattern against sample strings under flex semantics: matches are
+// longest-first, trailing context is matched but not consumed, and `^` only
+// holds at the start of a line.
+
+#[derive(Serialize, Clone, Debug)]
+pub struct MatchSpan {
+    /// Character offsets; `end` excludes trailing context.
+    pub start: usize,
+    pub end: usize,
+    /// The same span in UTF-8 bytes.
+    pub byte_start: usize,
+    pub byte_end: usize,
+    pub text: String,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct SampleResult {
+    pub input: String,
+    /// The whole sample matches (trailing context included).
+    pub full_match: bool,
+    /// Length of the lexeme a scanner positioned at the start would take.
+    pub prefix_length: Option<usize>,
+    /// Non-overlapping matches found scanning left to right.
+    pub matches: Vec<MatchSpan>,
+    /// DFA walk over the sample from the start of a line.
+    pub trace: DfaTrace,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct RegexTestReport {
+    pub pattern: String,
+    pub dfa_states: usize,
+    pub samples: Vec<SampleResult>,
+}
+
+/// Lexeme length at `pos`, if the pattern matches a non-empty prefix there.
+fn match_at(automaton: &LexerAutomaton, chars: &[char], pos: usize) -> Option<usize> {
+    let dfa = &automaton.dfa;
+    let bol = pos == 0 || chars[pos - 1] == '\n';
+    let start = dfa.start("INITIAL", bol)?;
+    let (len, _) = dfa.longest_match(start, &chars[pos..])?;
+    let head = automaton.rules[0].head_length(&chars[pos..pos + len]);
+    (len > 0).then_some(head)
+}
+
+fn test_sample(automaton: &LexerAutomaton, input: &str) -> SampleResult {
+    let chars: Vec<char> = input.chars().collect();
+    let dfa = &automaton.dfa;
+    let start = dfa.start("INITIAL", true).unwrap_or(0);
+    let trace = trace_dfa(dfa, start, input);
+    let full_match = trace.consumed == chars.len() && trace.last_accept.is_some_and(|(len, _)| len == chars.len());
+
+    let prefix_length = dfa.longest_match(start, &chars).map(|(len, _)| automaton.rules[0].head_length(&chars[..len]));
+
+    let byte_at = |i: usize| chars[..i].iter().map(|c| c.len_utf8()).sum::<usize>();
+    let mut matches = Vec::new();
+    let mut pos = 0;
+    while pos < chars.len() {
+        match match_at(automaton, &chars, pos) {
+            // A zero-length head still consumes nothing, so step past it.
+            Some(len) if len > 0 => {
+                matches.push(MatchSpan {
+                    start: pos,
+                    end: pos + len,
+                    byte_start: byte_at(pos),
+                    byte_end: byte_at(pos + len),
+                    text: chars[pos..pos + len].iter().collect(),
+                });
+                pos += len;
+            }
+            Some(_) => {
+                matches.push(MatchSpan { start: pos, end: pos, byte_start: byte_at(pos), byte_end: byte_at(pos), text: String::new() });
+                pos += 1;
+            }
+            None => pos += 1,
+        }
+    }
+    SampleResult { input: input.to_string(), full_match, prefix_length, matches, trace }
+}
+
+pub fn test_pattern(src: &str, definitions: &BTreeMap<String, String>, samples: &[String]) -> Result<RegexTestReport, String> {
+    let parsed = parse_flex_pattern(src, definitions)?;
+    let automaton = compile_pattern(src, &parsed)?;
+    let samples = samples.iter().map(|s| test_sample(&automaton, s)).collect();
+    Ok(RegexTestReport { pattern: src.to_string(), dfa_states: automaton.dfa.states.len(), samples })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(src: &str, samples: &[&str]) -> RegexTestReport {
+        let samples: Vec<String> = samples.iter().map(|s| s.to_string()).collect();
+        test_pattern(src, &BTreeMap::new(), &samples).unwrap()
+    }
+
+    fn spans(sample: &SampleResult) -> Vec<(usize, usize, &str)> {
+        sample.matches.iter().map(|m| (m.start, m.end, m.text.as_str())).collect()
+    }
+
+    #[test]
+    fn numbers_are_found_left_to_right() {
+        let report = run("[0-9]+", &["12 + 345", "42"]);
+        assert_eq!(report.pattern, "[0-9]+");
+        let sample = &report.samples[0];
+        assert!(!sample.full_match);
+        assert_eq!(sample.prefix_length, Some(2));
+        assert_eq!(spans(sample), [(0, 2, "12"), (5, 8, "345")]);
+        assert_eq!(sample.trace.consumed, 2);
+        assert!(report.samples[1].full_match);
+    }
+
+    #[test]
+    fn longest_match_takes_the_whole_keyword_prefix() {
+        let sample = &run("if|ifdef|[a-z]", &["ifdefx"]).samples[0];
+        assert_eq!(sample.prefix_length, Some(5));
+        assert_eq!(spans(sample), [(0, 5, "ifdef"), (5, 6, "x")]);
+    }
+
+    #[test]
+    fn trailing_context_is_matched_but_not_consumed() {
+        let report = run("[a-z]+/\"(\"", &["f(x) + gh(y)", "f("]);
+        assert_eq!(spans(&report.samples[0]), [(0, 1, "f"), (7, 9, "gh")]);
+        let whole = &report.samples[1];
+        assert!(whole.full_match);
+        assert_eq!(whole.prefix_length, Some(1));
+    }
+
+    #[test]
+    fn caret_only_matches_at_line_starts() {
+        let sample = &run("^#[a-z]", &["#a\n#b #c"]).samples[0];
+        assert_eq!(spans(sample), [(0, 2, "#a"), (3, 5, "#b")]);
+    }
+
+    #[test]
+    fn spans_report_bytes_as_well_as_chars() {
+        let sample = &run("é+", &["aéé!"]).samples[0];
+        let m = &sample.matches[0];
+        assert_eq!((m.start, m.end, m.byte_start, m.byte_end), (1, 3, 1, 5));
+        assert_eq!(m.text, "éé");
+    }
+
+    #[test]
+    fn bad_patterns_are_errors() {
+        let err = test_pattern("{NOPE}", &BTreeMap::new(), &[]).unwrap_err();
+        assert!(err.starts_with("Undefined definition '{NOPE}'"), "{}", err);
+    }
+}
@@ -0,0 +1,647 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+
+use crate::grammar::{Grammar, Production};
+use crate::ll1::find_left_recursion;
+use crate::ASTNode;
+
+// --- GRAMMAR TRANSFORMATIONS ---
+// Each transformation rewrites a working copy of the grammar in which every
+// rule remembers which productions of the *input* grammar it came from, so
+// the final changelog maps new rules back to the originals even when several
+// transformations are chained.
+
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Transformation {
+    EliminateLeftRecursion,
+    LeftFactor,
+    RemoveEpsilon,
+    RemoveUnit,
+    RemoveUseless,
+    ChomskyNormalForm,
+    GreibachNormalForm,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct ChangeEntry {
+    pub rule: String,
+    /// Original productions this rule was derived from.
+    pub from: Vec<String>,
+    pub notes: Vec<String>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct Transformed {
+    pub grammar: Grammar,
+    pub ast: ASTNode,
+    pub source: String,
+    pub changelog: Vec<ChangeEntry>,
+    /// Original productions with no counterpart in the result.
+    pub removed: Vec<String>,
+}
+
+/// Nullable occurrences in one rule beyond which epsilon removal refuses to
+/// expand (the rule would be copied 2^n times).
+const MAX_NULLABLE_OCCURRENCES: usize = 12;
+
+/// Rule count beyond which GNF substitution gives up (it can grow exponentially).
+const MAX_GNF_RULES: usize = 5_000;
+
+#[derive(Clone, Debug)]
+struct Rule {
+    lhs: String,
+    rhs: Vec<String>,
+    action: Option<String>,
+    origins: BTreeSet<usize>,
+    notes: Vec<String>,
+}
+
+impl Rule {
+    fn derived(lhs: &str, rhs: Vec<String>, origins: BTreeSet<usize>, notes: &[String], note: &str) -> Rule {
+        let mut notes = notes.to_vec();
+        if !notes.iter().any(|n| n == note) {
+            notes.push(note.to_string());
+        }
+        Rule { lhs: lhs.to_string(), rhs, action: None, origins, notes }
+    }
+}
+
+struct Work {
+    start: String,
+    terminals: Vec<String>,
+    nonterminals: Vec<String>,
+    rules: Vec<Rule>,
+}
+
+impl Work {
+    fn from_grammar(grammar: &Grammar) -> Work {
+        Work {
+            start: grammar.start.clone(),
+            terminals: grammar.terminals.clone(),
+            nonterminals: grammar.nonterminals.clone(),
+            rules: grammar
+                .productions
+                .iter()
+                .enumerate()
+                .map(|(i, p)| Rule {
+                    lhs: p.lhs.clone(),
+                    rhs: p.rhs.clone(),
+                    action: p.action.clone(),
+                    origins: BTreeSet::from([i]),
+                    notes: Vec::new(),
+                })
+                .collect(),
+        }
+    }
+
+    fn to_grammar(&self) -> Grammar {
+        Grammar {
+            start: self.start.clone(),
+            terminals: self.terminals.clone(),
+            nonterminals: self.nonterminals.clone(),
+            productions: self
+                .rules
+                .iter()
+                .map(|r| Production { lhs: r.lhs.clone(), rhs: r.rhs.clone(), action: r.action.clone() })
+                .collect(),
+        }
+    }
+
+    fn is_nonterminal(&self, symbol: &str) -> bool {
+        self.nonterminals.iter().any(|n| n == symbol)
+    }
+
+    fn rules_for(&self, lhs: &str) -> Vec<Rule> {
+        self.rules.iter().filter(|r| r.lhs == lhs).cloned().collect()
+    }
+
+    /// A new nonterminal name `base_suffix` (numbered if already taken),
+    /// inserted right after `base` so the printed grammar keeps related rules together.
+    fn fresh_nonterminal(&mut self, base: &str, suffix: &str) -> String {
+        let mut name = format!("{}_{}", base, suffix);
+        let mut n = 2;
+        while self.is_nonterminal(&name) || self.terminals.contains(&name) {
+            name = format!("{}_{}{}", base, suffix, n);
+            n += 1;
+        }
+        let at = self.nonterminals.iter().position(|x| x == base).map_or(self.nonterminals.len(), |i| i + 1);
+        self.nonterminals.insert(at, name.clone());
+        name
+    }
+}
+
+/// Adds `rule`, merging it into an existing rule with the same shape.
+fn push_rule(rules: &mut Vec<Rule>, rule: Rule) {
+    if let Some(existing) = rules.iter_mut().find(|r| r.lhs == rule.lhs && r.rhs == rule.rhs) {
+        existing.origins.extend(rule.origins);
+        for n in rule.notes {
+            if !existing.notes.contains(&n) {
+                existing.notes.push(n);
+            }
+        }
+        if existing.action.is_none() {
+            existing.action = rule.action;
+        }
+    } else {
+        rules.push(rule);
+    }
+}
+
+pub fn transform(grammar: &Grammar, steps: &[Transformation]) -> Result<Transformed, String> {
+    let mut work = Work::from_grammar(grammar);
+    for step in steps {
+        match step {
+            Transformation::EliminateLeftRecursion => eliminate_left_recursion(&mut work)?,
+            Transformation::LeftFactor => left_factor(&mut work),
+            Transformation::RemoveEpsilon => remove_epsilon(&mut work)?,
+            Transformation::RemoveUnit => remove_unit(&mut work),
+            Transformation::RemoveUseless => remove_useless(&mut work)?,
+            Transformation::ChomskyNormalForm => to_cnf(&mut work)?,
+            Transformation::GreibachNormalForm => to_gnf(&mut work)?,
+        }
+    }
+
+    let changelog = work
+        .rules
+        .iter()
+        .map(|r| ChangeEntry {
+            rule: Production { lhs: r.lhs.clone(), rhs: r.rhs.clone(), action: None }.to_string(),
+            from: r.origins.iter().map(|&i| grammar.productions[i].to_string()).collect(),
+            notes: r.notes.clone(),
+        })
+        .collect();
+    let used: BTreeSet<usize> = work.rules.iter().flat_map(|r| r.origins.iter().copied()).collect();
+    let removed = (0..grammar.productions.len())
+        .filter(|i| !used.contains(i))
+        .map(|i| grammar.productions[i].to_string())
+        .collect();
+
+    let result = work.to_grammar();
+    Ok(Transformed { ast: result.to_ast(), source: result.to_bison_source(), grammar: result, changelog, removed })
+}
+
+fn remove_useless(work: &mut Work) -> Result<(), String> {
+    let productive = work.to_grammar().productive();
+    if !productive.contains(&work.start) {
+        return Err(format!("Start symbol '{}' does not derive any terminal string", work.start));
+    }
+    let is_productive = |s: &String, w: &Work| !w.is_nonterminal(s) || productive.contains(s);
+    let kept: Vec<Rule> = work
+        .rules
+        .iter()
+        .filter(|r| productive.contains(&r.lhs) && r.rhs.iter().all(|s| is_productive(s, work)))
+        .cloned()
+        .collect();
+    work.rules = kept;
+    work.nonterminals.retain(|n| productive.contains(n));
+
+    let reachable = work.to_grammar().reachable();
+    work.rules.retain(|r| reachable.contains(&r.lhs));
+    work.nonterminals.retain(|n| reachable.contains(n));
+    let used: BTreeSet<&String> = work.rules.iter().flat_map(|r| r.rhs.iter()).collect();
+    let terminals: Vec<String> = work.terminals.iter().filter(|t| used.contains(t)).cloned().collect();
+    work.terminals = terminals;
+    Ok(())
+}
+
+fn remove_epsilon(work: &mut Work) -> Result<(), String> {
+    let nullable = work.to_grammar().nullable();
+    let mut out: Vec<Rule> = Vec::new();
+    for r in &work.rules {
+        let positions: Vec<usize> = (0..r.rhs.len()).filter(|&i| nullable.contains(&r.rhs[i])).collect();
+        if positions.len() > MAX_NULLABLE_OCCURRENCES {
+            return Err(format!("Rule '{}' has too many nullable symbols to expand", r.lhs));
+        }
+        for mask in 0u32..(1 << positions.len()) {
+            let rhs: Vec<String> = r
+                .rhs
+                .iter()
+                .enumerate()
+                .filter(|(i, _)| positions.iter().position(|p| p == i).is_none_or(|bit| mask & (1 << bit) == 0))
+                .map(|(_, s)| s.clone())
+                .collect();
+            if rhs.is_empty() {
+                continue;
+            }
+            if mask == 0 {
+                push_rule(&mut out, r.clone());
+            } else {
+                push_rule(&mut out, Rule::derived(&r.lhs, rhs, r.origins.clone(), &r.notes, "nullable symbols omitted"));
+            }
+        }
+    }
+
+    if nullable.contains(&work.start) {
+        let old_start = work.start.clone();
+        let new_start = work.fresh_nonterminal(&old_start, "start");
+        // The new start symbol goes first: parse_bison treats the first rule as the start.
+        work.nonterminals.retain(|n| *n != new_start);
+        work.nonterminals.insert(0, new_start.clone());
+        work.start = new_start.clone();
+        let note = "new start symbol keeps the empty string";
+        out.insert(0, Rule::derived(&new_start, Vec::new(), BTreeSet::new(), &[], note));
+        out.insert(0, Rule::derived(&new_start, vec![old_start], BTreeSet::new(), &[], note));
+    }
+    work.rules = out;
+    Ok(())
+}
+
+fn remove_unit(work: &mut Work) {
+    let is_unit = |r: &Rule, w: &Work| r.rhs.len() == 1 && w.is_nonterminal(&r.rhs[0]);
+    let mut out: Vec<Rule> = Vec::new();
+    for a in &work.nonterminals {
+        // Every B with A =>* B through unit rules, with the origins of the chain.
+        let mut closure: BTreeMap<String, BTreeSet<usize>> = BTreeMap::new();
+        let mut order = vec![a.clone()];
+        closure.insert(a.clone(), BTreeSet::new());
+        let mut queue = VecDeque::from([a.clone()]);
+        while let Some(b) = queue.pop_front() {
+            for r in work.rules.iter().filter(|r| r.lhs == b && is_unit(r, work)) {
+                let c = &r.rhs[0];
+                if !closure.contains_key(c) {
+                    let mut chain = closure[&b].clone();
+                    chain.extend(r.origins.iter().copied());
+                    closure.insert(c.clone(), chain);
+                    order.push(c.clone());
+                    queue.push_back(c.clone());
+                }
+            }
+        }
+        for b in &order {
+            for r in work.rules.iter().filter(|r| r.lhs == *b && !is_unit(r, work)) {
+                if b == a {
+                    push_rule(&mut out, r.clone());
+                } else {
+                    let mut origins = r.origins.clone();
+                    origins.extend(closure[b].iter().copied());
+                    push_rule(&mut out, Rule::derived(a, r.rhs.clone(), origins, &r.notes, "unit productions collapsed"));
+                }
+            }
+        }
+    }
+    work.rules = out;
+}
+
+/// Whether `to` can appear as the leftmost symbol of a derivation from `from`.
+fn left_reaches(work: &Work, from: &str, to: &str) -> bool {
+    let mut seen = BTreeSet::new();
+    let mut stack = vec![from.to_string()];
+    while let Some(cur) = stack.pop() {
+        if cur == to {
+            return true;
+        }
+        if !seen.insert(cur.clone()) {
+            continue;
+        }
+        for r in work.rules.iter().filter(|r| r.lhs == cur) {
+            if let Some(first) = r.rhs.first() {
+                if work.is_nonterminal(first) {
+                    stack.push(first.clone());
+                }
+            }
+        }
+    }
+    false
+}
+
+fn eliminate_left_recursion(work: &mut Work) -> Result<(), String> {
+    // Paull's algorithm, substituting A_i -> A_j γ only when A_j can lead back
+    // to A_i, so grammars without indirect recursion are left readable.
+    let order = work.nonterminals.clone();
+    for i in 0..order.len() {
+        let ai = &order[i];
+        for aj in &order[..i] {
+            if !left_reaches(work, aj, ai) {
+                continue;
+            }
+            let aj_rules = work.rules_for(aj);
+            let mut out = Vec::new();
+            for r in std::mem::take(&mut work.rules) {
+                if r.lhs == *ai && r.rhs.first() == Some(aj) {
+                    for d in &aj_rules {
+                        let mut rhs = d.rhs.clone();
+                        rhs.extend(r.rhs[1..].iter().cloned());
+                        let mut origins = r.origins.clone();
+                        origins.extend(d.origins.iter().copied());
+                        push_rule(&mut out, Rule::derived(ai, rhs, origins, &r.notes, "substituted to expose left recursion"));
+                    }
+                } else {
+                    out.push(r);
+                }
+            }
+            work.rules = out;
+        }
+        eliminate_immediate(work, ai);
+    }
+
+    let grammar = work.to_grammar();
+    if let Some(rec) = find_left_recursion(&grammar, &grammar.symbol_sets()).first() {
+        return Err(format!(
+            "Left recursion {} remains through nullable symbols; remove epsilon productions first",
+            rec.cycle.join(" -> ")
+        ));
+    }
+    Ok(())
+}
+
+fn eliminate_immediate(work: &mut Work, a: &str) {
+    let (recursive, others): (Vec<Rule>, Vec<Rule>) = work
+        .rules
+        .iter()
+        .filter(|r| r.lhs == a)
+        .cloned()
+        .partition(|r| r.rhs.first().map(|s| s.as_str()) == Some(a));
+    if recursive.is_empty() {
+        return;
+    }
+
+    let tail = work.fresh_nonterminal(a, "tail");
+    let note = "left recursion removed";
+    let mut out: Vec<Rule> = Vec::new();
+    let mut placed = false;
+    for r in std::mem::take(&mut work.rules) {
+        if r.lhs != a {
+            out.push(r);
+            continue;
+        }
+        if placed {
+            continue;
+        }
+        placed = true;
+        for beta in &others {
+            let mut rhs = beta.rhs.clone();
+            rhs.push(tail.clone());
+            push_rule(&mut out, Rule::derived(a, rhs, beta.origins.clone(), &beta.notes, note));
+        }
+        for alpha in &recursive {
+            // A -> A contributes nothing once the recursion is gone.
+            if alpha.rhs.len() > 1 {
+                let mut rhs = alpha.rhs[1..].to_vec();
+                rhs.push(tail.clone());
+                push_rule(&mut out, Rule::derived(&tail, rhs, alpha.origins.clone(), &alpha.notes, note));
+            }
+        }
+        let origins = recursive.iter().flat_map(|r| r.origins.iter().copied()).collect();
+        push_rule(&mut out, Rule::derived(&tail, Vec::new(), origins, &[], note));
+    }
+    work.rules = out;
+}
+
+fn left_factor(work: &mut Work) {
+    loop {
+        let mut changed = false;
+        for a in work.nonterminals.clone() {
+            let alts = work.rules_for(&a);
+            let group: Vec<Rule> = alts
+                .iter()
+                .find_map(|r| {
+                    let first = r.rhs.first()?;
+                    let g: Vec<Rule> = alts.iter().filter(|o| o.rhs.first() == Some(first)).cloned().collect();
+                    (g.len() > 1).then_some(g)
+                })
+                .unwrap_or_default();
+            if group.is_empty() {
+                continue;
+            }
+
+            let mut prefix_len = group[0].rhs.len();
+            for r in &group[1..] {
+                prefix_len = prefix_len.min(r.rhs.iter().zip(&group[0].rhs).take_while(|(x, y)| x == y).count());
+            }
+            let prefix = group[0].rhs[..prefix_len].to_vec();
+            let name = work.fresh_nonterminal(&a, "factored");
+            let note = "left factored";
+
+            let mut head = prefix.clone();
+            head.push(name.clone());
+            let origins: BTreeSet<usize> = group.iter().flat_map(|r| r.origins.iter().copied()).collect();
+            let mut out: Vec<Rule> = Vec::new();
+            let mut placed = false;
+            for r in std::mem::take(&mut work.rules) {
+                let in_group = r.lhs == a && r.rhs.len() >= prefix_len && r.rhs[..prefix_len] == prefix[..];
+                if !in_group {
+                    out.push(r);
+                } else if !placed {
+                    placed = true;
+                    push_rule(&mut out, Rule::derived(&a, head.clone(), origins.clone(), &[], note));
+                }
+            }
+            for r in &group {
+                push_rule(&mut out, Rule::derived(&name, r.rhs[prefix_len..].to_vec(), r.origins.clone(), &r.notes, note));
+            }
+            work.rules = out;
+            changed = true;
+            break;
+        }
+        if !changed {
+            break;
+        }
+    }
+}
+
+/// Name suffix for the nonterminal standing in for terminal `t` (`NUM`, `'+'` -> `x2b`).
+fn terminal_suffix(t: &str) -> String {
+    let inner = t.trim_matches(|c| c == '\'' || c == '"');
+    let mut out = String::new();
+    for c in inner.chars() {
+        if c.is_ascii_alphanumeric() || c == '_' {
+            out.push(c);
+        } else {
+            out.push_str(&format!("x{:02x}", c as u32));
+        }
+    }
+    out
+}
+
+/// Replaces terminals at rhs positions `>= from` (in rules longer than
+/// `min_len`) with nonterminals `T_x -> x`.
+fn lift_terminals(work: &mut Work, from: usize, min_len: usize) {
+    let mut lifted: BTreeMap<String, String> = BTreeMap::new();
+    let mut new_rules = Vec::new();
+    for i in 0..work.rules.len() {
+        if work.rules[i].rhs.len() < min_len {
+            continue;
+        }
+        for pos in from..work.rules[i].rhs.len() {
+            let sym = work.rules[i].rhs[pos].clone();
+            if work.is_nonterminal(&sym) {
+                continue;
+            }
+            let name = match lifted.get(&sym) {
+                Some(n) => n.clone(),
+                None => {
+                    let n = work.fresh_nonterminal("T", &terminal_suffix(&sym));
+                    new_rules.push(Rule::derived(&n, vec![sym.clone()], BTreeSet::new(), &[], "terminal lifted into its own rule"));
+                    lifted.insert(sym.clone(), n.clone());
+                    n
+                }
+            };
+            let r = &mut work.rules[i];
+            r.rhs[pos] = name;
+            r.action = None;
+            if !r.notes.iter().any(|n| n == "terminals lifted") {
+                r.notes.push("terminals lifted".to_string());
+            }
+        }
+    }
+    work.rules.extend(new_rules);
+}
+
+fn to_cnf(work: &mut Work) -> Result<(), String> {
+    // DEL and UNIT first: afterwards every rule is `A -> a`, `A -> X Y ...`
+    // or the start symbol's `S -> ε`, and the start never appears on a rhs.
+    remove_epsilon(work)?;
+    remove_unit(work);
+    remove_useless(work)?;
+    lift_terminals(work, 0, 2);
+
+    // BIN: A -> X1 X2 ... Xn becomes A -> X1 A_bin, A_bin -> X2 ... Xn, ...
+    let mut out = Vec::new();
+    for r in std::mem::take(&mut work.rules) {
+        if r.rhs.len() <= 2 {
+            out.push(r);
+            continue;
+        }
+        let mut lhs = r.lhs.clone();
+        let mut rest = r.rhs.clone();
+        while rest.len() > 2 {
+            let next = work.fresh_nonterminal(&r.lhs, "bin");
+            let head = rest.remove(0);
+            out.push(Rule::derived(&lhs, vec![head, next.clone()], r.origins.clone(), &r.notes, "split into binary rules"));
+            lhs = next;
+        }
+        out.push(Rule::derived(&lhs, rest, r.origins.clone(), &r.notes, "split into binary rules"));
+    }
+    work.rules = out;
+    Ok(())
+}
+
+fn to_gnf(work: &mut Work) -> Result<(), String> {
+    to_cnf(work)?;
+
+    // Set the start symbol's ε-rule aside so later passes see an ε-free grammar.
+    let start = work.start.clone();
+    let empty_start = work.rules.iter().position(|r| r.lhs == start && r.rhs.is_empty()).map(|i| work.rules.remove(i));
+
+    eliminate_left_recursion(work)?;
+    remove_epsilon(work)?;
+
+    // With no left recursion the left-corner graph is acyclic, so repeatedly
+    // expanding a leading nonterminal terminates with a terminal in front.
+    while let Some(i) = work.rules.iter().position(|r| r.rhs.first().is_some_and(|s| work.is_nonterminal(s))) {
+        let r = work.rules.remove(i);
+        let lead = &r.rhs[0];
+        let expansions = work.rules_for(lead);
+        let mut out = std::mem::take(&mut work.rules);
+        for d in expansions {
+            let mut rhs = d.rhs.clone();
+            rhs.extend(r.rhs[1..].iter().cloned());
+            let mut origins = r.origins.clone();
+            origins.extend(d.origins.iter().copied());
+            push_rule(&mut out, Rule::derived(&r.lhs, rhs, origins, &r.notes, "leading nonterminal expanded"));
+        }
+        work.rules = out;
+        if work.rules.len() > MAX_GNF_RULES {
+            return Err("Greibach normal form grows too large for this grammar".to_string());
+        }
+    }
+    lift_terminals(work, 1, 2);
+
+    if let Some(r) = empty_start {
+        work.rules.insert(0, r);
+    }
+    remove_useless(work)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ll1::analyze_ll1;
+
+    fn grammar(rules: &str) -> Grammar {
+        Grammar::from_ast(&crate::parse_bison(&format!("%token NUM ID IF THEN ELSE OTHER\n%%\n{}", rules))).unwrap()
+    }
+
+    fn rules(grammar: &Grammar) -> Vec<String> {
+        grammar.productions.iter().map(|p| p.to_string()).collect()
+    }
+
+    const EXPRESSIONS: &str = "e: e '+' t | t ;\nt: t '*' f | f ;\nf: '(' e ')' | NUM ;\n";
+
+    #[test]
+    fn left_recursion_elimination_makes_the_expression_grammar_ll1() {
+        let out = transform(&grammar(EXPRESSIONS), &[Transformation::EliminateLeftRecursion]).unwrap();
+        assert_eq!(
+            rules(&out.grammar),
+            ["e -> t e_tail", "e_tail -> '+' t e_tail", "e_tail -> ε", "t -> f t_tail", "t_tail -> '*' f t_tail", "t_tail -> ε", "f -> '(' e ')'", "f -> NUM"]
+        );
+        assert!(analyze_ll1(&out.grammar).is_ll1);
+        let tail = out.changelog.iter().find(|c| c.rule == "e_tail -> '+' t e_tail").unwrap();
+        assert_eq!(tail.from, ["e -> e '+' t"]);
+        assert_eq!(tail.notes, ["left recursion removed"]);
+        assert!(out.removed.is_empty());
+    }
+
+    #[test]
+    fn left_factoring_the_dangling_else_leaves_its_ambiguity() {
+        let g = grammar("stmt: IF ID THEN stmt | IF ID THEN stmt ELSE stmt | OTHER ;\n");
+        let out = transform(&g, &[Transformation::LeftFactor]).unwrap();
+        assert_eq!(rules(&out.grammar), ["stmt -> IF ID THEN stmt stmt_factored", "stmt -> OTHER", "stmt_factored -> ε", "stmt_factored -> ELSE stmt"]);
+        assert_eq!(out.changelog[0].from, ["stmt -> IF ID THEN stmt", "stmt -> IF ID THEN stmt ELSE stmt"]);
+        // Factoring removes the FIRST/FIRST conflict, but ELSE still follows stmt_factored.
+        let report = analyze_ll1(&out.grammar);
+        assert_eq!(report.conflicts.len(), 1);
+        assert_eq!(report.conflicts[0].nonterminal, "stmt_factored");
+    }
+
+    const NULLABLE: &str = "s: a b | 'c' ;\na: 'x' | ;\nb: 'y' | ;\nu: 'z' ;\nloop: loop 'q' ;\n";
+
+    #[test]
+    fn epsilon_removal_keeps_the_empty_sentence_on_a_new_start() {
+        let out = transform(&grammar(NULLABLE), &[Transformation::RemoveEpsilon]).unwrap();
+        assert_eq!(out.grammar.start, "s_start");
+        assert_eq!(&rules(&out.grammar)[..6], ["s_start -> s", "s_start -> ε", "s -> a b", "s -> b", "s -> a", "s -> 'c'"]);
+        assert!(out.grammar.productions.iter().all(|p| !p.rhs.is_empty() || p.lhs == "s_start"));
+        assert_eq!(out.removed, ["a -> ε", "b -> ε"]);
+    }
+
+    #[test]
+    fn useless_symbols_are_dropped() {
+        let out = transform(&grammar(NULLABLE), &[Transformation::RemoveUseless]).unwrap();
+        assert_eq!(out.removed, ["u -> 'z'", "loop -> loop 'q'"]);
+        assert!(!out.grammar.nonterminals.iter().any(|n| n == "u" || n == "loop"));
+
+        let err = transform(&grammar("s: s 'x' ;\n"), &[Transformation::RemoveUseless]).unwrap_err();
+        assert_eq!(err, "Start symbol 's' does not derive any terminal string");
+    }
+
+    #[test]
+    fn unit_removal_collapses_chains() {
+        let out = transform(&grammar(EXPRESSIONS), &[Transformation::RemoveUnit]).unwrap();
+        let g = &out.grammar;
+        assert!(g.productions.iter().all(|p| !(p.rhs.len() == 1 && g.nonterminals.contains(&p.rhs[0]))));
+        let direct = out.changelog.iter().find(|c| c.rule == "e -> NUM").unwrap();
+        assert_eq!(direct.from, ["e -> t", "t -> f", "f -> NUM"]);
+        assert_eq!(out.removed, Vec::<String>::new());
+    }
+
+    #[test]
+    fn normal_forms_have_their_shapes() {
+        let cnf = transform(&grammar(EXPRESSIONS), &[Transformation::ChomskyNormalForm]).unwrap().grammar;
+        for p in &cnf.productions {
+            let ok = match p.rhs.as_slice() {
+                [t] => cnf.is_terminal(t),
+                [a, b] => !cnf.is_terminal(a) && !cnf.is_terminal(b),
+                _ => false,
+            };
+            assert!(ok, "not in CNF: {}", p);
+        }
+
+        let gnf = transform(&grammar(EXPRESSIONS), &[Transformation::GreibachNormalForm]).unwrap().grammar;
+        for p in &gnf.productions {
+            assert!(p.rhs.first().is_some_and(|s| gnf.is_terminal(s)), "not in GNF: {}", p);
+            assert!(p.rhs[1..].iter().all(|s| !gnf.is_terminal(s)), "not in GNF: {}", p);
+        }
+    }
+}
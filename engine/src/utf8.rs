@@ -0,0 +1,126 @@
+use crate::automaton::{minimize, subset_construction, Dfa, LexerAutomaton, Nfa, NfaState, MAX_NFA_STATES};
+use crate::regex::Regex;
+
+// --- UTF-8 BYTE AUTOMATA ---
+// The generated C scanner reads bytes, so the char-level DFA is re-encoded:
+// every char-range transition becomes the UTF-8 byte-range sequences that
+// spell it, then the result is determinized and minimized again. Byte
+// automata reuse `Dfa`, with "characters" '\0'..='\u{ff}' standing for bytes.
+
+/// Byte-range sequences whose concatenations are exactly the UTF-8
+/// encodings of the scalar values in `lo..=hi`.
+pub fn utf8_sequences(lo: char, hi: char) -> Vec<Vec<(u8, u8)>> {
+    let mut out = Vec::new();
+    let mut stack = vec![(lo as u32, hi as u32)];
+    'next: while let Some((lo, hi)) = stack.pop() {
+        if lo > hi {
+            continue;
+        }
+        // Surrogates have no encoding.
+        if lo < 0xD800 && hi > 0xDFFF {
+            stack.push((0xE000, hi));
+            stack.push((lo, 0xD7FF));
+            continue;
+        }
+        // Same encoded length at both ends.
+        for boundary in [0x7F, 0x7FF, 0xFFFF] {
+            if lo <= boundary && hi > boundary {
+                stack.push((boundary + 1, hi));
+                stack.push((lo, boundary));
+                continue 'next;
+            }
+        }
+        if hi <= 0x7F {
+            out.push(vec![(lo as u8, hi as u8)]);
+            continue;
+        }
+        // Split until every continuation byte spans its full 0x80..=0xBF
+        // range wherever a leading byte varies.
+        let len = encoded_len(lo);
+        for i in 1..len {
+            let mask = (1u32 << (6 * i)) - 1;
+            if lo & !mask != hi & !mask {
+                if lo & mask != 0 {
+                    stack.push(((lo | mask) + 1, hi));
+                    stack.push((lo, lo | mask));
+                    continue 'next;
+                }
+                if hi & mask != mask {
+                    stack.push((hi & !mask, hi));
+                    stack.push((lo, (hi & !mask) - 1));
+                    continue 'next;
+                }
+            }
+        }
+        let (a, b) = (encode(lo), encode(hi));
+        out.push(a.iter().zip(b.iter()).map(|(&x, &y)| (x, y)).collect());
+    }
+    out
+}
+
+fn encoded_len(c: u32) -> usize {
+    match c {
+        0..=0x7F => 1,
+        0x80..=0x7FF => 2,
+        0x800..=0xFFFF => 3,
+        _ => 4,
+    }
+}
+
+fn encode(c: u32) -> Vec<u8> {
+    let mut buf = [0u8; 4];
+    // Split points avoid the surrogate gap, so every endpoint is a char.
+    char::from_u32(c).unwrap().encode_utf8(&mut buf).as_bytes().to_vec()
+}
+
+/// Length in bytes of every UTF-8 string `re` matches, when they all agree.
+pub fn fixed_byte_length(re: &Regex) -> Option<usize> {
+    match re {
+        Regex::Empty => Some(0),
+        Regex::Literal { c } => Some(c.len_utf8()),
+        Regex::Class { ranges } => {
+            let (lo, hi) = (ranges.first()?.0, ranges.last()?.1);
+            (lo.len_utf8() == hi.len_utf8()).then_some(lo.len_utf8())
+        }
+        Regex::Concat { items } => items.iter().map(fixed_byte_length).sum(),
+        Regex::Alt { items } => {
+            let first = fixed_byte_length(items.first()?)?;
+            items.iter().all(|item| fixed_byte_length(item) == Some(first)).then_some(first)
+        }
+        Regex::Repeat { inner, min, max } => match fixed_byte_length(inner)? {
+            0 => Some(0),
+            len if *max == Some(*min) => Some(len * *min as usize),
+            _ => None,
+        },
+    }
+}
+
+/// Byte-level version of the scanner's minimized DFA.
+pub fn byte_dfa(automaton: &LexerAutomaton) -> Result<Dfa, String> {
+    let dfa = &automaton.dfa;
+    let mut states: Vec<NfaState> =
+        dfa.states.iter().map(|st| NfaState { epsilon: Vec::new(), edges: Vec::new(), accept: st.accept }).collect();
+    for (from, st) in dfa.states.iter().enumerate() {
+        for (&class, &to) in &st.transitions {
+            let (lo, hi) = dfa.classes[class];
+            for seq in utf8_sequences(lo, hi) {
+                let mut cur = from;
+                for (i, &(blo, bhi)) in seq.iter().enumerate() {
+                    let next = if i + 1 == seq.len() {
+                        to
+                    } else {
+                        if states.len() >= MAX_NFA_STATES {
+                            return Err("Scanner is too large to encode as UTF-8 bytes".to_string());
+                        }
+                        states.push(NfaState::default());
+                        states.len() - 1
+                    };
+                    states[cur].edges.push((vec![(blo as char, bhi as char)], next));
+                    cur = next;
+                }
+            }
+        }
+    }
+    let nfa = Nfa { states, starts: dfa.starts.clone() };
+    Ok(minimize(&subset_construction(&nfa)?))
+}
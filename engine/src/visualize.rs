@@ -0,0 +1,295 @@
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+use crate::automaton::{Dfa, LexerAutomaton, StartStates};
+use crate::regex::normalize_ranges;
+
+// --- AUTOMATON EXPORT ---
+// Turns the compiled scanner automata into a node/edge graph the UI can
+// lay out, plus the same graph in Graphviz DOT.
+
+#[derive(Serialize, Clone, Debug)]
+pub struct GraphNode {
+    pub id: usize,
+    /// Rule index accepted in this state.
+    pub accept_rule: Option<usize>,
+    pub accept_pattern: Option<String>,
+    /// Start conditions entering here; `^`-entries are suffixed with " (bol)".
+    pub entry_for: Vec<String>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct GraphEdge {
+    pub from: usize,
+    pub to: usize,
+    pub label: String,
+    /// Empty for NFA epsilon edges.
+    pub ranges: Vec<(char, char)>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct DfaTrace {
+    /// States visited, starting with the entry state.
+    pub states: Vec<usize>,
+    /// Characters consumed before the automaton got stuck (or input ended).
+    pub consumed: usize,
+    /// Longest accepted prefix: `(length, rule)`.
+    pub last_accept: Option<(usize, usize)>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct AutomatonGraph {
+    pub kind: String,
+    pub nodes: Vec<GraphNode>,
+    pub edges: Vec<GraphEdge>,
+    pub entries: Vec<StartStates>,
+    pub trace: Option<DfaTrace>,
+    pub dot: String,
+}
+
+fn escape_char(c: char) -> String {
+    match c {
+        '\n' => "\\n".to_string(),
+        '\t' => "\\t".to_string(),
+        '\r' => "\\r".to_string(),
+        ' ' => "' '".to_string(),
+        '\\' | '-' | ']' | '^' | '[' => format!("\\{}", c),
+        c if (c as u32) < 0x20 || (0x7f..=0xff).contains(&(c as u32)) => format!("\\x{:02x}", c as u32),
+        c if (c as u32) > 0xff => format!("\\u{{{:x}}}", c as u32),
+        c => c.to_string(),
+    }
+}
+
+fn ranges_body(ranges: &[(char, char)]) -> String {
+    let mut out = String::new();
+    for &(lo, hi) in ranges {
+        out.push_str(&escape_char(lo));
+        if hi > lo {
+            if hi as u32 > lo as u32 + 1 {
+                out.push('-');
+            }
+            out.push_str(&escape_char(hi));
+        }
+    }
+    out
+}
+
+/// Compact label for a set of char ranges, e.g. `a`, `[0-9a-f]`, `[^\n]`.
+pub fn ranges_label(ranges: &[(char, char)]) -> String {
+    let ranges = normalize_ranges(ranges.to_vec());
+    if ranges.len() == 1 && ranges[0].0 == ranges[0].1 {
+        return escape_char(ranges[0].0);
+    }
+    let complement = crate::regex::complement_ranges(&ranges);
+    let covered: u64 = ranges.iter().map(|&(lo, hi)| hi as u64 - lo as u64 + 1).sum();
+    if covered > 0x10000 && complement.is_empty() {
+        return "any".to_string();
+    }
+    if covered > 0x10000 {
+        return format!("[^{}]", ranges_body(&complement));
+    }
+    format!("[{}]", ranges_body(&ranges))
+}
+
+fn dot_escape(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+fn entry_labels(entries: &[StartStates]) -> BTreeMap<usize, Vec<String>> {
+    let mut out: BTreeMap<usize, Vec<String>> = BTreeMap::new();
+    for e in entries {
+        out.entry(e.normal).or_default().push(e.condition.clone());
+        if e.bol != e.normal {
+            out.entry(e.bol).or_default().push(format!("{} (bol)", e.condition));
+        }
+    }
+    out
+}
+
+fn render_dot(kind: &str, nodes: &[GraphNode], edges: &[GraphEdge], trace: Option<&DfaTrace>) -> String {
+    let on_path = |s: usize| trace.is_some_and(|t| t.states.contains(&s));
+    let path_edge = |from: usize, to: usize| trace.is_some_and(|t| t.states.windows(2).any(|w| w[0] == from && w[1] == to));
+
+    let mut dot = format!("digraph {} {{\n    rankdir=LR;\n    node [shape=circle];\n", kind);
+    for n in nodes {
+        let shape = if n.accept_rule.is_some() { "doublecircle" } else { "circle" };
+        let label = match (&n.accept_rule, &n.accept_pattern) {
+            (Some(r), Some(p)) => format!("{}\\nrule {}: {}", n.id, r, dot_escape(p)),
+            _ => n.id.to_string(),
+        };
+        let color = if on_path(n.id) { ", color=red, penwidth=2" } else { "" };
+        dot.push_str(&format!("    s{} [shape={}, label=\"{}\"{}];\n", n.id, shape, label, color));
+        for (i, sc) in n.entry_for.iter().enumerate() {
+            let anchor = format!("start_{}_{}", n.id, i);
+            dot.push_str(&format!("    {} [shape=point];\n", anchor));
+            dot.push_str(&format!("    {} -> s{} [label=\"{}\"];\n", anchor, n.id, dot_escape(sc)));
+        }
+    }
+    for e in edges {
+        let color = if path_edge(e.from, e.to) { ", color=red, penwidth=2" } else { "" };
+        dot.push_str(&format!("    s{} -> s{} [label=\"{}\"{}];\n", e.from, e.to, dot_escape(&e.label), color));
+    }
+    dot.push_str("}\n");
+    dot
+}
+
+/// Runs `input` through the DFA from `start`, as one longest-match step.
+pub fn trace_dfa(dfa: &Dfa, start: usize, input: &str) -> DfaTrace {
+    let mut states = vec![start];
+    let mut last_accept = dfa.states[start].accept.map(|r| (0, r));
+    let mut consumed = 0;
+    let mut state = start;
+    for c in input.chars() {
+        match dfa.next(state, c) {
+            Some(next) => {
+                state = next;
+                consumed += 1;
+                states.push(state);
+                if let Some(r) = dfa.states[state].accept {
+                    last_accept = Some((consumed, r));
+                }
+            }
+            None => break,
+        }
+    }
+    DfaTrace { states, consumed, last_accept }
+}
+
+pub fn dfa_graph(automaton: &LexerAutomaton, dfa: &Dfa, input: Option<&str>, condition: &str) -> Result<AutomatonGraph, String> {
+    let entries = entry_labels(&dfa.starts);
+    let nodes: Vec<GraphNode> = dfa
+        .states
+        .iter()
+        .enumerate()
+        .map(|(id, st)| GraphNode {
+            id,
+            accept_rule: st.accept.map(|r| automaton.rules[r].index),
+            accept_pattern: st.accept.map(|r| automaton.rules[r].pattern.clone()),
+            entry_for: entries.get(&id).cloned().unwrap_or_default(),
+        })
+        .collect();
+
+    let mut edges = Vec::new();
+    for (from, st) in dfa.states.iter().enumerate() {
+        let mut by_target: BTreeMap<usize, Vec<(char, char)>> = BTreeMap::new();
+        for (&class, &to) in &st.transitions {
+            by_target.entry(to).or_default().push(dfa.classes[class]);
+        }
+        for (to, ranges) in by_target {
+            let ranges = normalize_ranges(ranges);
+            edges.push(GraphEdge { from, to, label: ranges_label(&ranges), ranges });
+        }
+    }
+
+    let trace = match input {
+        Some(text) => {
+            // Inputs are traced from the beginning of a line.
+            let start = dfa.start(condition, true).ok_or_else(|| format!("Unknown start condition '{}'", condition))?;
+            Some(trace_dfa(dfa, start, text))
+        }
+        None => None,
+    };
+    let dot = render_dot("dfa", &nodes, &edges, trace.as_ref());
+    Ok(AutomatonGraph { kind: "dfa".to_string(), nodes, edges, entries: dfa.starts.clone(), trace, dot })
+}
+
+pub fn nfa_graph(automaton: &LexerAutomaton) -> AutomatonGraph {
+    let nfa = &automaton.nfa;
+    let entries = entry_labels(&nfa.starts);
+    let nodes: Vec<GraphNode> = nfa
+        .states
+        .iter()
+        .enumerate()
+        .map(|(id, st)| GraphNode {
+            id,
+            accept_rule: st.accept.map(|r| automaton.rules[r].index),
+            accept_pattern: st.accept.map(|r| automaton.rules[r].pattern.clone()),
+            entry_for: entries.get(&id).cloned().unwrap_or_default(),
+        })
+        .collect();
+    let mut edges = Vec::new();
+    for (from, st) in nfa.states.iter().enumerate() {
+        for &to in &st.epsilon {
+            edges.push(GraphEdge { from, to, label: "ε".to_string(), ranges: Vec::new() });
+        }
+        for (ranges, to) in &st.edges {
+            edges.push(GraphEdge { from, to: *to, label: ranges_label(ranges), ranges: ranges.clone() });
+        }
+    }
+    let dot = render_dot("nfa", &nodes, &edges, None);
+    AutomatonGraph { kind: "nfa".to_string(), nodes, edges, entries: nfa.starts.clone(), trace: None, dot }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::automaton::compile_flex;
+
+    fn automaton(spec: &str) -> LexerAutomaton {
+        compile_flex(&crate::parse_flex(spec)).unwrap()
+    }
+
+    const KEYWORDS: &str = "%%\nif { return IF; }\n[a-z]+ { return ID; }\n[0-9]+ { return NUM; }\n";
+
+    #[test]
+    fn range_labels_are_compact() {
+        assert_eq!(ranges_label(&[('a', 'a')]), "a");
+        assert_eq!(ranges_label(&[('a', 'f'), ('0', '9')]), "[0-9a-f]");
+        assert_eq!(ranges_label(&[('a', 'b')]), "[ab]");
+        assert_eq!(ranges_label(&[('\n', '\n')]), "\\n");
+        assert_eq!(ranges_label(&[('-', '-'), (']', ']')]), "[\\-\\]]");
+        assert_eq!(ranges_label(&[('\0', '\t'), ('\u{b}', char::MAX)]), "[^\\n]");
+        assert_eq!(ranges_label(&[('\0', char::MAX)]), "any");
+    }
+
+    #[test]
+    fn trace_follows_the_longest_match() {
+        let a = automaton(KEYWORDS);
+        let start = a.dfa.start("INITIAL", true).unwrap();
+        // "if" alone is the keyword; "ifx" keeps going as an identifier.
+        let trace = trace_dfa(&a.dfa, start, "if");
+        assert_eq!((trace.consumed, trace.last_accept), (2, Some((2, 0))));
+        let trace = trace_dfa(&a.dfa, start, "ifx9");
+        assert_eq!((trace.consumed, trace.last_accept), (3, Some((3, 1))));
+        assert_eq!(trace.states.len(), 4);
+        let trace = trace_dfa(&a.dfa, start, "+");
+        assert_eq!((trace.consumed, trace.last_accept, trace.states), (0, None, vec![start]));
+    }
+
+    #[test]
+    fn dfa_graph_marks_the_traced_path_in_dot() {
+        let a = automaton(KEYWORDS);
+        let graph = dfa_graph(&a, &a.dfa, Some("if"), "INITIAL").unwrap();
+        assert_eq!(graph.kind, "dfa");
+        assert_eq!(graph.nodes.len(), a.dfa.states.len());
+        let path = &graph.trace.as_ref().unwrap().states;
+        let accepting = &graph.nodes[*path.last().unwrap()];
+        assert_eq!((accepting.accept_rule, accepting.accept_pattern.as_deref()), (Some(0), Some("if")));
+        let start = &graph.nodes[path[0]];
+        assert_eq!(start.entry_for, ["INITIAL"]);
+
+        let i_edge = graph.edges.iter().find(|e| e.label == "i").unwrap();
+        assert_eq!((i_edge.from, i_edge.to), (path[0], path[1]));
+        assert!(graph.dot.starts_with("digraph dfa {\n"));
+        assert!(graph.dot.contains(&format!("    s{} -> s{} [label=\"i\", color=red, penwidth=2];\n", path[0], path[1])));
+        assert!(graph.dot.contains(&format!("    s{} [shape=doublecircle, label=\"{}\\nrule 0: if\", color=red, penwidth=2];\n", path[2], path[2])));
+        assert!(graph.dot.contains("[label=\"[0-9]\"];\n"));
+
+        let err = dfa_graph(&a, &a.dfa, Some("if"), "STRING").unwrap_err();
+        assert_eq!(err, "Unknown start condition 'STRING'");
+    }
+
+    #[test]
+    fn nfa_graph_has_epsilon_edges_and_bol_entries() {
+        let a = automaton("%%\na|b* { return X; }\n");
+        let graph = nfa_graph(&a);
+        assert_eq!(graph.kind, "nfa");
+        assert!(graph.trace.is_none());
+        assert!(graph.edges.iter().any(|e| e.label == "ε" && e.ranges.is_empty()));
+        assert_eq!(graph.edges.iter().filter(|e| !e.ranges.is_empty()).map(|e| e.label.as_str()).collect::<Vec<_>>(), ["a", "b"]);
+        let entries: Vec<&str> = graph.nodes.iter().flat_map(|n| n.entry_for.iter().map(String::as_str)).collect();
+        assert_eq!(entries, ["INITIAL", "INITIAL (bol)"]);
+        assert_eq!(graph.nodes.iter().filter(|n| n.accept_rule == Some(0)).count(), 1);
+        assert!(graph.dot.contains("[label=\"INITIAL (bol)\"];\n"));
+    }
+}
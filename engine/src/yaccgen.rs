@@ -0,0 +1,1525 @@
+use std::collections::BTreeMap;
+
+use crate::grammar::Grammar;
+use crate::lalr::{build_lalr, terminal_symbols, LalrTables, LrAction, ERROR_SYMBOL, UNDEFINED_SYMBOL};
+use crate::lexgen::{c_array, c_string, comment_safe, defines_function, int_type, mentions, number_output_lines, user_code, SCANNER_TYPE};
+use crate::{ASTNode, CodegenOptions};
+
+// --- Y.TAB.C GENERATION ---
+// Emits a parser shaped like bison's yacc.c skeleton. The LALR(1) tables are
+// packed the way bison packs them: each state's explicit actions and each
+// nonterminal's non-default gotos are rows of one comb vector (`yytable`,
+// indexed through `yypact`/`yypgoto` and guarded by `yycheck`), and whatever
+// a row leaves out falls back to `yydefact`/`yydefgoto`. Positive entries
+// shift, negative ones reduce by rule `-n`, `YYTABLE_NINF` is an explicit
+// `%nonassoc` error. Rules are numbered from 1 as in bison, so rule 1 is
+// `$accept: start $end`.
+
+/// Name of the generated parser, as yacc writes it.
+pub const PARSER_OUTPUT: &str = "y.tab.c";
+
+/// Spec named in `#line` when the caller gives no file name.
+pub const PARSER_SOURCE: &str = "parser.y";
+
+/// Name under which scanners include the parser's header.
+pub const HEADER_NAME: &str = "y.tab.h";
+
+/// Include guard of y.tab.h, spelled as bison spells it.
+pub const HEADER_GUARD: &str = "YY_YY_Y_TAB_H_INCLUDED";
+
+/// Comb vector shared by action and goto rows. A row with base `b` keeps
+/// column `c` in `table[b + c]` when `check[b + c] == c`. Bases are unique,
+/// so a probe can never land in another row's slot with a matching check.
+struct BisonTables {
+    bases: Vec<Option<i64>>,
+    table: Vec<i64>,
+    check: Vec<i64>,
+}
+
+/// The integer tables both skeletons drive their parsers with, named as
+/// yacc.c names them.
+pub(crate) struct PackedParser {
+    pub r1: Vec<i64>,
+    pub r2: Vec<i64>,
+    pub defact: Vec<i64>,
+    pub pact: Vec<i64>,
+    pub pgoto: Vec<i64>,
+    pub defgoto: Vec<i64>,
+    pub table: Vec<i64>,
+    pub check: Vec<i64>,
+    pub pact_ninf: i64,
+    pub table_ninf: i64,
+}
+
+impl PackedParser {
+    /// Every table but `yytranslate`, in the order yacc.c emits them.
+    pub fn arrays(&self) -> [(&'static str, &[i64]); 8] {
+        [
+            ("yyr1", &self.r1),
+            ("yyr2", &self.r2),
+            ("yydefact", &self.defact),
+            ("yypact", &self.pact),
+            ("yypgoto", &self.pgoto),
+            ("yydefgoto", &self.defgoto),
+            ("yytable", &self.table),
+            ("yycheck", &self.check),
+        ]
+    }
+}
+
+pub(crate) fn pack_parser(tables: &LalrTables) -> PackedParser {
+    let ntokens = tables.terminal_count;
+    let mut r1 = vec![0];
+    let mut r2 = vec![0];
+    for rule in &tables.rules {
+        r1.push(rule.lhs as i64);
+        r2.push(rule.rhs.len() as i64);
+    }
+    let table_ninf = -(tables.rules.len() as i64 + 1);
+    let defact: Vec<i64> = tables.default_reductions.iter().map(|d| d.map_or(0, |r| r as i64 + 1)).collect();
+
+    let mut rows: Vec<Vec<(usize, i64)>> = tables
+        .actions
+        .iter()
+        .map(|row| {
+            row.iter()
+                .map(|(&t, a)| {
+                    let v = match a {
+                        LrAction::Shift { state } => *state as i64,
+                        LrAction::Reduce { rule } => -(*rule as i64 + 1),
+                        LrAction::Error => table_ninf,
+                    };
+                    (t, v)
+                })
+                .collect()
+        })
+        .collect();
+    let mut defgoto = Vec::new();
+    for nt in ntokens..tables.symbols.len() {
+        let targets: Vec<(usize, usize)> = tables.gotos.iter().enumerate().filter_map(|(s, g)| g.get(&nt).map(|&t| (s, t))).collect();
+        let mut counts: BTreeMap<usize, usize> = BTreeMap::new();
+        for &(_, t) in &targets {
+            *counts.entry(t).or_default() += 1;
+        }
+        let default = counts.iter().max_by_key(|&(&t, &n)| (n, std::cmp::Reverse(t))).map(|(&t, _)| t);
+        defgoto.push(default.map_or(-1, |t| t as i64));
+        rows.push(targets.into_iter().filter(|&(_, t)| Some(t) != default).map(|(s, t)| (s, t as i64)).collect());
+    }
+    let packed = pack_rows(&rows);
+    let pact_ninf = packed.bases.iter().flatten().copied().min().unwrap_or(0).min(0) - 1;
+    let mut pact: Vec<i64> = packed.bases.iter().map(|b| b.unwrap_or(pact_ninf)).collect();
+    let pgoto = pact.split_off(tables.actions.len());
+    PackedParser { r1, r2, defact, pact, pgoto, defgoto, table: packed.table, check: packed.check, pact_ninf, table_ninf }
+}
+
+fn pack_rows(rows: &[Vec<(usize, i64)>]) -> BisonTables {
+    let mut bases = Vec::with_capacity(rows.len());
+    let mut table: Vec<i64> = Vec::new();
+    let mut check: Vec<i64> = Vec::new();
+    let mut used = std::collections::BTreeSet::new();
+    for row in rows {
+        let Some(&(first, _)) = row.first() else {
+            bases.push(None);
+            continue;
+        };
+        let fits = |b: i64| {
+            !used.contains(&b)
+                && row.iter().all(|&(c, _)| check.get((b + c as i64) as usize).is_none_or(|&k| k == -1))
+        };
+        let mut b = -(first as i64);
+        while !fits(b) {
+            b += 1;
+        }
+        used.insert(b);
+        for &(c, v) in row {
+            let slot = (b + c as i64) as usize;
+            if slot >= table.len() {
+                table.resize(slot + 1, 0);
+                check.resize(slot + 1, -1);
+            }
+            table[slot] = v;
+            check[slot] = c as i64;
+        }
+        bases.push(Some(b));
+    }
+    BisonTables { bases, table, check }
+}
+
+/// Code of a `'c'` literal token, escapes included.
+pub(crate) fn char_literal_code(sym: &str) -> Option<i64> {
+    let inner = sym.strip_prefix('\'')?.strip_suffix('\'')?;
+    let mut chars = inner.chars();
+    let code = match (chars.next()?, chars.next()) {
+        (c, None) => c as i64,
+        ('\\', Some(e)) => {
+            let rest: String = chars.collect();
+            match (e, rest.as_str()) {
+                ('n', "") => 10,
+                ('t', "") => 9,
+                ('r', "") => 13,
+                ('a', "") => 7,
+                ('b', "") => 8,
+                ('f', "") => 12,
+                ('v', "") => 11,
+                ('\\' | '\'' | '"' | '?', "") => e as i64,
+                ('x', hex) => i64::from_str_radix(hex, 16).ok()?,
+                (d, oct) if d.is_digit(8) => i64::from_str_radix(&format!("{}{}", d, oct), 8).ok()?,
+                _ => return None,
+            }
+        }
+        _ => return None,
+    };
+    (code < 256).then_some(code)
+}
+
+pub(crate) fn is_c_identifier(sym: &str) -> bool {
+    sym.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_') && sym.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// External token number of every terminal: 0 for `$end`, 256 for `error`,
+/// 257 for `$undefined`, a character's code for `'c'`, then 258 upwards.
+pub fn token_codes(terminals: &[String]) -> Vec<i64> {
+    let mut next = 258;
+    terminals
+        .iter()
+        .enumerate()
+        .map(|(i, sym)| match i {
+            0 => 0,
+            ERROR_SYMBOL => 256,
+            UNDEFINED_SYMBOL => 257,
+            _ => char_literal_code(sym).unwrap_or_else(|| {
+                next += 1;
+                next - 1
+            }),
+        })
+        .collect()
+}
+
+/// `enum yytokentype` and the matching yacc-style `#define`s.
+fn token_definitions(terminals: &[String]) -> String {
+    let named: Vec<(&String, i64)> = terminals
+        .iter()
+        .zip(token_codes(terminals))
+        .skip(UNDEFINED_SYMBOL + 1)
+        .filter(|(sym, _)| is_c_identifier(sym))
+        .collect();
+    if named.is_empty() {
+        return String::new();
+    }
+    let mut code = String::from("/* Token kinds. */\n#ifndef YYTOKENTYPE\n# define YYTOKENTYPE\nenum yytokentype\n{\n");
+    for (i, (sym, n)) in named.iter().enumerate() {
+        code.push_str(&format!("    {} = {}{}\n", sym, n, if i + 1 < named.len() { "," } else { "" }));
+    }
+    code.push_str("};\n#endif\n");
+    for (sym, n) in &named {
+        code.push_str(&format!("#define {} {}\n", sym, n));
+    }
+    code.push('\n');
+    code
+}
+
+/// `%union` body, if any.
+pub(crate) fn union_body(bison: &ASTNode) -> Option<&str> {
+    let ASTNode::BisonFile { declarations, .. } = bison else { return None };
+    declarations.iter().find_map(|d| match d {
+        ASTNode::BisonUnionDecl { body } => Some(body.as_str()),
+        _ => None,
+    })
+}
+
+/// Source line of every production's action, in `Grammar::productions` order.
+pub(crate) fn action_lines(bison: &ASTNode) -> Vec<usize> {
+    let mut out = Vec::new();
+    if let ASTNode::BisonFile { rules, .. } = bison {
+        for rule in rules {
+            if let ASTNode::BisonGrammarRule { alternatives, .. } = rule {
+                for alt in alternatives {
+                    if let ASTNode::BisonAlternative { action_line, .. } = alt {
+                        out.push(*action_line);
+                    }
+                }
+            }
+        }
+    }
+    out
+}
+
+/// `<tag>` of every symbol given one by `%token`, `%type` and friends.
+pub(crate) fn symbol_types(bison: &ASTNode) -> BTreeMap<String, String> {
+    let mut out = BTreeMap::new();
+    if let ASTNode::BisonFile { declarations, .. } = bison {
+        for d in declarations {
+            if let ASTNode::BisonTypeDecl { type_name, symbols } = d {
+                for s in symbols {
+                    out.insert(s.clone(), type_name.clone());
+                }
+            }
+        }
+    }
+    out
+}
+
+/// Declaration of `YYSTYPE`: the `%union`, or `int` like yacc.
+fn value_type(bison: &ASTNode) -> String {
+    let body = match union_body(bison) {
+        Some(body) => format!("union YYSTYPE\n{{\n    {}\n}};\ntypedef union YYSTYPE YYSTYPE;\n", body),
+        None => "typedef int YYSTYPE;\n".to_string(),
+    };
+    format!(
+        "#if ! defined YYSTYPE && ! defined YYSTYPE_IS_DECLARED\n{}# define YYSTYPE_IS_TRIVIAL 1\n# define YYSTYPE_IS_DECLARED 1\n#endif\n\n",
+        body
+    )
+}
+
+/// How `$` and `@` references are spelled in the generated code.
+#[derive(Clone, Copy, PartialEq)]
+pub(crate) enum ValueAccess {
+    /// yacc.c's value and location stacks.
+    Stack,
+    /// The `yyrhs[n]` array a recursive-descent function fills in.
+    Frame,
+    /// Rust locals: `yyval`, `_1`, `yylocs[0]`.
+    Rust,
+}
+
+/// One rule's view of `$$`, `$n`, `$name` and their `@` counterparts.
+pub(crate) struct RuleContext<'a> {
+    pub lhs: &'a str,
+    pub rhs: &'a [String],
+    pub types: &'a BTreeMap<String, String>,
+    /// With a `%union`, every value needs a tag.
+    pub typed: bool,
+    pub access: ValueAccess,
+}
+
+impl RuleContext<'_> {
+    /// Position of a named reference; `None` is the left-hand side.
+    fn resolve(&self, name: &str) -> Result<Option<i64>, String> {
+        let mut found: Vec<Option<i64>> = Vec::new();
+        if name == self.lhs {
+            found.push(None);
+        }
+        found.extend(self.rhs.iter().enumerate().filter(|(_, s)| *s == name).map(|(i, _)| Some(i as i64 + 1)));
+        match found.as_slice() {
+            [one] => Ok(*one),
+            [] => Err(format!("invalid reference: '${}' in rule for '{}'", name, self.lhs)),
+            _ => Err(format!("ambiguous reference: '${}' in rule for '{}'", name, self.lhs)),
+        }
+    }
+
+    fn slot(&self, n: i64) -> String {
+        format!("[{}]", n - self.rhs.len() as i64)
+    }
+
+    fn value(&self, pos: Option<i64>, tag: Option<String>) -> Result<String, String> {
+        if self.access == ValueAccess::Rust {
+            return match (pos, tag) {
+                (_, Some(tag)) => Err(format!("'$<{}>' is not supported by the Rust target: values are typed by their symbol", tag)),
+                (None, None) => Ok("yyval".to_string()),
+                (Some(n), None) if n < 1 || n > self.rhs.len() as i64 => {
+                    Err(format!("integer out of range: '${}' in rule for '{}'", n, self.lhs))
+                }
+                (Some(n), None) => Ok(format!("_{}", n)),
+            };
+        }
+        let (base, symbol, shown) = match pos {
+            None => ("(yyval".to_string(), Some(self.lhs), "$$".to_string()),
+            Some(n) if n > self.rhs.len() as i64 => {
+                return Err(format!("integer out of range: '${}' in rule for '{}'", n, self.lhs));
+            }
+            Some(n) if self.access == ValueAccess::Frame && n < 1 => {
+                return Err(format!("'${}' in rule for '{}' reaches outside the rule, which a recursive-descent parser cannot do", n, self.lhs));
+            }
+            Some(n) => {
+                let base = match self.access {
+                    ValueAccess::Frame => format!("(yyrhs[{}]", n),
+                    _ => format!("(yyvsp{}", self.slot(n)),
+                };
+                (base, (n >= 1).then(|| self.rhs[n as usize - 1].as_str()), format!("${}", n))
+            }
+        };
+        let tag = match tag {
+            Some(tag) => Some(tag),
+            None if self.typed => match symbol.and_then(|s| self.types.get(s)) {
+                Some(tag) => Some(tag.clone()),
+                None => return Err(format!("{} of '{}' has no declared type", shown, self.lhs)),
+            },
+            None => None,
+        };
+        Ok(match tag {
+            Some(tag) => format!("{}.{})", base, tag),
+            None => format!("{})", base),
+        })
+    }
+
+    fn location(&self, pos: Option<i64>) -> Result<String, String> {
+        if self.access == ValueAccess::Frame {
+            return Err(format!("locations are not supported by the recursive-descent parser (rule for '{}')", self.lhs));
+        }
+        if self.access == ValueAccess::Rust {
+            return match pos {
+                None => Ok("yyloc".to_string()),
+                Some(n) if n < 1 || n > self.rhs.len() as i64 => Err(format!("integer out of range: '@{}' in rule for '{}'", n, self.lhs)),
+                Some(n) => Ok(format!("yylocs[{}]", n - 1)),
+            };
+        }
+        match pos {
+            None => Ok("(yyloc)".to_string()),
+            Some(n) if n > self.rhs.len() as i64 => Err(format!("integer out of range: '@{}' in rule for '{}'", n, self.lhs)),
+            Some(n) => Ok(format!("(yylsp{})", self.slot(n))),
+        }
+    }
+}
+
+/// Rewrites `$`/`@` references in a rule action into stack accesses,
+/// leaving strings, character constants and comments alone. Also tells
+/// whether the action uses locations.
+pub(crate) fn translate_action(action: &str, ctx: &RuleContext) -> Result<(String, bool), String> {
+    let chars: Vec<char> = action.chars().collect();
+    let mut out = String::with_capacity(action.len());
+    let mut uses_locations = false;
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let next = chars.get(i + 1).copied();
+        match c {
+            '"' | '\'' => {
+                let start = i;
+                i += 1;
+                while i < chars.len() && chars[i] != c {
+                    i += if chars[i] == '\\' { 2 } else { 1 };
+                }
+                i = (i + 1).min(chars.len());
+                out.extend(&chars[start..i]);
+            }
+            '/' if next == Some('/') => {
+                let start = i;
+                while i < chars.len() && chars[i] != '\n' {
+                    i += 1;
+                }
+                out.extend(&chars[start..i]);
+            }
+            '/' if next == Some('*') => {
+                let start = i;
+                i += 2;
+                while i < chars.len() && !(chars[i] == '*' && chars.get(i + 1) == Some(&'/')) {
+                    i += 1;
+                }
+                i = (i + 2).min(chars.len());
+                out.extend(&chars[start..i]);
+            }
+            '$' | '@' => {
+                let mut j = i + 1;
+                let mut tag = None;
+                if c == '$' && chars.get(j) == Some(&'<') {
+                    if let Some(end) = chars[j..].iter().position(|&t| t == '>') {
+                        tag = Some(chars[j + 1..j + end].iter().collect::<String>());
+                        j += end + 1;
+                    }
+                }
+                let is_ident = |ch: char| ch.is_alphanumeric() || ch == '_';
+                let reference = match chars.get(j) {
+                    Some('$') => {
+                        j += 1;
+                        Some(None)
+                    }
+                    Some(&d) if d.is_ascii_digit() || (d == '-' && chars.get(j + 1).is_some_and(|e| e.is_ascii_digit())) => {
+                        let start = j;
+                        j += 1;
+                        while chars.get(j).is_some_and(|e| e.is_ascii_digit()) {
+                            j += 1;
+                        }
+                        let n: String = chars[start..j].iter().collect();
+                        Some(Some(n.parse::<i64>().map_err(|e| e.to_string())?))
+                    }
+                    Some('[') => {
+                        let end = chars[j..].iter().position(|&t| t == ']').ok_or("Unterminated '[' in named reference")?;
+                        let name: String = chars[j + 1..j + end].iter().collect();
+                        j += end + 1;
+                        Some(ctx.resolve(name.trim())?)
+                    }
+                    Some(&d) if d.is_alphabetic() || d == '_' => {
+                        let start = j;
+                        while chars.get(j).is_some_and(|&e| is_ident(e)) {
+                            j += 1;
+                        }
+                        let name: String = chars[start..j].iter().collect();
+                        Some(ctx.resolve(&name)?)
+                    }
+                    _ => None,
+                };
+                match reference {
+                    Some(pos) if c == '$' => {
+                        out.push_str(&ctx.value(pos, tag)?);
+                        i = j;
+                    }
+                    Some(pos) => {
+                        out.push_str(&ctx.location(pos)?);
+                        uses_locations = true;
+                        i = j;
+                    }
+                    None => {
+                        out.push(c);
+                        i += 1;
+                    }
+                }
+            }
+            _ => {
+                out.push(c);
+                i += 1;
+            }
+        }
+    }
+    Ok((out, uses_locations))
+}
+
+/// The declaration heading the definition of `name` in `code`, e.g.
+/// `void yyerror(const char *s);`.
+fn function_signature(code: &str, name: &str) -> Option<String> {
+    let (at, _) = code.match_indices(name).find(|&(i, _)| {
+        let before = code[..i].trim_end();
+        code[i + name.len()..].trim_start().starts_with('(')
+            && before.ends_with(|c: char| c.is_alphanumeric() || c == '_' || c == '*')
+    })?;
+    // The return type may sit on the line above, K&R style.
+    let line_start = code[..at].rfind('\n').map_or(0, |p| p + 1);
+    let start = if code[line_start..at].trim().is_empty() {
+        code[..line_start.saturating_sub(1)].rfind('\n').map_or(0, |p| p + 1)
+    } else {
+        line_start
+    };
+    let open = at + code[at..].find('(')?;
+    let mut depth = 0;
+    for (k, ch) in code[open..].char_indices() {
+        match ch {
+            '(' => depth += 1,
+            ')' => {
+                depth -= 1;
+                if depth == 0 {
+                    let signature = code[start..=open + k].split_whitespace().collect::<Vec<_>>().join(" ");
+                    return Some(format!("{};\n", signature));
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// The spec asks for `%locations`.
+pub(crate) fn declares_locations(bison: &ASTNode) -> bool {
+    let ASTNode::BisonFile { declarations, .. } = bison else { return false };
+    declarations.iter().any(|d| matches!(d, ASTNode::BisonLocations))
+}
+
+/// Semantic actions by production, rewritten for the value and location
+/// stacks, and whether locations are tracked: `%locations`, or an action
+/// uses them.
+fn translate_actions(bison: &ASTNode, grammar: &Grammar) -> Result<(Vec<Option<String>>, bool), String> {
+    let types = symbol_types(bison);
+    let typed = union_body(bison).is_some();
+    let mut uses_locations = false;
+    let mut out = Vec::with_capacity(grammar.productions.len());
+    for p in &grammar.productions {
+        let Some(action) = &p.action else {
+            out.push(None);
+            continue;
+        };
+        let ctx = RuleContext { lhs: &p.lhs, rhs: &p.rhs, types: &types, typed, access: ValueAccess::Stack };
+        let (body, locations) = translate_action(action, &ctx)?;
+        uses_locations |= locations;
+        out.push(Some(body));
+    }
+    Ok((out, uses_locations || declares_locations(bison)))
+}
+
+// --- PURE PARSERS AND PARAMETERS ---
+
+/// `%define api.push-pull`: who drives the parse.
+#[derive(Default, Clone, Copy, PartialEq)]
+pub(crate) enum PushPull {
+    /// yyparse calls yylex for each token.
+    #[default]
+    Pull,
+    /// The caller hands tokens to yypush_parse one at a time.
+    Push,
+    /// yypush_parse, and yyparse/yypull_parse built on it.
+    Both,
+}
+
+/// How yyparse talks to its caller, yylex and yyerror: `%define api.pure`,
+/// `api.push-pull` and the `%parse-param`/`%lex-param` declarations.
+#[derive(Default)]
+pub(crate) struct Interface {
+    /// yylval, yylloc, yychar and yynerrs are yyparse's locals, and yylex
+    /// gets pointers to the first two.
+    pub pure: bool,
+    /// `api.pure full` rather than the legacy `true`.
+    pub full: bool,
+    pub parse_params: Vec<String>,
+    pub lex_params: Vec<String>,
+    pub push_pull: PushPull,
+}
+
+/// Name a parameter declaration declares: `int *count` -> `count`.
+fn param_name(decl: &str) -> &str {
+    let decl = decl.split('[').next().unwrap_or(decl);
+    let decl = decl.split_once("(*").map_or(decl, |(_, rest)| rest.split(')').next().unwrap_or(rest));
+    let is_ident = |c: char| c.is_alphanumeric() || c == '_';
+    let end = decl.trim_end().len();
+    let start = decl[..end].rfind(|c: char| !is_ident(c)).map_or(0, |i| i + 1);
+    &decl[start..end]
+}
+
+impl Interface {
+    pub fn from_ast(bison: &ASTNode) -> Self {
+        let mut out = Interface::default();
+        let ASTNode::BisonFile { declarations, .. } = bison else { return out };
+        for d in declarations {
+            match d {
+                ASTNode::BisonDefine { name, value } if name == "api.pure" => {
+                    out.pure = value != "false";
+                    out.full = value == "full";
+                }
+                ASTNode::BisonDefine { name, value } if name == "api.push-pull" => {
+                    out.push_pull = match value.as_str() {
+                        "push" => PushPull::Push,
+                        "both" => PushPull::Both,
+                        _ => PushPull::Pull,
+                    };
+                }
+                ASTNode::BisonParamDecl { kind, params } => {
+                    if kind != "lex" {
+                        out.parse_params.extend(params.iter().cloned());
+                    }
+                    if kind != "parse" {
+                        out.lex_params.extend(params.iter().cloned());
+                    }
+                }
+                _ => {}
+            }
+        }
+        out
+    }
+
+    /// Neither pure, push nor parameterized: yacc's original interface.
+    pub fn is_default(&self) -> bool {
+        !self.pure && self.parse_params.is_empty() && self.lex_params.is_empty() && self.push_pull == PushPull::Pull
+    }
+
+    /// There is a yypush_parse.
+    pub fn push(&self) -> bool {
+        self.push_pull != PushPull::Pull
+    }
+
+    /// There is a yyparse calling yylex.
+    pub fn pull(&self) -> bool {
+        self.push_pull != PushPull::Push
+    }
+
+    fn yylex_params(&self, locations: bool) -> Vec<String> {
+        let mut params = Vec::new();
+        if self.pure {
+            params.push("YYSTYPE *yylvalp".to_string());
+            if locations {
+                params.push("YYLTYPE *yyllocp".to_string());
+            }
+        }
+        params.extend(self.lex_params.iter().cloned());
+        params
+    }
+
+    /// Pure parsers pass yyerror the location first, except that legacy
+    /// `api.pure true` only does so alongside %parse-param.
+    fn yyerror_params(&self, locations: bool) -> Vec<String> {
+        let mut params = Vec::new();
+        if self.pure && locations && (self.full || !self.parse_params.is_empty()) {
+            params.push("YYLTYPE *yyllocp".to_string());
+        }
+        params.extend(self.parse_params.iter().cloned());
+        params.push("const char *yymsg".to_string());
+        params
+    }
+
+    pub fn yyparse_prototype(&self) -> String {
+        format!("int yyparse({})", prototype_params(&self.parse_params))
+    }
+
+    fn yypush_parse_prototype(&self, locations: bool) -> String {
+        let mut params = vec!["yypstate *yyps".to_string(), "int yypushed_char".to_string(), "YYSTYPE const *yypushed_val".to_string()];
+        if locations {
+            params.push("YYLTYPE *yypushed_loc".to_string());
+        }
+        params.extend(self.parse_params.iter().cloned());
+        format!("int yypush_parse({})", params.join(", "))
+    }
+
+    fn yypull_parse_prototype(&self) -> String {
+        let params: Vec<String> = std::iter::once("yypstate *yyps".to_string()).chain(self.parse_params.iter().cloned()).collect();
+        format!("int yypull_parse({})", params.join(", "))
+    }
+
+    /// The push-mode declarations y.tab.h makes in place of yyparse's alone.
+    fn push_declarations(&self, locations: bool) -> String {
+        let mut code = String::from(PUSH_TYPES);
+        if self.pull() {
+            code.push_str(&format!("{};\n", self.yyparse_prototype()));
+        }
+        code.push_str(&format!("{};\n", self.yypush_parse_prototype(locations)));
+        if self.pull() {
+            code.push_str(&format!("{};\n", self.yypull_parse_prototype()));
+        }
+        code.push_str("yypstate *yypstate_new(void);\nvoid yypstate_delete(yypstate *yyps);\n");
+        code
+    }
+
+    /// Fetches tokens with yylex and pushes them until the parse is over,
+    /// leaving its result in `yystatus`.
+    fn pull_loop(&self, locations: bool) -> String {
+        let mut args = vec!["yyps".to_string(), "yytok".to_string(), "&yylval".to_string()];
+        if locations {
+            args.push("&yylloc".to_string());
+        }
+        args.extend(self.parse_params.iter().map(|p| param_name(p).to_string()));
+        PULL_LOOP.replace("ARGS", &args.join(", "))
+    }
+
+    /// yypstate, yypush_parse, and with `both` yypull_parse and yyparse.
+    fn push_driver(&self, locations: bool, cases: &str) -> String {
+        let mut code = String::from(PUSH_STATE);
+        code.push_str(PUSH_STATE_MACROS);
+        code.push_str(&PUSH_DRIVER_ENTRY.replace("int yypush_parse(void)", &self.yypush_parse_prototype(locations)));
+        code.push_str(&PARSER_DRIVER_HEAD.replace(PULL_READ, PUSH_READ));
+        code.push_str(cases);
+        code.push_str(PARSER_DRIVER_TAIL);
+        code.push_str(PUSH_DRIVER_RETURN);
+        if self.pull() {
+            let args: Vec<&str> = std::iter::once("yyps").chain(self.parse_params.iter().map(|p| param_name(p))).collect();
+            code.push_str(&format!(
+                "
+{}
+{{
+    int yystatus;
+{}{}    return yystatus;
+}}
+",
+                self.yypull_parse_prototype(),
+                PULL_LOCALS,
+                self.pull_loop(locations)
+            ));
+            code.push_str(&format!(
+                "
+{}
+{{
+    yypstate *yyps = yypstate_new();
+    int yystatus;
+    if (!yyps)
+        return 2;
+    yystatus = yypull_parse({});
+    yypstate_delete(yyps);
+    return yystatus;
+}}
+",
+                self.yyparse_prototype(),
+                args.join(", ")
+            ));
+        }
+        code
+    }
+
+    pub fn yylex_prototype(&self, locations: bool) -> String {
+        format!("int yylex({})", prototype_params(&self.yylex_params(locations)))
+    }
+
+    /// `YYLEX` and `YYERROR_CALL(Msg)`: how the driver calls yylex and yyerror.
+    fn call_macros(&self, locations: bool) -> String {
+        let args = |params: &[String]| -> Vec<String> {
+            params
+                .iter()
+                .map(|p| match param_name(p) {
+                    "yylvalp" => "&yylval".to_string(),
+                    "yyllocp" => "&yylloc".to_string(),
+                    name => name.to_string(),
+                })
+                .collect()
+        };
+        let mut error_args = args(&self.yyerror_params(locations));
+        error_args.pop();
+        error_args.push("Msg".to_string());
+        format!(
+            "#define YYLEX yylex({})\n#define YYERROR_CALL(Msg) yyerror({})\n",
+            args(&self.yylex_params(locations)).join(", "),
+            error_args.join(", ")
+        )
+    }
+
+    /// Parameters mention flex's `yyscan_t`, which the header then declares.
+    fn uses_scanner_type(&self) -> bool {
+        self.parse_params.iter().chain(&self.lex_params).any(|p| mentions(p, "yyscan_t"))
+    }
+}
+
+fn prototype_params(params: &[String]) -> String {
+    if params.is_empty() {
+        "void".to_string()
+    } else {
+        params.join(", ")
+    }
+}
+
+/// `(void) name;` for each parameter a stand-in function ignores.
+fn ignore_params(params: &[String]) -> String {
+    params.iter().map(|p| format!("    (void) {};\n", param_name(p))).collect()
+}
+
+/// Everything y.tab.h declares. y.tab.c repeats it under the same include
+/// guard, so a prologue that includes the header does no harm.
+pub(crate) fn header_block(bison: &ASTNode, grammar: &Grammar, locations: bool) -> String {
+    let iface = Interface::from_ast(bison);
+    let mut code = format!("#ifndef {0}\n# define {0}\n\n", HEADER_GUARD);
+    code.push_str(&token_definitions(&terminal_symbols(grammar)));
+    code.push_str(&value_type(bison));
+    if locations {
+        code.push_str(LOCATION_TYPE);
+    }
+    if !iface.pure {
+        code.push_str("extern YYSTYPE yylval;\n");
+        if locations {
+            // A scanner including this header keeps yylloc up to date.
+            code.push_str("extern YYLTYPE yylloc;\n# define YYLLOC_IS_GLOBAL 1\n");
+        }
+    }
+    if iface.uses_scanner_type() {
+        code.push('\n');
+        code.push_str(SCANNER_TYPE);
+    }
+    if iface.push() {
+        code.push_str(&format!("\n{}\n#endif /* !{} */\n", iface.push_declarations(locations), HEADER_GUARD));
+    } else {
+        code.push_str(&format!("\n{};\n\n#endif /* !{} */\n", iface.yyparse_prototype(), HEADER_GUARD));
+    }
+    code
+}
+
+/// y.tab.h for a BisonFile: token numbers, `YYSTYPE`, `YYLTYPE` when
+/// locations are tracked, and the `yylval`/`yylloc` the scanner fills in.
+pub fn generate_header(bison: &ASTNode) -> Result<String, String> {
+    let grammar = Grammar::from_ast(bison)?;
+    let (_, locations) = translate_actions(bison, &grammar)?;
+    Ok(format!("/* Generated by Structura.ai Syntax Engine */\n{}", header_block(bison, &grammar, locations)))
+}
+
+/// yylex/yyerror: declarations of the user's, or a stand-in yyerror.
+pub(crate) fn support_declarations(prologue: &str, epilogue: &str, codegen: &CodegenOptions, iface: &Interface, locations: bool) -> String {
+    let mut code = String::new();
+    for name in ["yylex", "yyerror"] {
+        if !defines_function(prologue, name) {
+            if let Some(signature) = function_signature(epilogue, name) {
+                code.push_str(&signature);
+            }
+        }
+    }
+    if codegen.combined && !defines_function(prologue, "yylex") && !defines_function(epilogue, "yylex") {
+        code.push_str(&format!("{};\n", iface.yylex_prototype(locations)));
+    }
+    if !defines_function(prologue, "yyerror") && !defines_function(epilogue, "yyerror") {
+        let params = iface.yyerror_params(locations);
+        // Where the error is, as `line.column: `, when yyerror can tell.
+        let location = if params.iter().any(|p| param_name(p) == "yyllocp") {
+            Some("yyllocp->")
+        } else if locations && !iface.pure {
+            Some("yylloc.")
+        } else {
+            None
+        };
+        let ignored: Vec<String> = params[..params.len() - 1]
+            .iter()
+            .filter(|p| location != Some("yyllocp->") || param_name(p) != "yyllocp")
+            .cloned()
+            .collect();
+        let report = match location {
+            Some(l) => format!("fprintf(stderr, \"%d.%d: %s\\n\", {0}first_line, {0}first_column, yymsg);", l),
+            None => "fprintf(stderr, \"%s\\n\", yymsg);".to_string(),
+        };
+        code.push_str(&format!("\nstatic void yyerror({})\n{{\n{}    {}\n}}\n", params.join(", "), ignore_params(&ignored), report));
+    }
+    code.push('\n');
+    code
+}
+
+/// The stand-in yylex reading token names from stdin, with the tables it
+/// looks them up in. Expects `YYEOF`, `YYUNDEFTOK` and `YYNTOKENS`.
+pub(crate) fn default_lexer_code(terminals: &[String], iface: &Interface, locations: bool) -> String {
+    let mut code = String::from("static const char *const yytname[] =\n    {");
+    for (i, sym) in terminals.iter().enumerate() {
+        code.push_str(if i % 6 == 0 { "\n    " } else { " " });
+        code.push_str(&c_string(sym));
+        code.push(',');
+    }
+    code.push_str("\n    };\n\n");
+    let codes = token_codes(terminals);
+    c_array(&mut code, int_type(&codes), "yytoknum", &codes);
+    let params = iface.yylex_params(locations);
+    code.push_str(&DEFAULT_YYLEX.replace("int yylex(void)\n{\n", &format!("int yylex({})\n{{\n{}", prototype_params(&params), ignore_params(&params))));
+    code
+}
+
+/// A main() running yyparse, unless the spec has its own or yyparse takes
+/// arguments only the spec can supply. A push-only parser's pushes what
+/// yylex returns.
+pub(crate) fn default_main(prologue: &str, epilogue: &str, codegen: &CodegenOptions, iface: &Interface, locations: bool) -> String {
+    if defines_function(prologue, "main") || defines_function(epilogue, "main") || !iface.parse_params.is_empty() {
+        return String::new();
+    }
+    // Linked with a scanner, a main in another file wins, as over liby's.
+    let weak = if codegen.combined { "\n#ifdef __GNUC__\n__attribute__((weak))\n#endif" } else { "" };
+    if !iface.pull() {
+        return format!("{}{}", weak, PUSH_MAIN.replace("LOOP", &iface.pull_loop(locations)).replace("LOCALS", PULL_LOCALS));
+    }
+    format!("{}{}", weak, DEFAULT_MAIN)
+}
+
+pub fn generate_parser(bison: &ASTNode, codegen: &CodegenOptions) -> Result<String, String> {
+    let ASTNode::BisonFile { prologue, prologue_line, epilogue, epilogue_line, .. } = bison else {
+        return Err("Expected a Bison specification".to_string());
+    };
+    let tables = build_lalr(bison)?;
+    let prologue = prologue.as_deref().unwrap_or("");
+    let epilogue = epilogue.as_deref().unwrap_or("");
+
+    // Semantic actions first: they decide whether locations are tracked.
+    let (actions, uses_locations) = translate_actions(bison, &tables.grammar)?;
+    let lines = action_lines(bison);
+    let mut cases = String::new();
+    for (r, rule) in tables.rules.iter().enumerate() {
+        let Some(pi) = rule.production else { continue };
+        let Some(body) = &actions[pi] else { continue };
+        let p = &tables.grammar.productions[pi];
+        cases.push_str(&format!("    case {}: /* {} */\n", r + 1, comment_safe(&p.to_string())));
+        let line = lines.get(pi).copied().unwrap_or(0);
+        cases.push_str(&user_code(codegen, PARSER_SOURCE, line, &format!("      {{ {} }}", body)));
+        cases.push_str("      break;\n\n");
+    }
+
+    let mut code = String::from("/* Generated by Structura.ai Syntax Engine */\n");
+    code.push_str("#include <stdio.h>\n#include <stdlib.h>\n#include <string.h>\n\n");
+    if !tables.conflicts.is_empty() {
+        let count = |kind: &str| tables.conflicts.iter().filter(|c| c.kind == kind).count();
+        code.push_str(&format!(
+            "/* conflicts: {} shift/reduce, {} reduce/reduce (resolved as bison would) */\n\n",
+            count("shift/reduce"),
+            count("reduce/reduce")
+        ));
+    }
+    if !prologue.is_empty() {
+        code.push_str("/* --- PROLOGUE --- */\n");
+        code.push_str(&user_code(codegen, PARSER_SOURCE, *prologue_line, prologue));
+        code.push('\n');
+    }
+    let iface = Interface::from_ast(bison);
+    code.push_str(&header_block(bison, &tables.grammar, uses_locations));
+    code.push_str(&format!("\n#define YYLSP_NEEDED {}\n#define YYPURE {}\n", uses_locations as u8, iface.pure as u8));
+    if !iface.pure {
+        code.push_str(PARSER_GLOBALS);
+    }
+
+    // Push-only, the stand-in yylex only feeds the stand-in main.
+    let default_lexer = !codegen.combined
+        && !defines_function(prologue, "yylex")
+        && !defines_function(epilogue, "yylex")
+        && (iface.pull() || !(defines_function(prologue, "main") || defines_function(epilogue, "main") || !iface.parse_params.is_empty()));
+    code.push_str(&support_declarations(prologue, epilogue, codegen, &iface, uses_locations));
+
+    // --- Tables ---
+    let ntokens = tables.terminal_count;
+    let codes = token_codes(&tables.symbols[..ntokens]);
+    let max_code = *codes.iter().max().unwrap_or(&257);
+    let mut translate = vec![UNDEFINED_SYMBOL as i64; max_code as usize + 1];
+    for (sym, &c) in codes.iter().enumerate() {
+        translate[c as usize] = sym as i64;
+    }
+    let packed = pack_parser(&tables);
+    code.push_str(&format!(
+        "#define YYFINAL {}\n#define YYLAST {}\n#define YYNTOKENS {}\n#define YYNNTS {}\n#define YYNRULES {}\n#define YYNSTATES {}\n#define YYMAXUTOK {}\n#define YYPACT_NINF ({})\n#define YYTABLE_NINF ({})\n\n",
+        tables.final_state,
+        packed.table.len() as i64 - 1,
+        ntokens,
+        tables.symbols.len() - ntokens,
+        tables.rules.len(),
+        tables.actions.len(),
+        max_code,
+        packed.pact_ninf,
+        packed.table_ninf
+    ));
+    code.push_str(PARSER_MACROS);
+    code.push_str(&iface.call_macros(uses_locations));
+    code.push('\n');
+    code.push_str(&format!(
+        "/* {} states, {} rules; {} packed action and goto slots. */\n",
+        tables.actions.len(),
+        tables.rules.len(),
+        packed.table.len()
+    ));
+    for (name, values) in [("yytranslate", &translate[..])].into_iter().chain(packed.arrays()) {
+        c_array(&mut code, int_type(values), name, values);
+    }
+    if default_lexer {
+        code.push_str(&default_lexer_code(&tables.symbols[..ntokens], &iface, uses_locations));
+    }
+
+    // --- Driver ---
+    if iface.push() {
+        code.push_str(&iface.push_driver(uses_locations, &cases));
+    } else {
+        code.push_str(&PARSER_DRIVER_ENTRY.replace("int yyparse(void)", &iface.yyparse_prototype()));
+        code.push_str(PARSER_DRIVER_HEAD);
+        code.push_str(&cases);
+        code.push_str(PARSER_DRIVER_TAIL);
+        code.push_str(PARSER_DRIVER_RETURN);
+    }
+
+    code.push_str(&default_main(prologue, epilogue, codegen, &iface, uses_locations));
+    if !epilogue.is_empty() {
+        code.push_str("\n/* --- EPILOGUE --- */\n");
+        code.push_str(&user_code(codegen, PARSER_SOURCE, *epilogue_line, epilogue));
+    }
+    Ok(number_output_lines(code, PARSER_OUTPUT))
+}
+
+const LOCATION_TYPE: &str = r#"#if ! defined YYLTYPE && ! defined YYLTYPE_IS_DECLARED
+typedef struct YYLTYPE
+{
+    int first_line;
+    int first_column;
+    int last_line;
+    int last_column;
+} YYLTYPE;
+# define YYLTYPE_IS_DECLARED 1
+# define YYLTYPE_IS_TRIVIAL 1
+#endif
+
+"#;
+
+const PARSER_GLOBALS: &str = r#"
+int yychar;
+YYSTYPE yylval;
+int yynerrs;
+#if YYLSP_NEEDED
+YYLTYPE yylloc = { 1, 1, 1, 1 };
+#endif
+"#;
+
+const PARSER_MACROS: &str = r#"#define YYEMPTY (-2)
+#define YYEOF 0
+#define YYTERROR 1
+#define YYUNDEFTOK 2
+#define YYTRANSLATE(YYX) ((unsigned) (YYX) <= YYMAXUTOK ? yytranslate[YYX] : YYUNDEFTOK)
+
+#define yyerrok (yyerrstatus = 0)
+#define yyclearin (yychar = YYEMPTY)
+#define YYACCEPT goto yyacceptlab
+#define YYABORT goto yyabortlab
+#define YYERROR goto yyerrorlab
+#define YYRECOVERING() (!!yyerrstatus)
+
+#ifndef YYINITDEPTH
+# define YYINITDEPTH 200
+#endif
+#ifndef YYMAXDEPTH
+# define YYMAXDEPTH 10000
+#endif
+
+#if YYLSP_NEEDED
+# define YYRHSLOC(Rhs, K) ((Rhs)[K])
+# ifndef YYLLOC_DEFAULT
+#  define YYLLOC_DEFAULT(Current, Rhs, N)                                \
+    do                                                                   \
+        if (N)                                                           \
+        {                                                                \
+            (Current).first_line = YYRHSLOC(Rhs, 1).first_line;          \
+            (Current).first_column = YYRHSLOC(Rhs, 1).first_column;      \
+            (Current).last_line = YYRHSLOC(Rhs, N).last_line;            \
+            (Current).last_column = YYRHSLOC(Rhs, N).last_column;        \
+        }                                                                \
+        else                                                             \
+        {                                                                \
+            (Current).first_line = (Current).last_line =                 \
+                YYRHSLOC(Rhs, 0).last_line;                              \
+            (Current).first_column = (Current).last_column =             \
+                YYRHSLOC(Rhs, 0).last_column;                            \
+        }                                                                \
+    while (0)
+# endif
+#endif
+
+"#;
+
+const DEFAULT_YYLEX: &str = r#"/* No yylex was supplied: read whitespace-separated token names, e.g.
+   `NUMBER '+' NUMBER`, or single characters, from stdin. */
+static int yylex(void)
+{
+    char yyword[256];
+    int yyi;
+    if (scanf("%255s", yyword) != 1)
+        return YYEOF;
+    for (yyi = YYUNDEFTOK + 1; yyi < YYNTOKENS; yyi++)
+        if (strcmp(yyword, yytname[yyi]) == 0)
+            return yytoknum[yyi];
+    if (strcmp(yyword, "error") == 0)
+        return 256;
+    if (yyword[1] == '\0')
+        return (unsigned char) yyword[0];
+    return 257;
+}
+
+"#;
+
+const PARSER_DRIVER_ENTRY: &str = r#"int yyparse(void)
+{
+    int yystate = 0;
+    int yyerrstatus = 0;
+    int yyn = 0;
+    int yyresult;
+    int yytoken = 0;
+    int yylen = 0;
+#if YYPURE
+    int yychar;
+    int yynerrs;
+    YYSTYPE yylval;
+# if YYLSP_NEEDED
+    YYLTYPE yylloc = { 1, 1, 1, 1 };
+# endif
+#endif
+    long yystacksize = YYINITDEPTH;
+    int *yyss = (int *) malloc(YYINITDEPTH * sizeof (int));
+    int *yyssp = yyss;
+    YYSTYPE *yyvs = (YYSTYPE *) malloc(YYINITDEPTH * sizeof (YYSTYPE));
+    YYSTYPE *yyvsp = yyvs;
+    YYSTYPE yyval;
+#if YYLSP_NEEDED
+    YYLTYPE *yyls = (YYLTYPE *) malloc(YYINITDEPTH * sizeof (YYLTYPE));
+    YYLTYPE *yylsp = yyls;
+    YYLTYPE yyloc;
+    if (!yyls)
+        goto yyexhaustedlab;
+    *yylsp = yylloc;
+#endif
+    if (!yyss || !yyvs)
+        goto yyexhaustedlab;
+#if YYPURE
+    memset(&yylval, 0, sizeof yylval);
+#endif
+    yychar = YYEMPTY;
+    yynerrs = 0;
+    goto yysetstate;
+
+"#;
+
+const PARSER_DRIVER_HEAD: &str = r#"/* Push a new state, which is found in yystate. */
+yynewstate:
+    yyssp++;
+
+yysetstate:
+    *yyssp = yystate;
+    if (yyssp >= yyss + yystacksize - 1)
+    {
+        long yysize = (long) (yyssp - yyss) + 1;
+        int *yyss1;
+        YYSTYPE *yyvs1;
+        if (yystacksize >= YYMAXDEPTH)
+            goto yyexhaustedlab;
+        yystacksize = 2 * yystacksize < YYMAXDEPTH ? 2 * yystacksize : YYMAXDEPTH;
+        yyss1 = (int *) realloc(yyss, (size_t) yystacksize * sizeof (int));
+        if (!yyss1)
+            goto yyexhaustedlab;
+        yyss = yyss1;
+        yyvs1 = (YYSTYPE *) realloc(yyvs, (size_t) yystacksize * sizeof (YYSTYPE));
+        if (!yyvs1)
+            goto yyexhaustedlab;
+        yyvs = yyvs1;
+        yyssp = yyss + yysize - 1;
+        yyvsp = yyvs + yysize - 1;
+#if YYLSP_NEEDED
+        {
+            YYLTYPE *yyls1 = (YYLTYPE *) realloc(yyls, (size_t) yystacksize * sizeof (YYLTYPE));
+            if (!yyls1)
+                goto yyexhaustedlab;
+            yyls = yyls1;
+            yylsp = yyls + yysize - 1;
+        }
+#endif
+    }
+    if (yystate == YYFINAL)
+        YYACCEPT;
+
+/* Do the appropriate action for the current state, reading a lookahead
+   only if the state needs one. */
+    yyn = yypact[yystate];
+    if (yyn == YYPACT_NINF)
+        goto yydefault;
+    if (yychar == YYEMPTY)
+        yychar = YYLEX;
+    if (yychar <= YYEOF)
+        yychar = yytoken = YYEOF;
+    else
+        yytoken = YYTRANSLATE(yychar);
+    yyn += yytoken;
+    if (yyn < 0 || YYLAST < yyn || yycheck[yyn] != yytoken)
+        goto yydefault;
+    yyn = yytable[yyn];
+    if (yyn <= 0)
+    {
+        if (yyn == YYTABLE_NINF)
+            goto yyerrlab;
+        yyn = -yyn;
+        goto yyreduce;
+    }
+    /* Shift the lookahead token. */
+    if (yyerrstatus)
+        yyerrstatus--;
+    *++yyvsp = yylval;
+#if YYLSP_NEEDED
+    *++yylsp = yylloc;
+#endif
+    yychar = YYEMPTY;
+    yystate = yyn;
+    goto yynewstate;
+
+/* Do the default action for the current state. */
+yydefault:
+    yyn = yydefact[yystate];
+    if (yyn == 0)
+        goto yyerrlab;
+
+/* Reduce by rule yyn. */
+yyreduce:
+    yylen = yyr2[yyn];
+    /* Default action: $$ = $1. */
+    yyval = yyvsp[1 - yylen];
+#if YYLSP_NEEDED
+    YYLLOC_DEFAULT(yyloc, (yylsp - yylen), yylen);
+#endif
+    switch (yyn)
+    {
+"#;
+
+const PARSER_DRIVER_TAIL: &str = r#"    default:
+      break;
+    }
+    yyvsp -= yylen;
+    yyssp -= yylen;
+#if YYLSP_NEEDED
+    yylsp -= yylen;
+    *++yylsp = yyloc;
+#endif
+    yylen = 0;
+    *++yyvsp = yyval;
+    {
+        const int yylhs = yyr1[yyn] - YYNTOKENS;
+        const int yyi = yypgoto[yylhs] + *yyssp;
+        yystate = 0 <= yyi && yyi <= YYLAST && yycheck[yyi] == *yyssp ? yytable[yyi] : yydefgoto[yylhs];
+    }
+    goto yynewstate;
+
+/* Detected a syntax error. */
+yyerrlab:
+    if (!yyerrstatus)
+    {
+        ++yynerrs;
+        YYERROR_CALL("syntax error");
+    }
+    if (yyerrstatus == 3)
+    {
+        /* Just failed to recover: discard the lookahead, but not the end. */
+        if (yychar <= YYEOF)
+        {
+            if (yychar == YYEOF)
+                YYABORT;
+        }
+        else
+            yychar = YYEMPTY;
+    }
+    goto yyerrlab1;
+
+/* An action used YYERROR: drop what the rule had matched. */
+yyerrorlab:
+    if (0)
+        YYERROR;
+    yyvsp -= yylen;
+    yyssp -= yylen;
+#if YYLSP_NEEDED
+    yylsp -= yylen;
+#endif
+    yylen = 0;
+    yystate = *yyssp;
+
+/* Pop states until one can shift the error token. */
+yyerrlab1:
+    yyerrstatus = 3;
+    for (;;)
+    {
+        yyn = yypact[yystate];
+        if (yyn != YYPACT_NINF)
+        {
+            yyn += YYTERROR;
+            if (0 <= yyn && yyn <= YYLAST && yycheck[yyn] == YYTERROR)
+            {
+                yyn = yytable[yyn];
+                if (0 < yyn)
+                    break;
+            }
+        }
+        if (yyssp == yyss)
+            YYABORT;
+        yyvsp--;
+        yyssp--;
+#if YYLSP_NEEDED
+        yylsp--;
+#endif
+        yystate = *yyssp;
+    }
+    *++yyvsp = yylval;
+#if YYLSP_NEEDED
+    *++yylsp = yylloc;
+#endif
+    yystate = yyn;
+    goto yynewstate;
+
+yyacceptlab:
+    yyresult = 0;
+    goto yyreturn;
+
+yyabortlab:
+    yyresult = 1;
+    goto yyreturn;
+
+yyexhaustedlab:
+    YYERROR_CALL("memory exhausted");
+    yyresult = 2;
+
+"#;
+
+const PARSER_DRIVER_RETURN: &str = r#"yyreturn:
+    free(yyss);
+    free(yyvs);
+#if YYLSP_NEEDED
+    free(yyls);
+#endif
+    return yyresult;
+}
+"#;
+
+// --- PUSH PARSERS ---
+
+const PUSH_TYPES: &str = r#"#ifndef YYPUSH_MORE_DEFINED
+# define YYPUSH_MORE_DEFINED
+enum { YYPUSH_MORE = 4 };
+#endif
+
+typedef struct yypstate yypstate;
+
+"#;
+
+/// What yyparse keeps in locals, kept between pushes.
+const PUSH_STATE: &str = r#"
+struct yypstate
+{
+    int yystate;
+    int yyerrstatus;
+    int yychar;
+    int yynerrs;
+    YYSTYPE yylval;
+#if YYLSP_NEEDED
+    YYLTYPE yylloc;
+#endif
+    long yystacksize;
+    int *yyss;
+    int *yyssp;
+    YYSTYPE *yyvs;
+    YYSTYPE *yyvsp;
+#if YYLSP_NEEDED
+    YYLTYPE *yyls;
+    YYLTYPE *yylsp;
+#endif
+    /* No parse is under way: the next push starts one. */
+    int yynew;
+};
+
+yypstate *yypstate_new(void)
+{
+    yypstate *yyps = (yypstate *) calloc(1, sizeof (yypstate));
+    if (!yyps)
+        return NULL;
+    yyps->yystacksize = YYINITDEPTH;
+    yyps->yyss = (int *) malloc(YYINITDEPTH * sizeof (int));
+    yyps->yyvs = (YYSTYPE *) malloc(YYINITDEPTH * sizeof (YYSTYPE));
+#if YYLSP_NEEDED
+    yyps->yyls = (YYLTYPE *) malloc(YYINITDEPTH * sizeof (YYLTYPE));
+    if (!yyps->yyls)
+    {
+        yypstate_delete(yyps);
+        return NULL;
+    }
+#endif
+    if (!yyps->yyss || !yyps->yyvs)
+    {
+        yypstate_delete(yyps);
+        return NULL;
+    }
+    yyps->yynew = 1;
+    return yyps;
+}
+
+void yypstate_delete(yypstate *yyps)
+{
+    if (!yyps)
+        return;
+    free(yyps->yyss);
+    free(yyps->yyvs);
+#if YYLSP_NEEDED
+    free(yyps->yyls);
+#endif
+    free(yyps);
+}
+
+"#;
+
+/// yypush_parse reaches its state under yyparse's names for its locals.
+const PUSH_STATE_MACROS: &str = r#"#define yystate yyps->yystate
+#define yyerrstatus yyps->yyerrstatus
+#define yychar yyps->yychar
+#define yynerrs yyps->yynerrs
+#define yylval yyps->yylval
+#define yystacksize yyps->yystacksize
+#define yyss yyps->yyss
+#define yyssp yyps->yyssp
+#define yyvs yyps->yyvs
+#define yyvsp yyps->yyvsp
+#if YYLSP_NEEDED
+# define yylloc yyps->yylloc
+# define yyls yyps->yyls
+# define yylsp yyps->yylsp
+#endif
+
+"#;
+
+const PUSH_DRIVER_ENTRY: &str = r#"int yypush_parse(void)
+{
+    int yyn = 0;
+    int yyresult;
+    int yytoken = 0;
+    int yylen = 0;
+    YYSTYPE yyval;
+#if YYLSP_NEEDED
+    YYLTYPE yyloc;
+#endif
+    if (!yyps->yynew)
+    {
+        yyn = yypact[yystate];
+        goto yyread_pushed_token;
+    }
+    yystate = 0;
+    yyerrstatus = 0;
+    yyssp = yyss;
+    yyvsp = yyvs;
+    memset(&yylval, 0, sizeof yylval);
+#if YYLSP_NEEDED
+    yylloc.first_line = yylloc.first_column = yylloc.last_line = yylloc.last_column = 1;
+    yylsp = yyls;
+    *yylsp = yylloc;
+#endif
+    yychar = YYEMPTY;
+    yynerrs = 0;
+    goto yysetstate;
+
+"#;
+
+const PULL_READ: &str = "    if (yychar == YYEMPTY)
+        yychar = YYLEX;
+";
+
+/// The first token a parse needs is the one pushed to start it; for each
+/// later one yypush_parse returns YYPUSH_MORE and resumes here.
+const PUSH_READ: &str = "    if (yychar == YYEMPTY)
+    {
+        if (!yyps->yynew)
+        {
+            yyresult = YYPUSH_MORE;
+            goto yypushreturn;
+        }
+        yyps->yynew = 0;
+yyread_pushed_token:
+        yychar = yypushed_char;
+        if (yypushed_val)
+            yylval = *yypushed_val;
+#if YYLSP_NEEDED
+        if (yypushed_loc)
+            yylloc = *yypushed_loc;
+#endif
+    }
+";
+
+const PUSH_DRIVER_RETURN: &str = r#"yyreturn:
+    /* The stacks stay with yyps for its next parse. */
+    yyps->yynew = 1;
+
+yypushreturn:
+    return yyresult;
+}
+
+#undef yystate
+#undef yyerrstatus
+#undef yychar
+#undef yynerrs
+#undef yylval
+#undef yystacksize
+#undef yyss
+#undef yyssp
+#undef yyvs
+#undef yyvsp
+#if YYLSP_NEEDED
+# undef yylloc
+# undef yyls
+# undef yylsp
+#endif
+"#;
+
+/// The variables YYLEX fills in, when a pure parser keeps them local.
+const PULL_LOCALS: &str = r#"#if YYPURE
+    YYSTYPE yylval;
+# if YYLSP_NEEDED
+    YYLTYPE yylloc = { 1, 1, 1, 1 };
+# endif
+    memset(&yylval, 0, sizeof yylval);
+#endif
+"#;
+
+const PULL_LOOP: &str = r#"    do
+    {
+        int yytok = YYLEX;
+        yystatus = yypush_parse(ARGS);
+    }
+    while (yystatus == YYPUSH_MORE);
+"#;
+
+const PUSH_MAIN: &str = r#"
+int main(void)
+{
+    yypstate *yyps = yypstate_new();
+    int yystatus;
+LOCALS    if (!yyps)
+        return 2;
+LOOP    yypstate_delete(yyps);
+    printf("yypush_parse() returned %d (%s)\n", yystatus, yystatus == 0 ? "accepted" : "rejected");
+    return yystatus;
+}
+"#;
+
+const DEFAULT_MAIN: &str = r#"
+int main(void)
+{
+    int yystatus = yyparse();
+    printf("yyparse() returned %d (%s)\n", yystatus, yystatus == 0 ? "accepted" : "rejected");
+    return yystatus;
+}
+"#;
@@ -0,0 +1,152 @@
+//! Recursive-descent parsers for LL(1) grammars, built with gcc and rustc,
+//! and the error for grammars that have none.
+
+use std::path::PathBuf;
+use std::process::Command;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use engine::{generate_descent_code, parse_bison, CodegenOptions, Target};
+
+static BUILD_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+fn build_dir() -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("structura-descent-{}-{}", std::process::id(), BUILD_COUNTER.fetch_add(1, Ordering::Relaxed)));
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// Generates the C parser for `spec`, compiles it and returns what it prints.
+fn run_c_parser(spec: &str, input: &str) -> String {
+    let code = generate_descent_code(&parse_bison(spec), Target::C, &CodegenOptions::default());
+    assert!(code.contains("yyparse_"), "no parser generated:\n{}", code);
+    let dir = build_dir();
+    std::fs::write(dir.join("y.tab.c"), &code).unwrap();
+    let compile = Command::new("gcc").current_dir(&dir).args(["-Wall", "-Werror", "y.tab.c", "-o", "parser"]).output().expect("gcc must be installed");
+    assert!(compile.status.success(), "gcc failed:\n{}", String::from_utf8_lossy(&compile.stderr));
+    let mut child = Command::new(dir.join("parser"))
+        .stdin(std::process::Stdio::piped())
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .spawn()
+        .unwrap();
+    use std::io::Write;
+    child.stdin.take().unwrap().write_all(input.as_bytes()).unwrap();
+    let output = child.wait_with_output().unwrap();
+    let _ = std::fs::remove_dir_all(&dir);
+    String::from_utf8_lossy(&output.stdout).into_owned()
+}
+
+/// Generates the Rust parser for `spec`, compiles it beside `main` and
+/// returns what the program prints.
+fn run_rust_parser(spec: &str, main: &str) -> String {
+    let code = generate_descent_code(&parse_bison(spec), Target::Rust, &CodegenOptions::default());
+    assert!(code.contains("fn parse_"), "no parser generated:\n{}", code);
+    let dir = build_dir();
+    std::fs::write(dir.join("parser.rs"), &code).unwrap();
+    std::fs::write(dir.join("main.rs"), main).unwrap();
+    let compile = Command::new("rustc").current_dir(&dir).args(["--edition", "2021", "-D", "warnings", "main.rs", "-o", "program"]).output().expect("rustc must be installed");
+    assert!(compile.status.success(), "rustc failed:\n{}", String::from_utf8_lossy(&compile.stderr));
+    let output = Command::new(dir.join("program")).output().unwrap();
+    let _ = std::fs::remove_dir_all(&dir);
+    String::from_utf8_lossy(&output.stdout).into_owned()
+}
+
+/// The textbook expression grammar with left recursion removed: `e2` and
+/// `t2` carry the rest of a sum or product.
+const EXPRESSIONS: &str = r#"
+%token NUM
+%%
+line: e { print_value($1); } ;
+e: t e2 { $$ = $1 + $2; } ;
+e2: '+' t e2 { $$ = $2 + $3; } | { $$ = 0; } ;
+t: f t2 { $$ = $1 * $2; } ;
+t2: '*' f t2 { $$ = $2 * $3; } | { $$ = 1; } ;
+f: NUM | '(' e ')' { $$ = $2; } ;
+"#;
+
+#[test]
+fn c_parser_evaluates_an_ll1_expression_grammar() {
+    let driver = r#"
+int yylex(void)
+{
+    int c = getchar();
+    while (c == ' ')
+        c = getchar();
+    if (c == EOF || c == '\n')
+        return 0;
+    if (c >= '0' && c <= '9') {
+        yylval = c - '0';
+        return NUM;
+    }
+    return c;
+}
+
+void yyerror(const char *msg) { printf("%s\n", msg); }
+
+int main(void)
+{
+    int status = yyparse();
+    printf("=> %d\n", status);
+    return 0;
+}
+"#;
+    let spec = format!(
+        "%{{\n#include <stdio.h>\nint yylex(void);\nvoid yyerror(const char *msg);\n#define print_value(v) printf(\"%d\\n\", v)\n%}}\n{}%%\n{}",
+        EXPRESSIONS, driver
+    );
+    assert_eq!(run_c_parser(&spec, "1 + 2 * 3"), "7\n=> 0\n");
+    assert_eq!(run_c_parser(&spec, "(1 + 2) * 3"), "9\n=> 0\n");
+    assert_eq!(run_c_parser(&spec, "2 * (3 + 4) + 1"), "15\n=> 0\n");
+    assert_eq!(run_c_parser(&spec, "1 + * 2"), "syntax error\n=> 1\n");
+    assert_eq!(run_c_parser(&spec, "(1 + 2"), "syntax error\n=> 1\n");
+}
+
+#[test]
+fn rust_parser_evaluates_an_ll1_expression_grammar() {
+    let spec = EXPRESSIONS.replace("print_value($1);", "println!(\"{}\", $1);").replace("%token NUM", "%union { n: i64 }\n%token <n> NUM\n%type <n> e e2 t t2 f");
+    let main = r#"
+mod parser;
+
+/// Digits are NUM, blanks are skipped, anything else is a `Char`.
+struct Chars(Vec<char>, usize);
+
+impl parser::Lexer for Chars {
+    fn yylex(&mut self) -> parser::Token {
+        loop {
+            let Some(&c) = self.0.get(self.1) else { return parser::Token::Eof };
+            self.1 += 1;
+            match c {
+                ' ' => continue,
+                '0'..='9' => return parser::Token::NUM(c as i64 - '0' as i64),
+                _ => return parser::Token::Char(c),
+            }
+        }
+    }
+
+    fn yyerror(&mut self, message: &str) {
+        println!("{}", message);
+    }
+}
+
+fn main() {
+    for line in ["1 + 2 * 3", "(1 + 2) * 3", "1 + * 2"] {
+        let mut parser = parser::Parser::new(Chars(line.chars().collect(), 0));
+        println!("=> {:?}", parser.parse().is_ok());
+    }
+}
+"#;
+    assert_eq!(run_rust_parser(&spec, main), "7\n=> true\n9\n=> true\nsyntax error\n=> false\n");
+}
+
+#[test]
+fn left_recursive_grammar_is_not_ll1() {
+    let spec = "%token NUM\n%%\ne: e '+' t | t ;\nt: NUM ;\n";
+    let bison = parse_bison(spec);
+    let err = engine::descent::generate_c_descent(&bison, &CodegenOptions::default()).unwrap_err();
+    assert!(err.starts_with("Grammar is not LL(1): "), "{}", err);
+    assert!(err.contains("'e' is left-recursive"), "{}", err);
+    assert_eq!(engine::descent::generate_rust_descent(&bison).unwrap_err(), err);
+
+    let code = generate_descent_code(&bison, Target::C, &CodegenOptions::default());
+    assert!(code.starts_with("/* Cannot generate parser: Grammar is not LL(1)"), "{}", code);
+}
@@ -0,0 +1,228 @@
+//! Generated LALR(1) parsers, built with gcc and driven by a yylex in the
+//! grammar's epilogue: conflict resolution, error recovery and semantic values.
+
+use std::path::PathBuf;
+use std::process::Command;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+static BUILD_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// Generates the parser for `spec`, compiles it and returns what it prints.
+fn run_parser(spec: &str, input: &str) -> String {
+    let code = engine::generate_c_code(&engine::parse_bison(spec));
+    assert!(code.contains("yyparse"), "no parser generated:\n{}", code);
+    let dir: PathBuf = std::env::temp_dir().join(format!("structura-lalr-{}-{}", std::process::id(), BUILD_COUNTER.fetch_add(1, Ordering::Relaxed)));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("y.tab.c"), &code).unwrap();
+    let compile = Command::new("gcc").current_dir(&dir).args(["-Wall", "-Werror", "y.tab.c", "-o", "parser"]).output().expect("gcc must be installed");
+    assert!(compile.status.success(), "gcc failed:\n{}", String::from_utf8_lossy(&compile.stderr));
+    let mut child = Command::new(dir.join("parser"))
+        .stdin(std::process::Stdio::piped())
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .spawn()
+        .unwrap();
+    use std::io::Write;
+    child.stdin.take().unwrap().write_all(input.as_bytes()).unwrap();
+    let output = child.wait_with_output().unwrap();
+    let _ = std::fs::remove_dir_all(&dir);
+    String::from_utf8_lossy(&output.stdout).into_owned()
+}
+
+/// Digits are NUM with their value, blanks are skipped and anything else is
+/// its own character token; yyparse's result is printed after the parse.
+const DRIVER: &str = r#"
+int yylex(void)
+{
+    int c = getchar();
+    while (c == ' ')
+        c = getchar();
+    if (c == EOF)
+        return 0;
+    if (c >= '0' && c <= '9') {
+        yylval.n = c - '0';
+        return NUM;
+    }
+    return c;
+}
+
+void yyerror(const char *msg) { printf("%s\n", msg); }
+
+int main(void)
+{
+    int status = yyparse();
+    printf("=> %d\n", status);
+    return 0;
+}
+"#;
+
+fn with_driver(grammar: &str) -> String {
+    format!("%{{\n#include <stdio.h>\nint yylex(void);\nvoid yyerror(const char *msg);\n%}}\n{}\n%%\n{}", grammar, DRIVER)
+}
+
+#[test]
+fn precedence_and_associativity_resolve_the_expression_grammar() {
+    let spec = with_driver(
+        r#"
+%union { int n; }
+%token <n> NUM
+%type <n> e
+%nonassoc '<'
+%left '+' '-'
+%left '*'
+%right '^'
+%precedence NEG
+%%
+top: e { printf("%d\n", $1); } ;
+e: e '<' e { $$ = $1 < $3; }
+ | e '+' e { $$ = $1 + $3; }
+ | e '-' e { $$ = $1 - $3; }
+ | e '*' e { $$ = $1 * $3; }
+ | e '^' e { int i; $$ = 1; for (i = 0; i < $3; i++) $$ *= $1; }
+ | '-' e %prec NEG { $$ = -$2; }
+ | NUM
+ ;
+"#,
+    );
+    assert_eq!(run_parser(&spec, "1 + 2 * 3"), "7\n=> 0\n");
+    assert_eq!(run_parser(&spec, "9 - 3 - 2"), "4\n=> 0\n");
+    assert_eq!(run_parser(&spec, "2 ^ 3 ^ 2"), "512\n=> 0\n");
+    assert_eq!(run_parser(&spec, "-2 ^ 2"), "4\n=> 0\n");
+    assert_eq!(run_parser(&spec, "1 + 1 < 3"), "1\n=> 0\n");
+    // %nonassoc makes `a < b < c` a syntax error rather than picking a side.
+    assert_eq!(run_parser(&spec, "1 < 2 < 3"), "syntax error\n=> 1\n");
+}
+
+#[test]
+fn dangling_else_binds_to_the_nearest_if() {
+    let spec = with_driver(
+        r#"
+%union { int n; }
+%token <n> NUM
+%expect 1
+%%
+s: 'i' s { printf("if "); }
+ | 'i' s 'e' s { printf("if-else "); }
+ | NUM { printf("%d ", $1); }
+ ;
+"#,
+    );
+    assert_eq!(run_parser(&spec, "i i 1 e 2"), "1 2 if-else if => 0\n");
+    assert_eq!(run_parser(&spec, "i i 1 e 2 e 3"), "1 2 if-else 3 if-else => 0\n");
+}
+
+#[test]
+fn error_token_recovers_and_yyerrok_resumes_reporting() {
+    let spec = with_driver(
+        r#"
+%union { int n; }
+%token <n> NUM
+%type <n> e
+%left '+'
+%%
+lines: lines line | ;
+line: e '\n' { printf("%d\n", $1); }
+    | error '\n' { yyerrok; printf("recovered\n"); }
+    ;
+e: e '+' e { $$ = $1 + $3; } | NUM ;
+"#,
+    );
+    assert_eq!(run_parser(&spec, "1 + 2\n1 + + 2\n3\n"), "3\nsyntax error\nrecovered\n3\n=> 0\n");
+    // Thanks to yyerrok, the second error is reported straight away.
+    assert_eq!(run_parser(&spec, "+\n+\n4\n"), "syntax error\nrecovered\nsyntax error\nrecovered\n4\n=> 0\n");
+}
+
+#[test]
+fn yyaccept_and_yyabort_end_the_parse_early() {
+    let spec = with_driver(
+        r#"
+%union { int n; }
+%token <n> NUM
+%%
+items: items item | ;
+item: NUM { printf("%d ", $1); }
+    | 'q' { YYACCEPT; }
+    | 'x' { YYABORT; }
+    ;
+"#,
+    );
+    assert_eq!(run_parser(&spec, "1 2"), "1 2 => 0\n");
+    assert_eq!(run_parser(&spec, "1 q 2"), "1 => 0\n");
+    assert_eq!(run_parser(&spec, "1 x 2"), "1 => 1\n");
+}
+
+#[test]
+fn typed_semantic_values_flow_through_dollar_signs() {
+    let spec = r#"
+%{
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+int yylex(void);
+void yyerror(const char *msg);
+%}
+%union { int n; char *s; }
+%token <n> NUM
+%token <s> NAME
+%type <s> binding bindings
+%type <n> sum
+%%
+top: bindings { printf("%s\n", $1); free($1); } ;
+bindings: binding
+        | bindings ',' binding {
+              $$ = malloc(strlen($1) + strlen($3) + 3);
+              sprintf($$, "%s; %s", $1, $3);
+              free($1);
+              free($3);
+          }
+        ;
+binding: NAME '=' sum {
+             $$ = malloc(strlen($1) + 16);
+             sprintf($$, "%s:%d", $1, $3);
+             free($1);
+         }
+       ;
+sum: NUM | sum '+' NUM { $$ = $1 + $3; } ;
+%%
+int yylex(void)
+{
+    int c = getchar();
+    while (c == ' ')
+        c = getchar();
+    if (c == EOF)
+        return 0;
+    if (c >= '0' && c <= '9') {
+        yylval.n = c - '0';
+        return NUM;
+    }
+    if (c >= 'a' && c <= 'z') {
+        char word[32];
+        int len = 0;
+        while (c >= 'a' && c <= 'z' && len < 31) {
+            word[len++] = (char) c;
+            c = getchar();
+        }
+        ungetc(c, stdin);
+        word[len] = '\0';
+        yylval.s = strdup(word);
+        return NAME;
+    }
+    return c;
+}
+
+void yyerror(const char *msg) { printf("%s\n", msg); }
+
+int main(void)
+{
+    return yyparse();
+}
+"#;
+    assert_eq!(run_parser(spec, "ab = 1 + 2, c = 4, def = 5 + 5 + 5"), "ab:3; c:4; def:15\n");
+}
+
+#[test]
+fn prec_with_an_undeclared_symbol_is_an_error() {
+    let spec = "%token NUM\n%left '+'\n%%\ne: e '+' e | '-' e %prec NEG | NUM ;\n";
+    let err = engine::lalr::build_lalr(&engine::parse_bison(spec)).unwrap_err();
+    assert_eq!(err, "%prec NEG in rule 'e -> '-' e' names a symbol with no declared precedence");
+}
@@ -0,0 +1,135 @@
+//! Push parsers (`%define api.push-pull`), built with gcc and fed tokens one
+//! at a time by a C driver in the grammar's epilogue.
+
+use std::path::PathBuf;
+use std::process::Command;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+static BUILD_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// Generates the parser for `spec`, compiles it and returns what it prints.
+fn run_parser(spec: &str, input: &str) -> String {
+    let code = engine::generate_c_code(&engine::parse_bison(spec));
+    assert!(code.contains("yypush_parse"), "no push parser generated:\n{}", code);
+    let dir: PathBuf = std::env::temp_dir().join(format!("structura-push-{}-{}", std::process::id(), BUILD_COUNTER.fetch_add(1, Ordering::Relaxed)));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("y.tab.c"), &code).unwrap();
+    let compile = Command::new("gcc").current_dir(&dir).args(["-Wall", "-Werror", "y.tab.c", "-o", "parser"]).output().expect("gcc must be installed");
+    assert!(compile.status.success(), "gcc failed:\n{}", String::from_utf8_lossy(&compile.stderr));
+    let mut child = Command::new(dir.join("parser"))
+        .stdin(std::process::Stdio::piped())
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .spawn()
+        .unwrap();
+    use std::io::Write;
+    child.stdin.take().unwrap().write_all(input.as_bytes()).unwrap();
+    let output = child.wait_with_output().unwrap();
+    let _ = std::fs::remove_dir_all(&dir);
+    String::from_utf8_lossy(&output.stdout).into_owned()
+}
+
+const CALC: &str = r#"
+%{
+#include <stdio.h>
+%}
+%define api.pure full
+%define api.push-pull push
+%parse-param {int *result}
+%union { int n; }
+%token <n> NUM
+%type <n> e
+%left '+'
+%left '*'
+%%
+top: e { *result = $1; } | error { *result = -1; } ;
+e: e '+' e { $$ = $1 + $3; } | e '*' e { $$ = $1 * $3; } | NUM | '(' e ')' { $$ = $2; } ;
+%%
+void yyerror(int *result, const char *msg) { (void) result; printf("%s; ", msg); }
+
+static int push(yypstate *ps, int *result, int tok, int n)
+{
+    YYSTYPE v;
+    v.n = n;
+    return yypush_parse(ps, tok, &v, result);
+}
+"#;
+
+#[test]
+fn push_parse_asks_for_more_until_the_end_token() {
+    let spec = format!(
+        "{}{}",
+        CALC,
+        r#"
+int main(void)
+{
+    int result = 0;
+    yypstate *ps = yypstate_new();
+    printf("%d ", push(ps, &result, NUM, 2));
+    printf("%d ", push(ps, &result, '+', 0));
+    printf("%d ", push(ps, &result, NUM, 3));
+    printf("%d ", push(ps, &result, '*', 0));
+    printf("%d ", push(ps, &result, NUM, 4));
+    printf("%d ", push(ps, &result, 0, 0));
+    printf("= %d\n", result);
+    yypstate_delete(ps);
+    return 0;
+}
+"#
+    );
+    assert_eq!(run_parser(&spec, ""), "4 4 4 4 4 0 = 14\n");
+}
+
+#[test]
+fn pure_push_parsers_interleave_and_restart() {
+    let spec = format!(
+        "{}{}",
+        CALC,
+        r#"
+int main(void)
+{
+    int a = 0, b = 0, i, status;
+    yypstate *pa = yypstate_new(), *pb = yypstate_new();
+    push(pa, &a, NUM, 2);
+    push(pb, &b, NUM, 5);
+    push(pa, &a, '+', 0);
+    push(pb, &b, '*', 0);
+    push(pa, &a, NUM, 3);
+    push(pb, &b, NUM, 6);
+    printf("%d ", push(pa, &a, 0, 0));
+    printf("%d ", push(pb, &b, 0, 0));
+    printf("%d %d\n", a, b);
+    /* A finished yypstate starts over; deep nesting grows its stacks. */
+    for (i = 0; i < 500; i++)
+        push(pa, &a, '(', 0);
+    push(pa, &a, NUM, 7);
+    for (i = 0; i < 500; i++)
+        push(pa, &a, ')', 0);
+    status = push(pa, &a, 0, 0);
+    printf("%d %d\n", status, a);
+    /* Error recovery through the error token. */
+    push(pb, &b, NUM, 1);
+    push(pb, &b, '+', 0);
+    push(pb, &b, '+', 0);
+    status = push(pb, &b, 0, 0);
+    printf("%d %d\n", status, b);
+    yypstate_delete(pa);
+    yypstate_delete(pb);
+    return 0;
+}
+"#
+    );
+    assert_eq!(run_parser(&spec, ""), "0 0 5 30\n0 7\nsyntax error; 0 -1\n");
+}
+
+#[test]
+fn both_keeps_yyparse_on_top_of_yypush_parse() {
+    let spec = r#"
+%define api.push-pull both
+%token A B
+%%
+s: A s B | ;
+"#;
+    assert_eq!(run_parser(spec, "A A B B"), "yyparse() returned 0 (accepted)\n");
+    assert_eq!(run_parser(spec, "A B B"), "yyparse() returned 1 (rejected)\n");
+}
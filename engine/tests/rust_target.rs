@@ -0,0 +1,126 @@
+//! Generated Rust scanner and parser modules, built with rustc next to a
+//! small `main.rs` driver and run.
+
+use std::path::PathBuf;
+use std::process::Command;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+static BUILD_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// Writes `modules` beside `main`, compiles them and returns what the
+/// program prints.
+fn run_program(modules: &[(&str, String)], main: &str) -> String {
+    for (name, code) in modules {
+        assert!(!code.starts_with("// Cannot"), "no {} generated:\n{}", name, code);
+    }
+    let dir: PathBuf = std::env::temp_dir().join(format!("structura-rust-{}-{}", std::process::id(), BUILD_COUNTER.fetch_add(1, Ordering::Relaxed)));
+    std::fs::create_dir_all(&dir).unwrap();
+    for (name, code) in modules {
+        std::fs::write(dir.join(format!("{}.rs", name)), code).unwrap();
+    }
+    std::fs::write(dir.join("main.rs"), main).unwrap();
+    let compile = Command::new("rustc").current_dir(&dir).args(["--edition", "2021", "-D", "warnings", "main.rs", "-o", "program"]).output().expect("rustc must be installed");
+    assert!(compile.status.success(), "rustc failed:\n{}", String::from_utf8_lossy(&compile.stderr));
+    let output = Command::new(dir.join("program")).output().unwrap();
+    let _ = std::fs::remove_dir_all(&dir);
+    String::from_utf8_lossy(&output.stdout).into_owned()
+}
+
+#[test]
+fn scanner_returns_tokens_by_longest_match() {
+    let spec = r#"
+%%
+if              { return Token::IF; }
+[a-z]+/"("      { return Token::CALL; }
+[a-z]+          { return Token::ID; }
+[0-9]+          { return Token::NUM; }
+[ \t\n]+        ;
+.               { return Token::Char(yytext.chars().next().unwrap()); }
+"#;
+    let scanner = engine::generate_rust_code(&engine::parse_flex(spec));
+    let main = r#"
+mod scanner;
+
+fn main() {
+    let tokens: Vec<scanner::Token> = scanner::Scanner::new("if ifs + 42 f(x)").collect();
+    println!("{:?}", tokens);
+}
+"#;
+    let out = run_program(&[("scanner", scanner)], main);
+    assert_eq!(out.trim(), "[IF, ID, Char('+'), NUM, CALL, Char('('), ID, Char(')')]");
+}
+
+#[test]
+fn scanner_actions_see_yytext_and_start_conditions() {
+    let spec = r#"
+%x STR
+%%
+\"              { self.begin(STR); }
+<STR>[^"]*      { println!("string {:?}", yytext); }
+<STR>\"         { self.begin(INITIAL); }
+[a-z]+          { println!("word {} ({} bytes)", yytext, yyleng); }
+.|\n            ;
+"#;
+    let scanner = engine::generate_rust_code(&engine::parse_flex(spec));
+    let main = r#"
+mod scanner;
+
+fn main() {
+    let mut lexer = scanner::Scanner::new("say \"hi there\" twice");
+    while lexer.yylex() != scanner::Token::Eof {}
+}
+"#;
+    let out = run_program(&[("scanner", scanner)], main);
+    assert_eq!(out, "word say (3 bytes)\nstring \"hi there\"\nword twice (5 bytes)\n");
+}
+
+const CALC_SCANNER: &str = r#"
+%{
+use crate::parser::Token;
+%}
+%%
+[0-9]+          { return Token::NUM(yytext.parse().unwrap()); }
+[ \t]+          ;
+.               { return Token::Char(yytext.chars().next().unwrap()); }
+"#;
+
+const CALC_PARSER: &str = r#"
+%union { n: i64 }
+%token <n> NUM
+%type <n> top e
+%left '+' '-'
+%left '*'
+%%
+top: e ;
+e: e '+' e { $$ = $1 + $3; }
+ | e '-' e { $$ = $1 - $3; }
+ | e '*' e { $$ = $1 * $3; }
+ | NUM
+ | '(' e ')' { $$ = $2; }
+ ;
+"#;
+
+#[test]
+fn parser_evaluates_expressions_from_the_generated_scanner() {
+    let scanner = engine::generate_rust_code(&engine::parse_flex(CALC_SCANNER));
+    let parser = engine::generate_rust_code(&engine::parse_bison(CALC_PARSER));
+    let main = r#"
+mod parser;
+mod scanner;
+
+impl parser::Lexer for scanner::Scanner {
+    fn yylex(&mut self) -> parser::Token {
+        scanner::Scanner::yylex(self)
+    }
+}
+
+fn main() {
+    for line in ["1 + 2 * 3", "(1 + 2) * 3", "10 - 4 - 3", "1 +"] {
+        let mut parser = parser::Parser::new(scanner::Scanner::new(line));
+        println!("{:?}", parser.parse());
+    }
+}
+"#;
+    let out = run_program(&[("scanner", scanner), ("parser", parser)], main);
+    assert_eq!(out, "Ok(Some(7))\nOk(Some(9))\nOk(Some(3))\nErr(Aborted)\n");
+}
@@ -0,0 +1,139 @@
+//! Generated C scanners, built with gcc and run over sample input: the
+//! matching rules and the yytext-editing macros flex programs rely on.
+
+use std::path::PathBuf;
+use std::process::Command;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+static BUILD_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// Generates the scanner for `spec`, compiles it and returns what it prints.
+fn run_scanner(spec: &str, input: &str) -> String {
+    let code = engine::generate_c_code(&engine::parse_flex(spec));
+    assert!(code.contains("yylex"), "no scanner generated:\n{}", code);
+    let dir: PathBuf = std::env::temp_dir().join(format!("structura-scanner-{}-{}", std::process::id(), BUILD_COUNTER.fetch_add(1, Ordering::Relaxed)));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("lex.yy.c"), &code).unwrap();
+    let compile = Command::new("gcc").current_dir(&dir).args(["-Wall", "-Werror", "lex.yy.c", "-o", "scanner"]).output().expect("gcc must be installed");
+    assert!(compile.status.success(), "gcc failed:\n{}", String::from_utf8_lossy(&compile.stderr));
+    let mut child = Command::new(dir.join("scanner"))
+        .stdin(std::process::Stdio::piped())
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .spawn()
+        .unwrap();
+    use std::io::Write;
+    child.stdin.take().unwrap().write_all(input.as_bytes()).unwrap();
+    let output = child.wait_with_output().unwrap();
+    let _ = std::fs::remove_dir_all(&dir);
+    String::from_utf8_lossy(&output.stdout).into_owned()
+}
+
+#[test]
+fn longest_match_wins_and_ties_go_to_the_earlier_rule() {
+    let spec = r#"
+%%
+if          { printf("IF "); }
+[a-z]+      { printf("ID(%s) ", yytext); }
+"=="        { printf("EQ "); }
+"="         { printf("ASSIGN "); }
+[0-9]+      { printf("NUM(%s) ", yytext); }
+[ \n]       ;
+"#;
+    assert_eq!(run_scanner(spec, "if iffy = 42 == x\n"), "IF ID(iffy) ASSIGN NUM(42) EQ ID(x) ");
+}
+
+#[test]
+fn trailing_context_is_matched_but_not_consumed() {
+    let spec = r#"
+%%
+[a-z]+/"("  { printf("CALL(%s) ", yytext); }
+ab/c*d      { printf("AB(%s) ", yytext); }
+[a-z]+      { printf("ID(%s) ", yytext); }
+.|\n        { printf("[%s]", yytext); }
+"#;
+    assert_eq!(run_scanner(spec, "f(x)"), "CALL(f) [(]ID(x) [)]");
+    // The context counts towards the match length: "abccd" ties with [a-z]+.
+    assert_eq!(run_scanner(spec, "abccd"), "AB(ab) ID(ccd) ");
+}
+
+#[test]
+fn yymore_yyless_and_unput_edit_the_input() {
+    let spec = r#"
+%%
+"<"         { yymore(); }
+[a-z]+">"   { printf("TAG(%s) ", yytext); }
+[0-9]+      { if (yyleng > 2) { yyless(2); } printf("NUM(%s) ", yytext); }
+"@"         { unput('x'); unput('y'); }
+[A-Z]+      { printf("UP(%s) ", yytext); }
+.|\n        { printf("[%s]", yytext); }
+"#;
+    assert_eq!(run_scanner(spec, "<ab>12345"), "TAG(<ab>) NUM(12) NUM(34) NUM(5) ");
+    // The last character pushed back is read first.
+    assert_eq!(run_scanner(spec, "A@B"), "UP(A) [y][x]UP(B) ");
+}
+
+#[test]
+fn exclusive_start_conditions_only_run_their_own_rules() {
+    let spec = r#"
+%x COMMENT
+%s QUOTED
+%%
+"/*"            { BEGIN(COMMENT); printf("<c "); }
+<COMMENT>"*/"   { BEGIN(INITIAL); printf("c> "); }
+<COMMENT>.|\n   ;
+\"              { BEGIN(QUOTED); }
+<QUOTED>\"      { BEGIN(INITIAL); }
+<QUOTED>[a-z]+  { printf("STR(%s) ", yytext); }
+[a-z]+          { printf("ID(%s) ", yytext); }
+.|\n            ;
+"#;
+    assert_eq!(run_scanner(spec, "a /* b \" c */ d"), "ID(a) <c c> ID(d) ");
+    // An inclusive condition also keeps the unconditioned rules; "*/" then
+    // returns to INITIAL, so the closing quote opens a new string.
+    assert_eq!(run_scanner(spec, "\"x /* y */ w\" z"), "STR(x) <c c> ID(w) STR(z) ");
+}
+
+#[test]
+fn eof_rules_run_once_per_condition() {
+    let spec = r#"
+%x STR
+%%
+\"              { BEGIN(STR); }
+<STR>[^"]+      { printf("STR(%s) ", yytext); }
+<STR>\"         { BEGIN(INITIAL); }
+<STR><<EOF>>    { printf("unterminated string\n"); return 0; }
+<<EOF>>         { printf("done\n"); yyterminate(); }
+[a-z]+          { printf("ID(%s) ", yytext); }
+.|\n            ;
+"#;
+    assert_eq!(run_scanner(spec, "a \"b\" c"), "ID(a) STR(b) ID(c) done\n");
+    assert_eq!(run_scanner(spec, "a \"b c"), "ID(a) STR(b c) unterminated string\n");
+    assert_eq!(run_scanner(spec, ""), "done\n");
+}
+
+#[test]
+fn yylloc_tracks_lines_and_columns() {
+    let spec = r#"
+%{
+typedef struct { int first_line, first_column, last_line, last_column; } YYLTYPE;
+YYLTYPE yylloc;
+#define YYLLOC_IS_GLOBAL 1
+#define LOC(what) printf("%s %d:%d-%d:%d\n", what, yylloc.first_line, yylloc.first_column, yylloc.last_line, yylloc.last_column)
+%}
+%option yylineno
+%%
+"/*"[^*]*"*/"   { LOC("comment"); }
+"ab!"           { yyless(1); LOC("less"); }
+"@"             { unput('z'); }
+[a-z]+          { LOC(yytext); }
+[ \t\n]+        ;
+.               ;
+"#;
+    assert_eq!(
+        run_scanner(spec, "foo bar\n  baz /* x\ny */ qux\nab!c"),
+        "foo 1:1-1:3\nbar 1:5-1:7\nbaz 2:3-2:5\ncomment 2:7-3:4\nqux 3:6-3:8\nless 4:1-4:3\nb 4:2-4:2\nc 4:4-4:4\n"
+    );
+    // Pushed-back text is located where it now sits.
+    assert_eq!(run_scanner(spec, "xy @w\nv"), "xy 1:1-1:2\nzw 1:4-1:5\nv 2:1-2:1\n");
+}
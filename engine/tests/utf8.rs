@@ -0,0 +1,92 @@
+//! UTF-8 byte automata: the byte-range sequences for char ranges and the
+//! byte-level DFA the C scanner runs on.
+
+use engine::automaton::{compile_flex, Dfa};
+use engine::utf8::{byte_dfa, utf8_sequences};
+
+/// Whether `bytes` is spelled by one of `seqs`.
+fn spelled_by(seqs: &[Vec<(u8, u8)>], bytes: &[u8]) -> bool {
+    seqs.iter().any(|seq| seq.len() == bytes.len() && seq.iter().zip(bytes).all(|(&(lo, hi), b)| (lo..=hi).contains(b)))
+}
+
+/// Every scalar value in `lo..=hi` is spelled by exactly one sequence, and
+/// its neighbours outside the range by none.
+fn assert_exact(lo: char, hi: char) {
+    let seqs = utf8_sequences(lo, hi);
+    let mut buf = [0u8; 4];
+    for c in lo..=hi {
+        let bytes = c.encode_utf8(&mut buf).as_bytes();
+        let count = seqs.iter().filter(|seq| spelled_by(std::slice::from_ref(seq), bytes)).count();
+        assert_eq!(count, 1, "U+{:04X} is spelled {} times by {:x?}", c as u32, count, seqs);
+    }
+    for c in [char::from_u32(lo as u32 - 1), char::from_u32(hi as u32 + 1)].into_iter().flatten() {
+        assert!(!spelled_by(&seqs, c.encode_utf8(&mut buf).as_bytes()), "U+{:04X} is outside {:x?}", c as u32, seqs);
+    }
+}
+
+#[test]
+fn ranges_split_where_the_encoded_length_changes() {
+    assert_eq!(utf8_sequences('\u{7F}', '\u{80}'), vec![vec![(0x7F, 0x7F)], vec![(0xC2, 0xC2), (0x80, 0x80)]]);
+    assert_eq!(
+        utf8_sequences('\u{7FF}', '\u{800}'),
+        vec![vec![(0xDF, 0xDF), (0xBF, 0xBF)], vec![(0xE0, 0xE0), (0xA0, 0xA0), (0x80, 0x80)]]
+    );
+    assert_eq!(
+        utf8_sequences('\u{FFFF}', '\u{10000}'),
+        vec![vec![(0xEF, 0xEF), (0xBF, 0xBF), (0xBF, 0xBF)], vec![(0xF0, 0xF0), (0x90, 0x90), (0x80, 0x80), (0x80, 0x80)]]
+    );
+    assert_exact('\u{70}', '\u{900}');
+    assert_exact('\u{7FF}', '\u{1040}');
+    assert_exact('\u{FF00}', '\u{10100}');
+    assert_exact('\u{10FF00}', '\u{10FFFF}');
+}
+
+#[test]
+fn surrogates_are_never_spelled() {
+    assert_eq!(
+        utf8_sequences('\u{D7FF}', '\u{E000}'),
+        vec![vec![(0xED, 0xED), (0x9F, 0x9F), (0xBF, 0xBF)], vec![(0xEE, 0xEE), (0x80, 0x80), (0x80, 0x80)]]
+    );
+    assert_exact('\u{D000}', '\u{EFFF}');
+    // CESU-style encodings of U+D800 and U+DFFF.
+    let seqs = utf8_sequences('\0', char::MAX);
+    assert!(!spelled_by(&seqs, &[0xED, 0xA0, 0x80]));
+    assert!(!spelled_by(&seqs, &[0xED, 0xBF, 0xBF]));
+    assert!(spelled_by(&seqs, &[0xF4, 0x8F, 0xBF, 0xBF]));
+    assert!(!spelled_by(&seqs, &[0xF4, 0x90, 0x80, 0x80]));
+}
+
+/// Longest match of `bytes` from the byte DFA's INITIAL start state.
+fn byte_match(dfa: &Dfa, bytes: &[u8]) -> Option<(usize, usize)> {
+    let input: Vec<char> = bytes.iter().map(|&b| b as char).collect();
+    dfa.longest_match(dfa.start("INITIAL", false)?, &input)
+}
+
+#[test]
+fn property_classes_survive_the_byte_dfa() {
+    let spec = "%%\n\\p{L}+ { return 1; }\n[0-9]+ { return 2; }\n.|\\n { return 3; }\n";
+    let automaton = compile_flex(&engine::parse_flex(spec)).unwrap();
+    let bytes = byte_dfa(&automaton).unwrap();
+    let chars = &automaton.dfa;
+    let char_match = |s: &str| chars.longest_match(chars.start("INITIAL", false).unwrap(), &s.chars().collect::<Vec<_>>());
+    let letter = char_match("a").unwrap().1;
+    let other = char_match("!").unwrap().1;
+
+    // Lengths are in bytes: two-, three- and four-byte letters stay one lexeme.
+    assert_eq!(byte_match(&bytes, "héllo wörld".as_bytes()), Some((6, letter)));
+    assert_eq!(byte_match(&bytes, "Ωμέγα!".as_bytes()), Some((10, letter)));
+    assert_eq!(byte_match(&bytes, "漢字1".as_bytes()), Some((6, letter)));
+    assert_eq!(byte_match(&bytes, "\u{1D538}x".as_bytes()), Some((5, letter)));
+    // `.` takes a whole non-letter character, never part of one.
+    assert_eq!(byte_match(&bytes, "€a".as_bytes()), Some((3, other)));
+    // Malformed input matches nothing, so the default rule copies one byte.
+    assert_eq!(byte_match(&bytes, &[0xED, 0xA0, 0x80]), None);
+    assert_eq!(byte_match(&bytes, &[0xC3]), None);
+
+    // Each character's encoding gets the char DFA's verdict.
+    let mut buf = [0u8; 4];
+    for c in ('\0'..=char::MAX).step_by(97) {
+        let expected = char_match(&c.to_string()).map(|(_, rule)| (c.len_utf8(), rule));
+        assert_eq!(byte_match(&bytes, c.encode_utf8(&mut buf).as_bytes()), expected, "U+{:04X}", c as u32);
+    }
+}